@@ -1,9 +1,32 @@
 ---@diagnostic disable: lowercase-global
 local wifi = require "wifi"
 local tmr = require "tmr"
+local uart = require "uart"
+local node = require "node"
+local bit = require "bit"
+
+-- Version du script, envoyee dans les trames d'etat
+VERSION = "1.7"
 
 -- Canal initial
 CHANNEL = 1
+-- Canaux parcourus
+CHANNELS = {1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13}
+-- Position du canal courant dans CHANNELS
+INDEX = 1
+-- Temps passe sur chaque canal (ms)
+HOP_INTERVAL = 5000
+-- Temps passe sur chaque canal de CHANNELS (ms), HOP_INTERVAL si absent
+DWELLS = {}
+-- Parcours des canaux arrete par LOCK
+LOCKED = false
+-- Periode d'envoi de l'etat (ms)
+STATUS_INTERVAL = 10000
+-- Trames envoyees depuis le demarrage, sert de numero de trame
+FRAMES = 0
+-- Trames capturees : "probe" (appareils), "ap" (points d'acces) ou "all"
+MODE = "probe"
+PAUSED = false
 
 function init()
   wifi.setcountry({country="FR", start_ch=1, end_ch=13, policy=wifi.COUNTRY_AUTO})
@@ -11,39 +34,248 @@ function init()
   wifi.setmode(wifi.STATION)
 end
 
+-- CRC-16/CCITT-FALSE
+function crc16(data)
+  local crc = 0xFFFF
+  for i = 1, #data do
+    crc = bit.bxor(crc, bit.lshift(data:byte(i), 8))
+    for _ = 1, 8 do
+      if ( bit.band(crc, 0x8000) ~= 0 ) then
+        crc = bit.band(bit.bxor(bit.lshift(crc, 1), 0x1021), 0xFFFF)
+      else
+        crc = bit.band(bit.lshift(crc, 1), 0xFFFF)
+      end
+    end
+  end
+  return crc
+end
+
+-- Millisecondes depuis le demarrage
+-- node.uptime() retourne aussi le nombre de debordements sur les firmwares entiers
+function uptime_ms()
+  local us, wraps = node.uptime()
+  return math.floor(((wraps or 0) * 4294967296 + us) / 1000)
+end
+
+-- Envoie une trame au format version 2
+-- \2 : ASCII Debut texte
+-- \31 : ASCII Separation d'unite
+-- \3 : ASCII Fin de transmission
+-- \2V2\31nombre\31champ1\31...\31champN\31crc\31\3
+function emit(fields)
+  local body = "V2\31" .. #fields .. "\31" .. table.concat(fields, "\31") .. "\31"
+  print(string.format("\2%s%04X\31\3", body, crc16(body)))
+end
+
+-- Encode une chaine en hexadecimal
+function hex(data)
+  return (data:gsub(".", function(c) return string.format("%02X", c:byte()) end))
+end
+
+-- IEs envoyes pour l'empreinte de l'appareil
+-- 1 : Debits, 45 : HT, 50 : Debits etendus, 127 : Capacites etendues, 191 : VHT, 221 : Constructeur
+FINGERPRINT_IES = {1, 45, 50, 127, 191, 221}
+
+-- IEs selectionnes en hexadecimal : identifiant, longueur, contenu
+function ies(pkt)
+  local out = {}
+  for _, id in ipairs(FINGERPRINT_IES) do
+    local ie = pkt[id]
+    if ( ie ~= nil ) then
+      out[#out + 1] = hex(string.char(id, #ie) .. ie)
+    end
+  end
+  return table.concat(out)
+end
+
+function on_packet(pkt)
+  tmr.delay(10)
+  -- 4 : Probe request
+  -- L'appareil est identifie par l'adresse de l'emetteur (srcmac)
+  -- Le SSID est toujours envoye, vide s'il n'est pas broadcaste
+  -- Suivi du tick (ms) et du numero de trame pour la datation et la detection des pertes
+  if ( MODE ~= "ap" and pkt.subtype == 4 ) then
+    FRAMES = FRAMES + 1
+    emit({pkt.channel, pkt.srcmac_hex, pkt.rssi, pkt.ie_ssid, uptime_ms(), FRAMES,
+      pkt.dstmac_hex, pkt.bssid_hex, pkt.subtype, pkt.seq, ies(pkt)})
+  -- 5 : Probe response, 8 : Beacon
+  -- 48 : Element RSN, envoye en hexadecimal pour determiner la securite
+  elseif ( MODE ~= "probe" and (pkt.subtype == 5 or pkt.subtype == 8) ) then
+    FRAMES = FRAMES + 1
+    emit({"AP", pkt.channel, pkt.bssid_hex, pkt.rssi, pkt.ie_ssid, pkt.capability or 0,
+      hex(pkt[48] or ""), pkt.subtype, uptime_ms(), FRAMES})
+  end
+end
+
 function start_sniff()
   -- Activer le moniteur
   -- 13 : Premier bit de la trame
-  -- 0x40 : Filtrer que pour les probe requests
-  -- if #pkt : Recupere la longueur du SSID et si elle est nulle, ne pas l'inclure
-  -- \2 : ASCII Debut texte
-  -- \31 : ASCII Separation d'unite
-  -- \3 : ASCII Fin de transmission
-  wifi.monitor.start(13, 0x40, function(pkt)
-    if ( #pkt.ie_ssid == 0 ) then
-        send = string.format("\2%d\31%s\31%d\31\3", pkt.channel, pkt.dstmac_hex, pkt.rssi)
-    else
-        send = string.format("\2%d\31%s\31%d\31%s\31\3", pkt.channel, pkt.dstmac_hex, pkt.rssi, pkt.ie_ssid)
-    end
-    tmr.delay(10)
-    print(send)
-    end)
+  if ( MODE == "probe" ) then
+    -- 0x40 : Filtrer que pour les probe requests
+    wifi.monitor.start(13, 0x40, on_packet)
+  else
+    -- 0x00 avec le masque 0x0C : Toutes les trames de gestion
+    wifi.monitor.start(13, 0x00, 0x0C, on_packet)
+  end
+end
+
+function set_channel(channel)
+  CHANNEL = channel
+  wifi.monitor.channel(CHANNEL)
 end
 
 function sniff()
 -- Iterer entre les canaux
-  if ( CHANNEL < 13 ) then
-    CHANNEL = CHANNEL + 1
-  elseif ( CHANNEL == 13 ) then
-    CHANNEL = 1
+  if ( LOCKED ) then
+    return
+  end
+  INDEX = INDEX % #CHANNELS + 1
+  set_channel(CHANNELS[INDEX])
+  channel_switcher:interval(DWELLS[INDEX] or HOP_INTERVAL)
+end
+
+-- Reponse a une commande
+-- reponse, argument
+function reply(kind, argument)
+  emit({kind, argument})
+end
+
+-- Etat de la carte
+-- STATUS, uptime, canal, heap, trames, version
+function send_status()
+  emit({"STATUS", tmr.time(), CHANNEL, node.heap(), FRAMES, VERSION})
+end
+
+-- Verifie qu'un canal est autorise
+function valid_channel(channel)
+  return channel ~= nil and channel >= 1 and channel <= 13
+end
+
+-- Commandes envoyees par le PC
+-- \2commande\31argument\31\3
+commands = {
+  CH = function(arg)
+    local channel = tonumber(arg)
+    if ( not valid_channel(channel) ) then return false end
+    set_channel(channel)
+    return true
+  end,
+  LOCK = function(arg)
+    local channel = tonumber(arg)
+    if ( not valid_channel(channel) ) then return false end
+    LOCKED = true
+    set_channel(channel)
+    return true
+  end,
+  UNLOCK = function()
+    LOCKED = false
+    return true
+  end,
+  HOP = function(arg)
+    local interval = tonumber(arg)
+    if ( interval == nil or interval <= 0 ) then return false end
+    HOP_INTERVAL = interval
+    DWELLS = {}
+    channel_switcher:interval(HOP_INTERVAL)
+    return true
+  end,
+  LIST = function(arg)
+    local channels = {}
+    for channel in string.gmatch(arg or "", "[^,]+") do
+      channel = tonumber(channel)
+      if ( not valid_channel(channel) ) then return false end
+      channels[#channels + 1] = channel
+    end
+    if ( #channels == 0 ) then return false end
+    CHANNELS = channels
+    DWELLS = {}
+    INDEX = 1
+    if ( not LOCKED ) then set_channel(CHANNELS[INDEX]) end
+    channel_switcher:interval(HOP_INTERVAL)
+    return true
+  end,
+  DWELL = function(arg)
+    -- canal:ms,canal:ms,...
+    local channels, dwells = {}, {}
+    for pair in string.gmatch(arg or "", "[^,]+") do
+      local channel, interval = string.match(pair, "^(%d+):(%d+)$")
+      if ( channel == nil ) then return false end
+      channel, interval = tonumber(channel), tonumber(interval)
+      if ( not valid_channel(channel) or interval <= 0 ) then return false end
+      channels[#channels + 1] = channel
+      dwells[#dwells + 1] = interval
+    end
+    if ( #channels == 0 ) then return false end
+    CHANNELS = channels
+    DWELLS = dwells
+    INDEX = 1
+    if ( not LOCKED ) then set_channel(CHANNELS[INDEX]) end
+    channel_switcher:interval(DWELLS[INDEX])
+    return true
+  end,
+  PAUSE = function()
+    PAUSED = true
+    channel_switcher:stop()
+    wifi.monitor.stop()
+    return true
+  end,
+  RESUME = function()
+    PAUSED = false
+    start_sniff()
+    wifi.monitor.channel(CHANNEL)
+    channel_switcher:start()
+    return true
+  end,
+  MODE = function(arg)
+    if ( arg ~= "probe" and arg ~= "ap" and arg ~= "all" ) then return false end
+    MODE = arg
+    -- Redemarrer le moniteur avec le nouveau filtre
+    if ( not PAUSED ) then
+      wifi.monitor.stop()
+      start_sniff()
+      wifi.monitor.channel(CHANNEL)
+    end
+    return true
+  end,
+}
+
+function handle_command(line)
+  -- Recuperer les champs entre \2 et \3
+  local fields = {}
+  for field in string.gmatch(line:gsub("[\2\3\r\n]", ""), "([^\31]*)\31") do
+    fields[#fields + 1] = field
+  end
+  local name, arg = fields[1], fields[2]
+  if ( name == "PING" ) then
+    reply("PONG", arg or "")
+  elseif ( name == "STATUS" ) then
+    send_status()
+  elseif ( commands[name] ~= nil and commands[name](arg) ) then
+    reply("ACK", name)
+  else
+    reply("ERR", name or "")
+  end
+end
+
+function on_line(line)
+  -- Les lignes ne commencant pas par \2 sont envoyees a l'interpreteur Lua (ESPlorer)
+  if ( line:byte(1) == 2 ) then
+    handle_command(line)
+  else
+    node.input(line)
   end
-  wifi.monitor.channel(CHANNEL)
 end
 
 -- Creer un timer pour le changement de channel
 channel_switcher = tmr.create()
-channel_switcher:register(5000, tmr.ALARM_AUTO, function() sniff() end)
+channel_switcher:register(HOP_INTERVAL, tmr.ALARM_AUTO, function() sniff() end)
+-- Creer un timer pour l'envoi de l'etat, independant de la pause
+status_sender = tmr.create()
+status_sender:register(STATUS_INTERVAL, tmr.ALARM_AUTO, function() send_status() end)
 
 init()
 start_sniff()
 channel_switcher:start()
+status_sender:start()
+-- Lecture des commandes, ligne par ligne, sans passer par l'interpreteur
+uart.on("data", "\n", on_line, 0)
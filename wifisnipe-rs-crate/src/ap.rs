@@ -0,0 +1,214 @@
+//! Inventaire des points d'accès, à partir des beacons et probe responses
+//!
+//! Disponible lorsque la carte est en mode `ap` ou `all` (voir [`crate::command::CaptureMode`])
+//!
+//! ## Schéma
+//!
+//! - `AP`, canal, BSSID, RSSI, SSID, capacités, IE RSN, sous-type, tick, numéro de trame
+//!
+//! | Champ | Description |
+//! | ----- | ----------- |
+//! | `capacités` | Champ Capability Information de la trame (entier) |
+//! | `IE RSN` | Contenu de l'élément RSN (`48`) en hexadécimal, vide si absent |
+//! | `sous-type` | `5` : probe response, `8` : beacon |
+//!
+//! Le tick et le numéro de trame sont traités comme pour les appareils (voir [`crate::clock`]).
+
+use chrono::{DateTime, Utc};
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::retention::Purge;
+use crate::{checked_mac, clock, default_rssi, privacy};
+
+/// Bit `Privacy` du champ Capability Information
+const CAPABILITY_PRIVACY: u16 = 0x0010;
+
+lazy_static! {
+    /// HashMap des points d'accès par BSSID
+    static ref ACCESS_POINTS: Arc<Mutex<HashMap<String, AccessPoint>>> = {
+        let aps = HashMap::new();
+        Arc::new(Mutex::new(aps))
+    };
+}
+
+/// Point d'accès
+///
+/// Champs:
+/// | Champ        | Type     | Description                                      |
+/// |--------------|----------|--------------------------------------------------|
+/// | `bssid`      | `String` | BSSID                                            |
+/// | `ssid`       | `String` | SSID (vide si masqué)                            |
+/// | `ts`         | `int`    | UNIX Timestamp (UTC) de la dernière trame        |
+/// | `channel`    | `int`    | Canal                                            |
+/// | `rssi`       | `int`    | Dernier RSSI                                     |
+/// | `capability` | `int`    | Champ Capability Information                     |
+/// | `security`   | `String` | Sécurité déduite des IEs (ex. `WPA2`, `Ouvert`)  |
+/// | `frames`     | `int`    | Nombre de beacons et probe responses reçus       |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessPoint {
+    /// BSSID
+    pub bssid: String,
+    /// SSID
+    pub ssid: String,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// Canal
+    pub channel: u32,
+    /// RSSI
+    pub rssi: i32,
+    /// Champ Capability Information
+    pub capability: u16,
+    /// Sécurité
+    pub security: String,
+    /// Nombre de trames reçues
+    pub frames: u64,
+}
+
+/// Déduit la sécurité d'un point d'accès à partir des capacités et de l'élément RSN
+///
+/// | Valeur | Condition |
+/// | ------ | --------- |
+/// | `Ouvert` | Ni RSN, ni bit `Privacy` |
+/// | `WEP/WPA` | Bit `Privacy` sans RSN |
+/// | `WPA2`, `WPA3`, `WPA2/WPA3` | RSN avec authentification PSK et/ou SAE |
+/// | `WPA2-Entreprise` | RSN avec authentification 802.1X |
+/// | `OWE` | RSN avec Opportunistic Wireless Encryption |
+pub fn security(capability: u16, rsn: &[u8]) -> String {
+    // Retire l'en-tête de l'élément s'il est présent
+    let rsn = match rsn {
+        [0x30, len, body @ ..] if *len as usize == body.len() => body,
+        _ => rsn,
+    };
+    if rsn.len() < 8 {
+        return String::from(if capability & CAPABILITY_PRIVACY != 0 {
+            "WEP/WPA"
+        } else {
+            "Ouvert"
+        });
+    }
+    // Version (2), suite de groupe (4), suites par paire (2 + 4n), authentifications (2 + 4m)
+    let pairwise = u16::from_le_bytes([rsn[6], rsn[7]]) as usize;
+    let akm_start = 8 + 4 * pairwise;
+    let akms: Vec<u8> = match rsn.get(akm_start..akm_start + 2) {
+        Some(count) => {
+            let count = u16::from_le_bytes([count[0], count[1]]) as usize;
+            rsn[akm_start + 2..]
+                .chunks_exact(4)
+                .take(count)
+                .map(|suite| suite[3])
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let psk = akms.iter().any(|akm| matches!(akm, 2 | 6));
+    let sae = akms.iter().any(|akm| matches!(akm, 8 | 24));
+    let label = if psk && sae {
+        "WPA2/WPA3"
+    } else if sae {
+        "WPA3"
+    } else if psk {
+        "WPA2"
+    } else if akms.iter().any(|akm| matches!(akm, 1 | 3 | 5 | 11 | 12)) {
+        "WPA2-Entreprise"
+    } else if akms.contains(&18) {
+        "OWE"
+    } else {
+        "RSN"
+    };
+    String::from(label)
+}
+
+// Décode une chaîne hexadécimale, vide si invalide
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(hex.get(2 * i..2 * i + 2).unwrap_or(""), 16))
+        .collect::<Result<_, _>>()
+        .unwrap_or_default()
+}
+
+/// Enregistre une trame de point d'accès (champs après `AP`)
+///
+/// Retourne `false` si la trame est mal formée
+pub(crate) fn store(fields: &[&str]) -> bool {
+    let field = |index: usize| fields.get(index).copied().unwrap_or("");
+    let bssid = checked_mac(field(1));
+    if bssid.is_empty() {
+        return false;
+    }
+    let tick = field(7).parse::<u64>().ok();
+    let seq = field(8).parse::<u64>().ok();
+    let seen = match (tick, seq) {
+        (Some(tick), Some(seq)) => clock::sync(tick, seq, SystemTime::now()),
+        _ => SystemTime::now(),
+    };
+    let capability = field(4).parse::<u16>().unwrap_or(0);
+    let ts = DateTime::<Utc>::from(seen).timestamp();
+    // Pseudonymisation avant tout enregistrement (mode confidentialité)
+    let bssid = privacy::mac(&bssid, ts);
+    let ap = AccessPoint {
+        bssid: bssid.clone(),
+        ssid: privacy::ssid(field(3), ts),
+        ts,
+        channel: field(0).parse::<u32>().unwrap_or(0),
+        rssi: field(2).parse::<i32>().unwrap_or(default_rssi()),
+        capability,
+        security: security(capability, &from_hex(field(5))),
+        frames: 1,
+    };
+    let mut ap_table = ACCESS_POINTS.lock().unwrap();
+    ap_table
+        .entry(bssid)
+        .and_modify(|known| {
+            // Un SSID masqué ne remplace pas celui révélé par une probe response
+            let ssid = if ap.ssid.is_empty() {
+                known.ssid.clone()
+            } else {
+                ap.ssid.clone()
+            };
+            let frames = known.frames + 1;
+            *known = AccessPoint {
+                ssid,
+                frames,
+                ..ap.clone()
+            };
+        })
+        .or_insert(ap);
+    true
+}
+
+/// Récupère une copie des points d'accès en mémoire, triés par BSSID
+pub fn snapshot() -> Vec<AccessPoint> {
+    let mut aps: Vec<AccessPoint> = ACCESS_POINTS.lock().unwrap().values().cloned().collect();
+    aps.sort_by(|a, b| a.bssid.cmp(&b.bssid));
+    aps
+}
+
+/// Date du plus ancien point d'accès
+pub(crate) fn oldest() -> Option<i64> {
+    ACCESS_POINTS.lock().unwrap().values().map(|ap| ap.ts).min()
+}
+
+/// Efface les points d'accès sélectionnés (selon leur dernière trame)
+///
+/// Retourne le nombre de points d'accès effacés
+pub(crate) fn purge(purge: &Purge) -> usize {
+    let mut ap_table = ACCESS_POINTS.lock().unwrap();
+    let count = ap_table.len();
+    ap_table.retain(|bssid, ap| !purge.matches(bssid, ap.ts));
+    count - ap_table.len()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Bundle de tous les points d'accès en mémoire pour la génération d'un fichier JSON (voir [`AccessPoint`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_aps_all<'a>() -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&snapshot()).unwrap_or(String::from("")))
+}
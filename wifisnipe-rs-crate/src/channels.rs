@@ -0,0 +1,378 @@
+//! Activité par canal et parcours des canaux pondéré
+//!
+//! Les trames reçues sont comptées par canal dans des fenêtres de [`CHANNEL_WINDOW`] secondes,
+//! les [`CHANNEL_HISTORY`] dernières fenêtres sont conservées ([`windows()`]).
+//!
+//! Par défaut, `sniper.lua` passe le même temps sur chaque canal, qu'il y ait du trafic ou non.
+//! [`recommend()`] répartit la durée d'un cycle de parcours selon l'activité récente : chaque
+//! canal garde une durée minimale pour découvrir de nouveaux appareils, le reste du cycle est
+//! partagé au prorata des trames reçues. Le parcours est envoyé à la carte par la commande
+//! `DWELL` ([`push()`]), ou recalculé et envoyé périodiquement avec [`set_adaptive()`].
+//!
+//! | Paramètre | Défaut | Description |
+//! | --------- | ------ | ----------- |
+//! | `window` | `600` | Activité prise en compte (secondes) |
+//! | `cycle_ms` | `65000` | Durée d'un cycle de parcours (13 canaux de 5 s) |
+//! | `min_dwell_ms` | `1000` | Durée minimale sur chaque canal |
+//! | `channels` | `1` à `13` | Canaux parcourus |
+
+use chrono::Utc;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Condvar, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::command::{self, Command, CHANNEL_RANGE};
+
+/// Durée d'une fenêtre d'activité (secondes)
+pub const CHANNEL_WINDOW: i64 = 60;
+
+/// Nombre de fenêtres d'activité conservées (24 heures)
+pub const CHANNEL_HISTORY: usize = 1440;
+
+/// Démarrage unique du thread de parcours adaptatif
+static ADAPTIVE: Once = Once::new();
+
+lazy_static! {
+    /// Fenêtres d'activité, la plus ancienne en premier
+    static ref WINDOWS: Mutex<VecDeque<ChannelWindow>> = Mutex::new(VecDeque::new());
+    /// Parcours adaptatif
+    static ref ADAPTIVE_STATE: Mutex<Adaptive> = Mutex::new(Adaptive::default());
+    /// Réveille le thread de parcours adaptatif au changement des paramètres
+    static ref ADAPTIVE_WAKE: Condvar = Condvar::new();
+}
+
+// Paramètres du parcours adaptatif
+#[derive(Default)]
+struct Adaptive {
+    // Période (secondes), `0` si désactivé
+    period: i64,
+    config: ScheduleConfig,
+    // Prochain envoi, recalculé au changement des paramètres
+    next: Option<Instant>,
+}
+
+/// Trames reçues par canal pendant une fenêtre
+///
+/// Champs:
+/// | Champ      | Type                | Description                                 |
+/// |------------|---------------------|---------------------------------------------|
+/// | `start`    | `int`               | UNIX Timestamp (UTC) du début de la fenêtre |
+/// | `duration` | `int`               | Durée de la fenêtre (secondes)              |
+/// | `channels` | `dict[int, int]`    | Nombre de trames par canal                  |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelWindow {
+    /// Début de la fenêtre
+    pub start: i64,
+    /// Durée de la fenêtre
+    pub duration: i64,
+    /// Trames par canal
+    pub channels: BTreeMap<u32, u64>,
+}
+
+impl ChannelWindow {
+    fn new(start: i64) -> Self {
+        ChannelWindow {
+            start,
+            duration: CHANNEL_WINDOW,
+            channels: BTreeMap::new(),
+        }
+    }
+}
+
+/// Paramètres du calcul du parcours
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Activité prise en compte (secondes)
+    pub window: i64,
+    /// Durée d'un cycle de parcours (millisecondes)
+    pub cycle_ms: u32,
+    /// Durée minimale sur chaque canal (millisecondes)
+    pub min_dwell_ms: u32,
+    /// Canaux parcourus, vide pour tous les canaux autorisés
+    #[serde(skip)]
+    pub channels: Vec<u8>,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            window: 600,
+            cycle_ms: 65_000,
+            min_dwell_ms: 1000,
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl ScheduleConfig {
+    /// Vérifie les paramètres
+    pub fn is_valid(&self) -> bool {
+        self.window > 0
+            && self.cycle_ms > 0
+            && self.min_dwell_ms > 0
+            && self.channels.iter().all(|c| CHANNEL_RANGE.contains(c))
+    }
+
+    // Canaux parcourus, triés et dédupliqués
+    fn channels(&self) -> Vec<u8> {
+        if self.channels.is_empty() {
+            return CHANNEL_RANGE.collect();
+        }
+        let mut channels = self.channels.clone();
+        channels.sort_unstable();
+        channels.dedup();
+        channels
+    }
+}
+
+/// Durée passée sur un canal dans le parcours recommandé
+///
+/// Champs:
+/// | Champ      | Type  | Description                                     |
+/// |------------|-------|-------------------------------------------------|
+/// | `channel`  | `int` | Canal                                           |
+/// | `dwell_ms` | `int` | Durée passée sur le canal (millisecondes)       |
+/// | `frames`   | `int` | Trames reçues sur le canal pendant la période   |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelDwell {
+    /// Canal
+    pub channel: u8,
+    /// Durée passée sur le canal
+    pub dwell_ms: u32,
+    /// Trames reçues
+    pub frames: u64,
+}
+
+/// Compte une trame reçue sur un canal
+pub(crate) fn record(channel: u32, ts: i64) {
+    let start = ts - ts.rem_euclid(CHANNEL_WINDOW);
+    let mut windows = WINDOWS.lock().unwrap();
+    // Les trames arrivent dans l'ordre, sauf en rejeu de plusieurs fichiers
+    let index = match windows.iter().rposition(|window| window.start <= start) {
+        Some(index) if windows[index].start == start => index,
+        Some(index) => {
+            windows.insert(index + 1, ChannelWindow::new(start));
+            index + 1
+        }
+        None => {
+            windows.push_front(ChannelWindow::new(start));
+            0
+        }
+    };
+    *windows[index].channels.entry(channel).or_insert(0) += 1;
+    while windows.len() > CHANNEL_HISTORY {
+        windows.pop_front();
+    }
+}
+
+/// Fenêtres d'activité se terminant après `since`, la plus ancienne en premier
+pub fn windows(since: i64) -> Vec<ChannelWindow> {
+    WINDOWS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|window| window.start + window.duration > since)
+        .cloned()
+        .collect()
+}
+
+/// Trames par canal dans les fenêtres se terminant après `since`
+pub fn totals(since: i64) -> BTreeMap<u32, u64> {
+    let mut totals = BTreeMap::new();
+    for window in windows(since) {
+        for (channel, frames) in window.channels {
+            *totals.entry(channel).or_insert(0) += frames;
+        }
+    }
+    totals
+}
+
+/// Parcours recommandé selon l'activité des `window` dernières secondes, dans l'ordre des canaux
+///
+/// La période se termine à l'heure actuelle : après le rejeu d'une capture horodatée ancienne,
+/// utiliser [`recommend_at()`] avec l'heure de la dernière trame.
+pub fn recommend(config: &ScheduleConfig) -> Vec<ChannelDwell> {
+    recommend_at(config, Utc::now().timestamp())
+}
+
+/// Parcours recommandé selon l'activité des `window` secondes précédant `now`
+/// (UNIX Timestamp UTC), dans l'ordre des canaux
+///
+/// Sans aucune trame, le cycle est réparti également. Si les durées minimales dépassent la
+/// durée du cycle, chaque canal reçoit la durée minimale.
+/// Retourne un parcours vide si les paramètres sont invalides
+pub fn recommend_at(config: &ScheduleConfig, now: i64) -> Vec<ChannelDwell> {
+    if !config.is_valid() {
+        return Vec::new();
+    }
+    let channels = config.channels();
+    let totals = totals(now - config.window);
+    let frames: Vec<u64> = channels
+        .iter()
+        .map(|channel| totals.get(&(*channel as u32)).copied().unwrap_or(0))
+        .collect();
+    let total: u64 = frames.iter().sum();
+    let count = channels.len() as u64;
+    let spare = (config.cycle_ms as u64).saturating_sub(config.min_dwell_ms as u64 * count);
+    channels
+        .into_iter()
+        .zip(frames)
+        .map(|(channel, frames)| {
+            // Sans trame, répartition égale
+            let share = (spare * frames).checked_div(total).unwrap_or(spare / count);
+            ChannelDwell {
+                channel,
+                dwell_ms: config.min_dwell_ms + share as u32,
+                frames,
+            }
+        })
+        .collect()
+}
+
+/// Commande `DWELL` correspondant à un parcours
+pub fn to_command(schedule: &[ChannelDwell]) -> Command {
+    Command::Dwell(
+        schedule
+            .iter()
+            .map(|dwell| (dwell.channel, dwell.dwell_ms))
+            .collect(),
+    )
+}
+
+/// Envoie un parcours à la carte
+///
+/// Retourne `true` si la commande a été envoyée
+pub fn push(schedule: &[ChannelDwell]) -> bool {
+    command::send(to_command(schedule))
+}
+
+/// Recalcule et envoie le parcours à la carte toutes les `period` secondes (`0` : désactivé)
+///
+/// Démarre le thread de parcours adaptatif au premier appel avec une période.
+/// Retourne `false`, sans rien changer, si les paramètres sont invalides
+pub fn set_adaptive(period: i64, config: ScheduleConfig) -> bool {
+    if !config.is_valid() {
+        return false;
+    }
+    *ADAPTIVE_STATE.lock().unwrap() = Adaptive {
+        period: period.max(0),
+        config,
+        next: None,
+    };
+    if period > 0 {
+        ADAPTIVE.call_once(|| {
+            thread::spawn(adaptive);
+        });
+    }
+    ADAPTIVE_WAKE.notify_all();
+    true
+}
+
+// Thread de parcours adaptatif, endormi tant qu'il est désactivé
+fn adaptive() {
+    let mut state = ADAPTIVE_STATE.lock().unwrap();
+    loop {
+        if state.period == 0 {
+            state = ADAPTIVE_WAKE.wait(state).unwrap();
+            continue;
+        }
+        let period = Duration::from_secs(state.period as u64);
+        let next = *state.next.get_or_insert_with(|| Instant::now() + period);
+        let now = Instant::now();
+        if now < next {
+            state = ADAPTIVE_WAKE.wait_timeout(state, next - now).unwrap().0;
+            continue;
+        }
+        state.next = Some(now + period);
+        let config = state.config.clone();
+        drop(state);
+        push(&recommend(&config));
+        state = ADAPTIVE_STATE.lock().unwrap();
+    }
+}
+
+// Paramètres FFI, `0` pour la valeur par défaut
+fn ffi_config(window: u32, cycle_ms: u32, min_dwell_ms: u32) -> ScheduleConfig {
+    let default = ScheduleConfig::default();
+    ScheduleConfig {
+        window: if window == 0 {
+            default.window
+        } else {
+            window as i64
+        },
+        cycle_ms: if cycle_ms == 0 {
+            default.cycle_ms
+        } else {
+            cycle_ms
+        },
+        min_dwell_ms: if min_dwell_ms == 0 {
+            default.min_dwell_ms
+        } else {
+            min_dwell_ms
+        },
+        channels: Vec::new(),
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Trames par canal dans les fenêtres d'activité se terminant après `since`, pour la
+/// génération d'un fichier JSON (voir [`ChannelWindow`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_channel_activity<'a>(since: i64) -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&windows(since)).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Parcours des canaux recommandé, pour la génération d'un fichier JSON (voir [`ChannelDwell`])
+///
+/// | Paramètre | Valeurs |
+/// | --------- | ------- |
+/// | `window` | Activité prise en compte en secondes |
+/// | `cycle_ms` | Durée d'un cycle de parcours en millisecondes |
+/// | `min_dwell_ms` | Durée minimale sur chaque canal en millisecondes |
+///
+/// `0` : valeur par défaut
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_hop_schedule<'a>(
+    window: u32,
+    cycle_ms: u32,
+    min_dwell_ms: u32,
+) -> AsciiPointer<'a> {
+    let schedule = recommend(&ffi_config(window, cycle_ms, min_dwell_ms));
+    crate::ffi_string(serde_json::to_string(&schedule).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Envoie à la carte le parcours recommandé (paramètres de [`get_hop_schedule()`])
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn push_hop_schedule(window: u32, cycle_ms: u32, min_dwell_ms: u32) -> bool {
+    push(&recommend(&ffi_config(window, cycle_ms, min_dwell_ms)))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Recalcule et envoie le parcours recommandé toutes les `period` secondes (`0` : désactivé),
+/// paramètres de [`get_hop_schedule()`]
+///
+/// Retourne `false` si les paramètres sont invalides
+pub extern "C" fn set_adaptive_hopping(
+    period: u32,
+    window: u32,
+    cycle_ms: u32,
+    min_dwell_ms: u32,
+) -> bool {
+    set_adaptive(period as i64, ffi_config(window, cycle_ms, min_dwell_ms))
+}
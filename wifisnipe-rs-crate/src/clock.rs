@@ -0,0 +1,98 @@
+//! Horloge de la carte et détection des trames perdues
+//!
+//! En version 2, chaque trame d'appareil porte deux champs supplémentaires :
+//!
+//! | Champ | Description |
+//! | ----- | ----------- |
+//! | `tick` | Millisecondes depuis le démarrage de la carte |
+//! | `seq` | Numéro de la trame depuis le démarrage de la carte (à partir de `1`) |
+//!
+//! Le décalage entre l'horloge de la carte et celle du PC est estimé par le minimum de
+//! `heure du PC - tick` sur les [`OFFSET_WINDOW`] dernières trames : le délai de transmission
+//! ne peut qu'augmenter cette différence. Les trames sont alors datées avec l'heure de la carte,
+//! sans le délai de la liaison série ni de la file d'attente.
+//!
+//! Un saut dans les numéros de trame est compté comme des trames perdues. Un retour en arrière
+//! du numéro ou du tick signale un redémarrage de la carte et réinitialise l'estimation.
+
+use interoptopus::ffi_function;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Nombre de trames utilisées pour l'estimation du décalage
+pub const OFFSET_WINDOW: usize = 256;
+
+lazy_static! {
+    /// État de la synchronisation
+    static ref CLOCK: Mutex<Clock> = Mutex::new(Clock::default());
+}
+
+#[derive(Default)]
+struct Clock {
+    // Échantillons `heure du PC - tick` (ms)
+    samples: VecDeque<i64>,
+    // Dernier tick reçu
+    last_tick: Option<u64>,
+    // Dernier numéro de trame reçu
+    last_seq: Option<u64>,
+    // Trames perdues
+    lost: u64,
+    // Redémarrages détectés
+    resets: u64,
+}
+
+/// Millisecondes UNIX d'un instant
+fn unix_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Enregistre le tick et le numéro d'une trame reçue à l'instant `received`
+///
+/// Retourne l'heure de la trame selon l'horloge de la carte
+pub(crate) fn sync(tick: u64, seq: u64, received: SystemTime) -> SystemTime {
+    let mut clock = CLOCK.lock().unwrap();
+    let rebooted = clock.last_seq.is_some_and(|last| seq <= last)
+        || clock.last_tick.is_some_and(|last| tick < last);
+    if rebooted {
+        clock.samples.clear();
+        clock.resets += 1;
+    } else if let Some(last) = clock.last_seq {
+        clock.lost += seq - last - 1;
+    }
+    clock.last_seq = Some(seq);
+    clock.last_tick = Some(tick);
+
+    if clock.samples.len() >= OFFSET_WINDOW {
+        clock.samples.pop_front();
+    }
+    clock.samples.push_back(unix_ms(received) - tick as i64);
+    let offset = clock.samples.iter().copied().min().unwrap_or(0);
+    UNIX_EPOCH + Duration::from_millis((offset + tick as i64).max(0) as u64)
+}
+
+/// Décalage estimé (ms) entre l'horloge de la carte et l'heure UNIX du PC
+///
+/// `None` tant qu'aucune trame avec tick n'a été reçue depuis le dernier redémarrage de la carte
+pub fn offset() -> Option<i64> {
+    CLOCK.lock().unwrap().samples.iter().copied().min()
+}
+
+/// Nombre de trames perdues depuis le chargement de la librairie
+pub fn frames_lost() -> u64 {
+    CLOCK.lock().unwrap().lost
+}
+
+/// Nombre de redémarrages de la carte détectés
+pub fn sensor_resets() -> u64 {
+    CLOCK.lock().unwrap().resets
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Nombre de trames perdues entre la carte et le PC (sauts dans les numéros de trame)
+pub extern "C" fn get_frames_lost() -> u64 {
+    frames_lost()
+}
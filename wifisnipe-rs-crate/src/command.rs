@@ -0,0 +1,344 @@
+//! Commandes envoyées à la carte par le port série
+//!
+//! ## Schéma
+//!
+//! Les commandes utilisent la même structure que les trames reçues :
+//!
+//! - `\u{2}commande\u{31}argument\u{31}\u{3}\n`
+//!
+//! | Commande | Argument | Effet |
+//! | -------- | -------- | ----- |
+//! | `CH` | Canal | Passe sur le canal, le parcours des canaux continue |
+//! | `LOCK` | Canal | Reste sur le canal, le parcours des canaux est arrêté |
+//! | `UNLOCK` | | Reprend le parcours des canaux |
+//! | `HOP` | Millisecondes | Durée passée sur chaque canal |
+//! | `LIST` | Canaux séparés par `,` | Canaux parcourus |
+//! | `DWELL` | `canal:millisecondes` séparés par `,` | Canaux parcourus et durée passée sur chacun ([`crate::channels`]) |
+//! | `PAUSE` | | Arrête la capture |
+//! | `RESUME` | | Reprend la capture |
+//! | `MODE` | `probe`, `ap` ou `all` | Trames capturées ([`CaptureMode`]) |
+//! | `PING` | Jeton | Demande une réponse `PONG` |
+//! | `STATUS` | | Demande une trame d'état ([`crate::sensor`]) |
+//!
+//! La carte répond par une ligne de la même structure :
+//!
+//! | Réponse | Argument |
+//! | ------- | -------- |
+//! | `ACK` | Commande exécutée |
+//! | `ERR` | Commande refusée |
+//! | `PONG` | Jeton reçu dans `PING` |
+//!
+//! Un `PONG` n'est retenu (voir [`get_last_pong()`]) que si son jeton est celui du dernier `PING`.
+//!
+//! Les lignes ne commençant pas par `\u{2}` sont transmises à l'interpréteur Lua de la carte,
+//! ESPlorer reste donc utilisable.
+
+use chrono::Utc;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+use std::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Canaux autorisés (réglementation FR)
+pub const CHANNEL_RANGE: std::ops::RangeInclusive<u8> = 1..=13;
+
+/// Durée passée sur chaque canal par défaut par `sniper.lua` (millisecondes)
+pub const DEFAULT_HOP_INTERVAL: u32 = 5000;
+
+lazy_static! {
+    /// Émetteur vers le port série ouvert, `None` si l'enregistrement est arrêté
+    static ref COMMAND_TX: Mutex<Option<UnboundedSender<Command>>> = Mutex::new(None);
+    /// Jeton du dernier ping envoyé
+    static ref PING_TOKEN: AtomicU32 = AtomicU32::new(0);
+    /// UNIX Timestamp (UTC) du dernier `PONG` reçu, `0` si aucun
+    static ref LAST_PONG: AtomicI64 = AtomicI64::new(0);
+    /// Commandes envoyées à chaque ouverture du port série
+    static ref ON_START: Mutex<Vec<Command>> = Mutex::new(Vec::new());
+}
+
+/// Trames capturées par la carte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// Probe requests des appareils (défaut)
+    Probes,
+    /// Beacons et probe responses des points d'accès (voir [`crate::ap`])
+    AccessPoints,
+    /// Les deux
+    All,
+}
+
+impl CaptureMode {
+    /// Nom du mode dans la commande `MODE`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CaptureMode::Probes => "probe",
+            CaptureMode::AccessPoints => "ap",
+            CaptureMode::All => "all",
+        }
+    }
+
+    /// Retrouve un mode à partir de son nom
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            CaptureMode::Probes,
+            CaptureMode::AccessPoints,
+            CaptureMode::All,
+        ]
+        .into_iter()
+        .find(|mode| mode.as_str() == name)
+    }
+}
+
+/// Commande à destination de la carte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Passe sur un canal, le parcours continue
+    SetChannel(u8),
+    /// Reste sur un canal
+    LockChannel(u8),
+    /// Reprend le parcours des canaux
+    Unlock,
+    /// Durée passée sur chaque canal en millisecondes
+    HopInterval(u32),
+    /// Canaux parcourus
+    ChannelList(Vec<u8>),
+    /// Canaux parcourus et durée passée sur chacun en millisecondes
+    Dwell(Vec<(u8, u32)>),
+    /// Arrête la capture
+    Pause,
+    /// Reprend la capture
+    Resume,
+    /// Change les trames capturées
+    Mode(CaptureMode),
+    /// Demande une réponse `PONG` portant le jeton
+    Ping(u32),
+    /// Demande une trame d'état
+    Status,
+}
+
+impl Command {
+    /// Vérifie que les arguments sont acceptables par la carte
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Command::SetChannel(channel) | Command::LockChannel(channel) => {
+                CHANNEL_RANGE.contains(channel)
+            }
+            Command::HopInterval(ms) => *ms > 0,
+            Command::ChannelList(channels) => {
+                !channels.is_empty() && channels.iter().all(|c| CHANNEL_RANGE.contains(c))
+            }
+            Command::Dwell(dwells) => {
+                !dwells.is_empty()
+                    && dwells
+                        .iter()
+                        .all(|(c, ms)| CHANNEL_RANGE.contains(c) && *ms > 0)
+            }
+            _ => true,
+        }
+    }
+
+    /// Trame envoyée à la carte, sans le retour à la ligne
+    pub fn to_frame(&self) -> String {
+        let (name, argument) = match self {
+            Command::SetChannel(channel) => ("CH", Some(channel.to_string())),
+            Command::LockChannel(channel) => ("LOCK", Some(channel.to_string())),
+            Command::Unlock => ("UNLOCK", None),
+            Command::HopInterval(ms) => ("HOP", Some(ms.to_string())),
+            Command::ChannelList(channels) => (
+                "LIST",
+                Some(
+                    channels
+                        .iter()
+                        .map(u8::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+            ),
+            Command::Dwell(dwells) => (
+                "DWELL",
+                Some(
+                    dwells
+                        .iter()
+                        .map(|(channel, ms)| format!("{channel}:{ms}"))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+            ),
+            Command::Pause => ("PAUSE", None),
+            Command::Resume => ("RESUME", None),
+            Command::Mode(mode) => ("MODE", Some(mode.as_str().to_owned())),
+            Command::Ping(token) => ("PING", Some(token.to_string())),
+            Command::Status => ("STATUS", None),
+        };
+        match argument {
+            Some(argument) => format!("\u{2}{name}\u{1F}{argument}\u{1F}\u{3}"),
+            None => format!("\u{2}{name}\u{1F}\u{3}"),
+        }
+    }
+}
+
+/// Branche le port série ouvert
+pub(crate) fn attach(tx: UnboundedSender<Command>) {
+    for command in ON_START.lock().unwrap().iter() {
+        let _ = tx.send(command.clone());
+    }
+    *COMMAND_TX.lock().unwrap() = Some(tx);
+}
+
+/// Débranche le port série
+pub(crate) fn detach() {
+    *COMMAND_TX.lock().unwrap() = None;
+}
+
+/// Note la réception d'un `PONG`, ignoré si son jeton n'est pas celui du dernier ping
+pub(crate) fn pong(token: &str) {
+    // Le jeton `0` n'est jamais envoyé
+    let expected = PING_TOKEN.load(Ordering::SeqCst);
+    if expected != 0 && token.parse::<u32>().ok() == Some(expected) {
+        LAST_PONG.store(Utc::now().timestamp(), Ordering::SeqCst);
+    }
+}
+
+/// Envoie une commande à la carte
+///
+/// Retourne
+///   - `true` : Si la commande a été mise en attente d'envoi
+///   - `false` : Si la commande est invalide ou si le port série n'est pas ouvert
+pub fn send(command: Command) -> bool {
+    if !command.is_valid() {
+        return false;
+    }
+    match COMMAND_TX.lock().unwrap().as_ref() {
+        Some(tx) => tx.send(command).is_ok(),
+        None => false,
+    }
+}
+
+/// Envoie des commandes à chaque ouverture du port série, en plus des commandes précédentes
+///
+/// Les commandes sont aussi envoyées tout de suite si le port série est ouvert.
+/// Retourne `false`, sans rien envoyer, si une commande est invalide
+pub fn send_on_start(commands: Vec<Command>) -> bool {
+    if !commands.iter().all(Command::is_valid) {
+        return false;
+    }
+    for command in &commands {
+        send(command.clone());
+    }
+    ON_START.lock().unwrap().extend(commands);
+    true
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Passe la carte sur un canal, le parcours des canaux continue
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn set_channel(channel: u8) -> bool {
+    send(Command::SetChannel(channel))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Bloque la carte sur un canal (`0` pour reprendre le parcours des canaux)
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn lock_channel(channel: u8) -> bool {
+    if channel == 0 {
+        send(Command::Unlock)
+    } else {
+        send(Command::LockChannel(channel))
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Règle la durée passée sur chaque canal en millisecondes
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn set_hop_interval(ms: u32) -> bool {
+    send(Command::HopInterval(ms))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Règle les canaux parcourus, séparés par des virgules (ex. `1,6,11`)
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn set_channel_list(channels: AsciiPointer) -> bool {
+    let channels: Option<Vec<u8>> = channels.as_str().ok().and_then(|list| {
+        list.split(',')
+            .map(|channel| channel.trim().parse::<u8>().ok())
+            .collect()
+    });
+    match channels {
+        Some(channels) => send(Command::ChannelList(channels)),
+        None => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Suspend la capture sur la carte
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn pause_capture() -> bool {
+    send(Command::Pause)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Reprend la capture sur la carte
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn resume_capture() -> bool {
+    send(Command::Resume)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Change les trames capturées par la carte
+///
+/// | Valeur de `mode` | Trames |
+/// | ---------------- | ------ |
+/// | `0` | Probe requests des appareils |
+/// | `1` | Beacons et probe responses des points d'accès |
+/// | `2` | Les deux |
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn set_capture_mode(mode: u8) -> bool {
+    match mode {
+        0 => send(Command::Mode(CaptureMode::Probes)),
+        1 => send(Command::Mode(CaptureMode::AccessPoints)),
+        2 => send(Command::Mode(CaptureMode::All)),
+        _ => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Envoie un ping à la carte, la réponse est consultable par [`get_last_pong()`]
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn ping() -> bool {
+    send(Command::Ping(PING_TOKEN.fetch_add(1, Ordering::SeqCst) + 1))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Demande à la carte d'envoyer son état, consultable par [`crate::sensor::get_sensor_status()`]
+///
+/// Retourne `true` si la commande a été envoyée
+pub extern "C" fn request_status() -> bool {
+    send(Command::Status)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// UNIX Timestamp (UTC) de la dernière réponse à [`ping()`]
+///
+/// Retourne `0` si la carte n'a jamais répondu
+pub extern "C" fn get_last_pong() -> i64 {
+    LAST_PONG.load(Ordering::SeqCst)
+}
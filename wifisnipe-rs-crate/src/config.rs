@@ -0,0 +1,758 @@
+//! Fichier de configuration (TOML)
+//!
+//! Un fichier décrit le capteur, la conservation des données, le mode confidentialité, les
+//! sorties et les règles d'alerte. Toutes les clés sont optionnelles, les valeurs par défaut
+//! sont celles de la librairie :
+//!
+//! ```toml
+//! [sensor]
+//! name = "salle1"            # Nom du capteur (défaut : sensor)
+//! port = "COM3"              # Port série (programme en ligne de commande)
+//! replay = "capture.txt"     # Capture relue au lieu du port série (programme en ligne de commande)
+//! baud_rate = 115200         # Débit du port série
+//! queue_capacity = 255       # Lignes en attente de traitement
+//! default_rssi = 0           # RSSI retenu quand celui d'une trame est illisible
+//! capture = "all"            # Trames capturées : probe, ap ou all
+//! channels = [1, 6, 11]      # Canaux parcourus
+//! hop_interval = 5000        # Durée passée sur chaque canal (millisecondes)
+//! adaptive_hop = 300         # Parcours pondéré par l'activité, recalculé toutes les 300 s
+//! position = [0.0, 0.0]      # Position en mètres, pour la localisation
+//!
+//! [[sensors]]                # Autres capteurs, pour la localisation
+//! name = "salle2"
+//! position = [8.0, 0.0]
+//!
+//! [display]                  # Programme en ligne de commande
+//! interval = 30              # Secondes entre deux affichages du tableau
+//! max_age = 1800             # Appareils affichés s'ils ont été vus depuis (secondes)
+//!
+//! [retention]
+//! max_age = 2592000          # Durée de conservation (secondes), 0 pour aucune limite
+//! audit_log = "audit.jsonl"  # Journal d'audit des effacements
+//! audit_key = "secret"       # Clé des empreintes d'adresses du journal d'audit
+//!
+//! [privacy]                  # Mode confidentialité, activé par la présence de la section
+//! key = "secret du site"     # Clé de pseudonymisation, aléatoire si vide
+//! daily_salt = true          # Pseudonymes renouvelés chaque jour
+//! ssid = "drop"              # keep, hash, drop ou nombre de caractères conservés
+//!
+//! [outputs]
+//! http = "127.0.0.1:8080"    # API HTTP (feature http)
+//! metrics = "0.0.0.0:9100"   # Métriques Prometheus (feature http)
+//! alert_log = "alerts.jsonl" # Journal des alertes
+//!
+//! [outputs.mqtt]             # Publication MQTT (feature mqtt)
+//! host = "192.168.1.10"
+//! port = 1883
+//! qos = 1
+//! retain = false
+//!
+//! [outputs.webhook]          # Notifications par POST (feature webhook)
+//! urls = ["https://exemple.fr/alertes"]
+//! outbox = "outbox.jsonl"
+//! summary_interval = 3600    # Secondes entre deux résumés, 0 pour aucun
+//!
+//! [alerts]
+//! watchlist = "watchlist.txt" # Fichier de règles (voir crate::watchlist)
+//! cooldown = 300              # Délai de répétition d'une alerte (secondes)
+//!
+//! [[alerts.rules]]
+//! kind = "mac"
+//! pattern = "da:a1:19:00:00:01"
+//! name = "direction"
+//! ```
+//!
+//! ## Erreurs
+//!
+//! Le fichier est entièrement vérifié avant d'être appliqué. Une erreur ([`ConfigError`]) désigne
+//! la clé fautive et, pour les erreurs de syntaxe ou de type, sa ligne :
+//!
+//! ```text
+//! sensor.baud_rate (ligne 3) : invalid type: string "rapide", expected u32
+//! privacy.ssid : mode SSID invalide : court
+//! ```
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+use crate::channels::{self, ScheduleConfig};
+use crate::command::{self, CaptureMode, Command, CHANNEL_RANGE, DEFAULT_HOP_INTERVAL};
+use crate::privacy::{self, PrivacyConfig, SsidPolicy};
+use crate::watchlist::{self, AlertRoute, Matcher, WatchRule};
+use crate::{localization, retention, DEFAULT_BAUD_RATE, QUEUE_CAPACITY};
+
+/// Erreur de configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Clé fautive (ex. `sensor.baud_rate`), vide pour une erreur portant sur le fichier
+    pub key: String,
+    /// Ligne de la clé, si connue
+    pub line: Option<usize>,
+    /// Description de l'erreur
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        ConfigError {
+            key: key.into(),
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.key.is_empty(), self.line) {
+            (true, Some(line)) => write!(f, "ligne {line} : {}", self.message),
+            (true, None) => write!(f, "{}", self.message),
+            (false, Some(line)) => write!(f, "{} (ligne {line}) : {}", self.key, self.message),
+            (false, None) => write!(f, "{} : {}", self.key, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Configuration complète (voir le format dans la documentation du module)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Capteur local
+    pub sensor: SensorSection,
+    /// Autres capteurs, pour la localisation
+    pub sensors: Vec<RemoteSensor>,
+    /// Affichage du programme en ligne de commande
+    pub display: DisplaySection,
+    /// Conservation des données
+    pub retention: RetentionSection,
+    /// Mode confidentialité, désactivé si absent
+    pub privacy: Option<PrivacySection>,
+    /// Sorties
+    pub outputs: OutputSection,
+    /// Alertes
+    pub alerts: AlertSection,
+}
+
+/// Section `[sensor]`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorSection {
+    /// Nom du capteur
+    pub name: String,
+    /// Port série
+    pub port: Option<String>,
+    /// Capture relue au lieu du port série
+    pub replay: Option<String>,
+    /// Débit du port série (bauds)
+    pub baud_rate: u32,
+    /// Capacité de la file d'attente
+    pub queue_capacity: usize,
+    /// RSSI retenu quand celui d'une trame est illisible
+    pub default_rssi: i32,
+    /// Trames capturées : `probe`, `ap` ou `all`
+    pub capture: Option<String>,
+    /// Canaux parcourus, tous si vide
+    pub channels: Vec<u8>,
+    /// Durée passée sur chaque canal (millisecondes)
+    pub hop_interval: Option<u32>,
+    /// Période de recalcul du parcours pondéré par l'activité (secondes), `0` pour aucun
+    pub adaptive_hop: u32,
+    /// Position en mètres
+    pub position: Option<[f64; 2]>,
+}
+
+impl Default for SensorSection {
+    fn default() -> Self {
+        SensorSection {
+            name: String::from("sensor"),
+            port: None,
+            replay: None,
+            baud_rate: DEFAULT_BAUD_RATE,
+            queue_capacity: QUEUE_CAPACITY,
+            default_rssi: 0,
+            capture: None,
+            channels: Vec::new(),
+            hop_interval: None,
+            adaptive_hop: 0,
+            position: None,
+        }
+    }
+}
+
+impl SensorSection {
+    /// Commandes à envoyer à la carte à l'ouverture du port série
+    pub fn commands(&self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        if let Some(mode) = self.capture.as_deref().and_then(CaptureMode::from_name) {
+            commands.push(Command::Mode(mode));
+        }
+        if !self.channels.is_empty() {
+            commands.push(Command::ChannelList(self.channels.clone()));
+        }
+        if let Some(ms) = self.hop_interval {
+            commands.push(Command::HopInterval(ms));
+        }
+        commands
+    }
+
+    /// Paramètres du parcours pondéré : même durée de cycle que le parcours régulier
+    pub fn schedule(&self) -> ScheduleConfig {
+        let count = if self.channels.is_empty() {
+            CHANNEL_RANGE.count()
+        } else {
+            self.channels.len()
+        };
+        ScheduleConfig {
+            cycle_ms: self
+                .hop_interval
+                .unwrap_or(DEFAULT_HOP_INTERVAL)
+                .saturating_mul(count as u32),
+            channels: self.channels.clone(),
+            ..ScheduleConfig::default()
+        }
+    }
+}
+
+/// Entrée `[[sensors]]`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteSensor {
+    /// Nom du capteur
+    pub name: String,
+    /// Position en mètres
+    pub position: [f64; 2],
+}
+
+/// Section `[display]`, utilisée par le programme en ligne de commande
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplaySection {
+    /// Secondes entre deux affichages du tableau
+    pub interval: u64,
+    /// Appareils affichés s'ils ont été vus depuis (secondes)
+    pub max_age: i64,
+}
+
+impl Default for DisplaySection {
+    fn default() -> Self {
+        DisplaySection {
+            interval: 30,
+            max_age: 1800,
+        }
+    }
+}
+
+/// Section `[retention]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionSection {
+    /// Durée de conservation (secondes), `0` pour aucune limite
+    pub max_age: u32,
+    /// Journal d'audit des effacements
+    pub audit_log: Option<PathBuf>,
+    /// Clé des empreintes d'adresses du journal d'audit
+    pub audit_key: Option<String>,
+}
+
+/// Traitement des SSIDs : nom du mode ou nombre de caractères conservés
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum SsidSetting {
+    /// `keep`, `hash` ou `drop`
+    Mode(String),
+    /// SSIDs tronqués
+    Length(usize),
+}
+
+impl SsidSetting {
+    /// Traitement correspondant, `None` si le mode est inconnu
+    pub fn policy(&self) -> Option<SsidPolicy> {
+        match self {
+            SsidSetting::Mode(mode) => match mode.as_str() {
+                "keep" => Some(SsidPolicy::Keep),
+                "hash" => Some(SsidPolicy::Hash),
+                "drop" => Some(SsidPolicy::Drop),
+                _ => None,
+            },
+            SsidSetting::Length(len) => Some(SsidPolicy::Truncate(*len)),
+        }
+    }
+}
+
+/// Section `[privacy]`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivacySection {
+    /// Clé de pseudonymisation, aléatoire si vide
+    pub key: String,
+    /// Pseudonymes renouvelés chaque jour
+    pub daily_salt: bool,
+    /// Traitement des SSIDs
+    pub ssid: SsidSetting,
+}
+
+impl Default for PrivacySection {
+    fn default() -> Self {
+        PrivacySection {
+            key: String::new(),
+            daily_salt: false,
+            ssid: SsidSetting::Mode(String::from("hash")),
+        }
+    }
+}
+
+/// Section `[outputs]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSection {
+    /// Adresse de l'API HTTP
+    pub http: Option<String>,
+    /// Adresse des métriques Prometheus
+    pub metrics: Option<String>,
+    /// Journal des alertes
+    pub alert_log: Option<PathBuf>,
+    /// Publication MQTT
+    pub mqtt: Option<MqttSection>,
+    /// Notifications par POST
+    pub webhook: Option<WebhookSection>,
+}
+
+/// Section `[outputs.mqtt]`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttSection {
+    /// Adresse du broker
+    pub host: String,
+    /// Port du broker
+    pub port: u16,
+    /// QoS des messages (`0`, `1` ou `2`)
+    pub qos: u8,
+    /// Retient les messages des observations
+    pub retain: bool,
+}
+
+impl Default for MqttSection {
+    fn default() -> Self {
+        MqttSection {
+            host: String::from("127.0.0.1"),
+            port: 1883,
+            qos: 0,
+            retain: false,
+        }
+    }
+}
+
+/// Section `[outputs.webhook]`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookSection {
+    /// URLs notifiées
+    pub urls: Vec<String>,
+    /// Boîte d'envoi des notifications
+    pub outbox: Option<PathBuf>,
+    /// Secondes entre deux résumés, `0` pour aucun
+    pub summary_interval: u64,
+}
+
+impl Default for WebhookSection {
+    fn default() -> Self {
+        WebhookSection {
+            urls: Vec::new(),
+            outbox: None,
+            summary_interval: 3600,
+        }
+    }
+}
+
+/// Section `[alerts]`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertSection {
+    /// Fichier de règles
+    pub watchlist: Option<String>,
+    /// Délai de répétition d'une alerte (secondes)
+    pub cooldown: u32,
+    /// Règles
+    pub rules: Vec<RuleSection>,
+}
+
+impl Default for AlertSection {
+    fn default() -> Self {
+        AlertSection {
+            watchlist: None,
+            cooldown: watchlist::DEFAULT_COOLDOWN as u32,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Entrée `[[alerts.rules]]`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSection {
+    /// Type de règle : `mac`, `prefix` (ou `oui`), `ssid` ou `regex`
+    pub kind: String,
+    /// Motif
+    pub pattern: String,
+    /// Nom de la règle, le motif si absent
+    pub name: Option<String>,
+}
+
+impl RuleSection {
+    /// Règle de la liste de surveillance
+    pub fn rule(&self) -> Result<WatchRule, String> {
+        Ok(WatchRule::new(
+            self.name.as_deref().unwrap_or(""),
+            Matcher::new(&self.kind, &self.pattern)?,
+        ))
+    }
+}
+
+// Ligne d'une position dans le texte
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+// Vérifie qu'une section dont la feature est absente n'est pas utilisée
+fn require_feature(key: &str, feature: &str, used: bool, enabled: bool) -> Result<(), ConfigError> {
+    if used && !enabled {
+        return Err(ConfigError::new(
+            key,
+            format!("librairie compilée sans la feature {feature}"),
+        ));
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Lit et vérifie une configuration
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let deserializer = toml::Deserializer::new(text);
+        let config: Config = serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let path = err.path().to_string();
+            let inner = err.into_inner();
+            ConfigError {
+                key: if path == "." { String::new() } else { path },
+                line: inner.span().map(|span| line_of(text, span.start)),
+                message: inner.message().to_owned(),
+            }
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Vérifie les valeurs de la configuration
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let sensor = &self.sensor;
+        if sensor.name.is_empty() {
+            return Err(ConfigError::new("sensor.name", "nom vide"));
+        }
+        if sensor.baud_rate == 0 {
+            return Err(ConfigError::new("sensor.baud_rate", "débit nul"));
+        }
+        if sensor.queue_capacity == 0 {
+            return Err(ConfigError::new("sensor.queue_capacity", "capacité nulle"));
+        }
+        if let Some(mode) = &sensor.capture {
+            if CaptureMode::from_name(mode).is_none() {
+                return Err(ConfigError::new(
+                    "sensor.capture",
+                    format!("mode de capture invalide : {mode} (probe, ap ou all)"),
+                ));
+            }
+        }
+        for (index, channel) in sensor.channels.iter().enumerate() {
+            if !CHANNEL_RANGE.contains(channel) {
+                return Err(ConfigError::new(
+                    format!("sensor.channels[{index}]"),
+                    format!("canal invalide : {channel}"),
+                ));
+            }
+        }
+        if sensor.hop_interval == Some(0) {
+            return Err(ConfigError::new("sensor.hop_interval", "durée nulle"));
+        }
+        if let Some(position) = sensor.position {
+            check_position("sensor.position", position)?;
+        }
+        for (index, remote) in self.sensors.iter().enumerate() {
+            if remote.name.is_empty() {
+                return Err(ConfigError::new(
+                    format!("sensors[{index}].name"),
+                    "nom vide",
+                ));
+            }
+            check_position(&format!("sensors[{index}].position"), remote.position)?;
+        }
+        if self.display.interval == 0 {
+            return Err(ConfigError::new("display.interval", "durée nulle"));
+        }
+        if let Some(privacy) = &self.privacy {
+            if privacy.ssid.policy().is_none() {
+                return Err(ConfigError::new(
+                    "privacy.ssid",
+                    format!(
+                        "mode SSID invalide : {:?} (keep, hash, drop ou nombre)",
+                        privacy.ssid
+                    ),
+                ));
+            }
+        }
+        let outputs = &self.outputs;
+        require_feature(
+            "outputs.http",
+            "http",
+            outputs.http.is_some(),
+            cfg!(feature = "http"),
+        )?;
+        require_feature(
+            "outputs.metrics",
+            "http",
+            outputs.metrics.is_some(),
+            cfg!(feature = "http"),
+        )?;
+        require_feature(
+            "outputs.mqtt",
+            "mqtt",
+            outputs.mqtt.is_some(),
+            cfg!(feature = "mqtt"),
+        )?;
+        require_feature(
+            "outputs.webhook",
+            "webhook",
+            outputs.webhook.is_some(),
+            cfg!(feature = "webhook"),
+        )?;
+        if let Some(mqtt) = &outputs.mqtt {
+            if mqtt.host.is_empty() {
+                return Err(ConfigError::new("outputs.mqtt.host", "adresse vide"));
+            }
+            if mqtt.qos > 2 {
+                return Err(ConfigError::new(
+                    "outputs.mqtt.qos",
+                    format!("QoS invalide : {} (0, 1 ou 2)", mqtt.qos),
+                ));
+            }
+        }
+        if let Some(webhook) = &outputs.webhook {
+            if webhook.urls.is_empty() {
+                return Err(ConfigError::new("outputs.webhook.urls", "aucune URL"));
+            }
+            for (index, url) in webhook.urls.iter().enumerate() {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(ConfigError::new(
+                        format!("outputs.webhook.urls[{index}]"),
+                        format!("URL invalide : {url}"),
+                    ));
+                }
+            }
+        }
+        for (index, rule) in self.alerts.rules.iter().enumerate() {
+            rule.rule()
+                .map_err(|err| ConfigError::new(format!("alerts.rules[{index}]"), err))?;
+        }
+        Ok(())
+    }
+
+    /// Applique la configuration à la librairie
+    ///
+    /// Les réglages du port série sont pris en compte au prochain démarrage, les commandes
+    /// (capture, canaux) à chaque ouverture du port série. Le mode confidentialité doit être
+    /// appliqué avant le démarrage de l'enregistrement. Les sorties sont démarrées.
+    ///
+    /// Tout ce qui peut échouer (mode confidentialité, journal d'audit, fichier de surveillance)
+    /// est vérifié avant de modifier la librairie, sauf le démarrage des sorties, fait en dernier :
+    /// si une sortie ne démarre pas, les réglages et les sorties déjà démarrées restent appliqués.
+    ///
+    /// `port`, `replay` et `[display]` ne sont utilisés que par le programme en ligne de commande
+    pub fn apply(&self) -> Result<(), ConfigError> {
+        self.validate()?;
+        // Pseudonymisation, avant les premières trames
+        let privacy = match &self.privacy {
+            Some(section) => Some(
+                privacy::prepare(PrivacyConfig {
+                    key: section.key.as_bytes().to_vec(),
+                    daily_salt: section.daily_salt,
+                    ssid: section.ssid.policy().unwrap_or(SsidPolicy::Hash),
+                })
+                .ok_or_else(privacy_error)?,
+            ),
+            None => None,
+        };
+        if let Some(path) = &self.retention.audit_log {
+            // Vérifie que le journal est accessible en écriture
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| ConfigError::new("retention.audit_log", err.to_string()))?;
+        }
+        let watchlist_rules = match &self.alerts.watchlist {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| watchlist::parse(&text))
+                    .map_err(|err| {
+                        ConfigError::new("alerts.watchlist", format!("{path} : {err}"))
+                    })?,
+            ),
+            None => None,
+        };
+
+        if let Some(config) = privacy {
+            if !privacy::enable(config) {
+                return Err(privacy_error());
+            }
+        }
+        let sensor = &self.sensor;
+        crate::set_baud_rate(sensor.baud_rate);
+        crate::set_queue_capacity(sensor.queue_capacity);
+        crate::set_default_rssi(sensor.default_rssi);
+        command::send_on_start(sensor.commands());
+        if sensor.adaptive_hop > 0 {
+            channels::set_adaptive(sensor.adaptive_hop as i64, sensor.schedule());
+        }
+        // Localisation
+        localization::set_local(&sensor.name);
+        if let Some(position) = sensor.position {
+            place("sensor.position", &sensor.name, position)?;
+        }
+        for (index, remote) in self.sensors.iter().enumerate() {
+            place(
+                &format!("sensors[{index}].position"),
+                &remote.name,
+                remote.position,
+            )?;
+        }
+        // Conservation
+        if let Some(path) = &self.retention.audit_log {
+            retention::set_audit_log(path.clone())
+                .map_err(|err| ConfigError::new("retention.audit_log", err.to_string()))?;
+        }
+        if let Some(key) = &self.retention.audit_key {
+            retention::set_audit_key(key.as_bytes());
+        }
+        if self.retention.max_age > 0 {
+            retention::set_max_age(self.retention.max_age as i64);
+        }
+        // Alertes
+        watchlist::set_cooldown(self.alerts.cooldown as i64);
+        if let Some(rules) = watchlist_rules {
+            watchlist::clear_rules();
+            for rule in rules {
+                watchlist::add_rule(rule);
+            }
+        }
+        for rule in &self.alerts.rules {
+            if let Ok(rule) = rule.rule() {
+                watchlist::add_rule(rule);
+            }
+        }
+        if let Some(path) = &self.outputs.alert_log {
+            watchlist::add_route(AlertRoute::Log(path.clone()));
+        }
+        self.start_outputs()
+    }
+
+    // Démarre les sorties disponibles
+    fn start_outputs(&self) -> Result<(), ConfigError> {
+        #[cfg(feature = "http")]
+        if let Some(addr) = &self.outputs.http {
+            crate::http::serve(addr)
+                .map_err(|err| ConfigError::new("outputs.http", format!("{addr} : {err}")))?;
+        }
+        #[cfg(feature = "http")]
+        if let Some(addr) = &self.outputs.metrics {
+            crate::http::serve_metrics(addr)
+                .map_err(|err| ConfigError::new("outputs.metrics", format!("{addr} : {err}")))?;
+        }
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.outputs.mqtt {
+            crate::mqtt::start(crate::mqtt::MqttConfig {
+                host: mqtt.host.clone(),
+                port: mqtt.port,
+                sensor: self.sensor.name.clone(),
+                qos: mqtt.qos,
+                retain_observations: mqtt.retain,
+                ..crate::mqtt::MqttConfig::default()
+            })
+            .map_err(|err| ConfigError::new("outputs.mqtt", err.to_string()))?;
+        }
+        #[cfg(feature = "webhook")]
+        if let Some(webhook) = &self.outputs.webhook {
+            crate::webhook::start(crate::webhook::WebhookConfig {
+                urls: webhook.urls.clone(),
+                sensor: self.sensor.name.clone(),
+                outbox: webhook.outbox.clone(),
+                summary_interval: match webhook.summary_interval {
+                    0 => None,
+                    seconds => Some(std::time::Duration::from_secs(seconds)),
+                },
+                ..crate::webhook::WebhookConfig::default()
+            })
+            .map_err(|err| ConfigError::new("outputs.webhook", err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+// Erreur du mode confidentialité impossible à activer
+fn privacy_error() -> ConfigError {
+    ConfigError::new(
+        "privacy",
+        "impossible d'activer le mode confidentialité, des trames ont déjà été enregistrées ou aucune clé aléatoire n'a pu être tirée",
+    )
+}
+
+// Vérifie que les coordonnées d'un capteur sont des nombres finis
+fn check_position(key: &str, [x, y]: [f64; 2]) -> Result<(), ConfigError> {
+    if x.is_finite() && y.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::new(
+            key,
+            format!("coordonnées invalides : [{x}, {y}]"),
+        ))
+    }
+}
+
+// Place un capteur pour la localisation
+fn place(key: &str, sensor: &str, [x, y]: [f64; 2]) -> Result<(), ConfigError> {
+    if localization::set_position(sensor, x, y) {
+        Ok(())
+    } else {
+        Err(ConfigError::new(key, "position refusée"))
+    }
+}
+
+/// Lit et vérifie un fichier de configuration
+pub fn load(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| ConfigError::new("", format!("{} : {err}", path.display())))?;
+    Config::parse(&text)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Lit, vérifie et applique un fichier de configuration TOML (voir [`Config`]), avant le démarrage
+/// de l'enregistrement
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`) : vide si la
+/// configuration a été appliquée, sinon l'erreur avec la clé fautive
+pub extern "C" fn load_config<'a>(path: AsciiPointer) -> AsciiPointer<'a> {
+    let result = match path.as_str() {
+        Ok(path) => load(path).and_then(|config| config.apply()),
+        Err(_) => Err(ConfigError::new("", "chemin invalide")),
+    };
+    crate::ffi_string(match result {
+        Ok(()) => String::new(),
+        Err(err) => err.to_string(),
+    })
+}
@@ -0,0 +1,110 @@
+//! Flux d'évènements émis au fil du traitement des trames
+//!
+//! Chaque observation acceptée par le traitement est publiée dès son enregistrement,
+//! précédée d'un évènement [`Event::NewDevice`] si l'adresse MAC n'avait jamais été vue,
+//! et des évènements de présence [`Event::Arrive`] et [`Event::Leave`] (voir [`crate::sessions`])
+//! ou de changement de zone [`Event::Zone`] (voir [`crate::zones`]), et suivie des alertes
+//! [`Event::Alert`] de la liste de surveillance (voir [`crate::watchlist`]).
+//!
+//! Les abonnés reçoivent les évènements publiés après leur abonnement.
+//! Un abonné trop lent perd les évènements les plus anciens (voir [`EVENT_CAPACITY`]).
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::sessions::Session;
+use crate::watchlist::Alert;
+use crate::zones::ZoneChange;
+use crate::Observation;
+
+/// Nombre d'évènements conservés pour chaque abonné en attente de lecture
+pub const EVENT_CAPACITY: usize = 1024;
+
+lazy_static! {
+    /// Émetteur des évènements
+    static ref EVENTS: broadcast::Sender<Event> = broadcast::channel(EVENT_CAPACITY).0;
+}
+
+/// Évènement sérialisé en JSON avec un champ `type`
+///
+/// | `type` | Description |
+/// | ------ | ----------- |
+/// | `observation` | Observation acceptée ([`Observation`]) |
+/// | `new_device` | Première observation d'une adresse MAC ([`Observation`]) |
+/// | `arrive` | Début d'une session de présence ([`Session`]) |
+/// | `leave` | Fin d'une session de présence ([`Session`]) |
+/// | `zone` | Changement de zone de proximité ([`ZoneChange`]) |
+/// | `alert` | Alerte de la liste de surveillance ([`Alert`]) |
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// Observation acceptée
+    Observation(Observation),
+    /// Première observation d'une adresse MAC
+    NewDevice(Observation),
+    /// Début d'une session de présence
+    Arrive(Session),
+    /// Fin d'une session de présence
+    Leave(Session),
+    /// Changement de zone de proximité
+    Zone(ZoneChange),
+    /// Alerte de la liste de surveillance
+    Alert(Alert),
+}
+
+impl Event {
+    /// Nom de l'évènement, identique au champ `type` du JSON
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Observation(_) => "observation",
+            Event::NewDevice(_) => "new_device",
+            Event::Arrive(_) => "arrive",
+            Event::Leave(_) => "leave",
+            Event::Zone(_) => "zone",
+            Event::Alert(_) => "alert",
+        }
+    }
+
+    /// Observation portée par l'évènement, `None` pour les autres évènements
+    pub fn observation(&self) -> Option<&Observation> {
+        match self {
+            Event::Observation(obs) | Event::NewDevice(obs) => Some(obs),
+            _ => None,
+        }
+    }
+
+    /// Session portée par l'évènement, `None` pour les autres évènements
+    pub fn session(&self) -> Option<&Session> {
+        match self {
+            Event::Arrive(session) | Event::Leave(session) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Changement de zone porté par l'évènement, `None` pour les autres évènements
+    pub fn zone(&self) -> Option<&ZoneChange> {
+        match self {
+            Event::Zone(change) => Some(change),
+            _ => None,
+        }
+    }
+
+    /// Alerte portée par l'évènement, `None` pour les autres évènements
+    pub fn alert(&self) -> Option<&Alert> {
+        match self {
+            Event::Alert(alert) => Some(alert),
+            _ => None,
+        }
+    }
+}
+
+/// S'abonne aux évènements
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    EVENTS.subscribe()
+}
+
+/// Publie un évènement auprès de tous les abonnés
+pub(crate) fn publish(event: Event) {
+    // Une erreur signifie seulement qu'il n'y a aucun abonné
+    let _ = EVENTS.send(event);
+}
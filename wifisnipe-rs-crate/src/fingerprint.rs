@@ -0,0 +1,91 @@
+//! Empreinte des appareils à partir des éléments d'information (IE) des probe requests
+//!
+//! `sniper.lua` envoie une sélection d'IEs sous forme `identifiant, longueur, contenu`
+//! encodée en hexadécimal. Ces éléments dépendent de la puce et du système de l'appareil,
+//! pas de son adresse MAC : ils restent identiques lorsque l'adresse est aléatoire.
+//!
+//! | IE | Nom | Partie utilisée |
+//! | -- | --- | --------------- |
+//! | `1` | Supported Rates | Contenu |
+//! | `45` | HT Capabilities | Contenu |
+//! | `50` | Extended Supported Rates | Contenu |
+//! | `127` | Extended Capabilities | Contenu |
+//! | `191` | VHT Capabilities | Contenu |
+//! | `221` | Vendor Specific | OUI et type (4 premiers octets) |
+//!
+//! L'empreinte est le hash FNV-1a 64 bits, en hexadécimal, des identifiants dans l'ordre de la
+//! trame et des parties utilisées. Les autres IEs (ex. SSID) sont ignorés.
+
+/// Identifiants des IEs utilisés pour l'empreinte
+pub const FINGERPRINT_IES: [u8; 6] = [1, 45, 50, 127, 191, 221];
+
+/// Identifiant de l'IE Vendor Specific
+const VENDOR_SPECIFIC: u8 = 221;
+
+/// Élément d'information 802.11
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InformationElement {
+    /// Identifiant
+    pub id: u8,
+    /// Contenu, sans l'identifiant ni la longueur
+    pub data: Vec<u8>,
+}
+
+/// Décode une suite d'IEs encodée en hexadécimal
+///
+/// Le décodage s'arrête au premier élément tronqué ou au premier caractère invalide
+pub fn parse_ies(hex: &str) -> Vec<InformationElement> {
+    let bytes: Vec<u8> = hex
+        .as_bytes()
+        .chunks_exact(2)
+        .map_while(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect();
+    let mut ies = Vec::new();
+    let mut rest = bytes.as_slice();
+    while let [id, len, tail @ ..] = rest {
+        let len = *len as usize;
+        if tail.len() < len {
+            break;
+        }
+        ies.push(InformationElement {
+            id: *id,
+            data: tail[..len].to_vec(),
+        });
+        rest = &tail[len..];
+    }
+    ies
+}
+
+/// FNV-1a 64 bits
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Calcule l'empreinte d'une suite d'IEs
+///
+/// Retourne une chaîne vide si aucun IE utilisé n'est présent
+pub fn fingerprint(ies: &[InformationElement]) -> String {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    let mut used = false;
+    for ie in ies.iter().filter(|ie| FINGERPRINT_IES.contains(&ie.id)) {
+        let data = if ie.id == VENDOR_SPECIFIC {
+            &ie.data[..ie.data.len().min(4)]
+        } else {
+            &ie.data[..]
+        };
+        hash = fnv1a(hash, &[ie.id, data.len() as u8]);
+        hash = fnv1a(hash, data);
+        used = true;
+    }
+    if used {
+        format!("{hash:016x}")
+    } else {
+        String::new()
+    }
+}
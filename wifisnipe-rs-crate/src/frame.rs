@@ -0,0 +1,151 @@
+//! Décodage des lignes envoyées par la carte
+//!
+//! ## Version 1
+//!
+//! - `\u{2}champ1\u{31}champ2\u{31}...\u{31}\u{3}`
+//!
+//! Le nombre de champs est implicite : une trame d'appareil sans SSID n'a que trois champs.
+//!
+//! ## Version 2
+//!
+//! - `\u{2}V2\u{31}nombre\u{31}champ1\u{31}...\u{31}champN\u{31}crc\u{31}\u{3}`
+//!
+//! | Champ | Description |
+//! | ----- | ----------- |
+//! | `V2` | Version du format |
+//! | `nombre` | Nombre de champs de la trame |
+//! | `champ1` à `champN` | Champs de la trame, identiques à la version 1 (SSID toujours présent, même vide) |
+//! | `crc` | CRC-16/CCITT-FALSE en hexadécimal (4 caractères) de tout ce qui précède, de `V2` au dernier `\u{31}` |
+//!
+//! ## Trame d'appareil en version 2
+//!
+//! | Position | Champ |
+//! | -------- | ----- |
+//! | 1 | Canal |
+//! | 2 | Adresse MAC de l'émetteur (destinataire en version 1) |
+//! | 3 | RSSI |
+//! | 4 | SSID |
+//! | 5 | Millisecondes depuis le démarrage de la carte (voir [`crate::clock`]) |
+//! | 6 | Numéro de trame de la carte |
+//! | 7 | Adresse MAC du destinataire |
+//! | 8 | BSSID |
+//! | 9 | Sous-type 802.11 |
+//! | 10 | Champ Sequence Control 802.11 (numéro de séquence et de fragment) |
+//! | 11 | IEs sélectionnés en hexadécimal (voir [`crate::fingerprint`]) |
+//!
+//! Les champs absents (versions précédentes de `sniper.lua`) sont laissés vides.
+//!
+//! Les deux versions sont acceptées simultanément, la version est détectée par le premier champ.
+
+use std::fmt;
+
+/// Étiquette de la version 2
+pub const V2_TAG: &str = "V2";
+
+/// Séparateur de champs (ASCII Séparation d'unité)
+const SEPARATOR: char = '\u{1F}';
+
+/// Version du format d'une ligne
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// Format historique, sans contrôle
+    V1,
+    /// Format avec nombre de champs et CRC
+    V2,
+}
+
+/// Ligne décodée
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Version du format
+    pub version: Version,
+    /// Champs de la trame
+    pub fields: Vec<&'a str>,
+}
+
+/// Erreur de décodage d'une ligne
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    /// Ligne vide ou sans champ (ex. messages de démarrage)
+    Empty,
+    /// Nombre de champs différent de celui annoncé
+    FieldCount,
+    /// CRC absent ou différent de celui calculé
+    Checksum,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Empty => write!(f, "ligne sans champ"),
+            FrameError::FieldCount => write!(f, "nombre de champs invalide"),
+            FrameError::Checksum => write!(f, "CRC invalide"),
+        }
+    }
+}
+
+/// CRC-16/CCITT-FALSE (polynôme `0x1021`, valeur initiale `0xFFFF`), identique à `sniper.lua`
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Décode une ligne débarrassée de `\u{2}`, `\u{3}` et des fins de ligne
+pub fn decode(line: &str) -> Result<Frame<'_>, FrameError> {
+    // Le dernier séparateur termine le dernier champ
+    let body = line.strip_suffix(SEPARATOR).unwrap_or(line);
+    if !body.starts_with(V2_TAG) || !body[V2_TAG.len()..].starts_with(SEPARATOR) {
+        let fields: Vec<&str> = body.split(SEPARATOR).collect();
+        // Une ligne sans séparateur n'est pas une trame
+        if fields.len() < 2 {
+            return Err(FrameError::Empty);
+        }
+        return Ok(Frame {
+            version: Version::V1,
+            fields,
+        });
+    }
+
+    // Le CRC porte sur tout ce qui précède le dernier champ
+    let crc_start = body.rfind(SEPARATOR).ok_or(FrameError::Checksum)? + 1;
+    let crc = u16::from_str_radix(&body[crc_start..], 16).map_err(|_| FrameError::Checksum)?;
+    if crc16(&body.as_bytes()[..crc_start]) != crc {
+        return Err(FrameError::Checksum);
+    }
+    let mut fields = body[..crc_start - 1].split(SEPARATOR).skip(1);
+    let count: usize = fields
+        .next()
+        .and_then(|count| count.parse().ok())
+        .ok_or(FrameError::FieldCount)?;
+    let fields: Vec<&str> = fields.collect();
+    if count == 0 || fields.len() != count {
+        return Err(FrameError::FieldCount);
+    }
+    Ok(Frame {
+        version: Version::V2,
+        fields,
+    })
+}
+
+/// Construit une ligne au format version 2, sans le retour à la ligne
+///
+/// Utile pour générer des captures de test
+pub fn encode_v2(fields: &[&str]) -> String {
+    let mut body = format!("{V2_TAG}{SEPARATOR}{}{SEPARATOR}", fields.len());
+    for field in fields {
+        body.push_str(field);
+        body.push(SEPARATOR);
+    }
+    let crc = crc16(body.as_bytes());
+    format!("\u{2}{body}{crc:04X}{SEPARATOR}\u{3}")
+}
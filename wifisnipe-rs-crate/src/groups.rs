@@ -0,0 +1,282 @@
+//! Regroupement des adresses MAC aléatoires en appareils physiques probables
+//!
+//! Un téléphone change régulièrement d'adresse MAC aléatoire et apparaît comme plusieurs appareils.
+//! Les adresses aléatoires (voir [`crate::oui::is_randomized()`]) sont reliées deux à deux à partir
+//! de l'historique des observations :
+//!
+//! | Indice | Score |
+//! | ------ | ----- |
+//! | Empreinte d'IEs commune ([`crate::fingerprint`]) | `+0.5` |
+//! | SSIDs communs | `+0.3` × indice de Jaccard des SSIDs |
+//! | Numéros de séquence 802.11 continus entre la dernière trame de l'une et la première de l'autre | `+0.3` |
+//! | L'une apparaît moins de [`GroupConfig::max_gap`] secondes après la disparition de l'autre | `+0.1` |
+//! | Les deux émettent en même temps pendant plus de [`GroupConfig::max_overlap`] secondes | score × `0.3` |
+//!
+//! Les paires dont le score atteint [`GroupConfig::threshold`] sont reliées, et les groupes sont les
+//! composantes connexes de ces liens. La confiance d'un groupe est le score de son lien le plus faible
+//! (`1.0` pour une adresse seule). Les adresses non aléatoires forment toujours un groupe à elles seules.
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use crate::{observation_version, observations, oui, Observation};
+
+/// Nombre de numéros de séquence 802.11 (12 bits)
+const SN_MODULO: u16 = 4096;
+
+lazy_static! {
+    /// Derniers groupes calculés, avec la version de l'historique et les paramètres utilisés
+    static ref CACHE: Mutex<Option<(u64, GroupConfig, Vec<DeviceGroup>)>> = Mutex::new(None);
+}
+
+/// Paramètres du regroupement
+///
+/// Utiliser [`GroupConfig::default()`] pour les valeurs par défaut
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupConfig {
+    /// Score minimal pour relier deux adresses (défaut : `0.5`)
+    pub threshold: f64,
+    /// Secondes maximales entre deux adresses successives (défaut : `60`)
+    pub max_gap: i64,
+    /// Secondes d'émission simultanée tolérées (défaut : `10`)
+    pub max_overlap: i64,
+    /// Écart maximal entre numéros de séquence 802.11 successifs (défaut : `64`)
+    pub sn_window: u16,
+}
+
+impl Default for GroupConfig {
+    fn default() -> Self {
+        GroupConfig {
+            threshold: 0.5,
+            max_gap: 60,
+            max_overlap: 10,
+            sn_window: 64,
+        }
+    }
+}
+
+/// Appareil physique probable
+///
+/// Champs:
+/// | Champ          | Type            | Description                                  |
+/// |----------------|-----------------|----------------------------------------------|
+/// | `id`           | `String`        | Première adresse MAC vue du groupe            |
+/// | `macs`         | `Array<String>` | Adresses MAC du groupe, de la plus ancienne à la plus récente |
+/// | `confidence`   | `float`         | Confiance du regroupement (`0` à `1`)        |
+/// | `first_seen`   | `int`           | UNIX Timestamp (UTC) de la première observation |
+/// | `last_seen`    | `int`           | UNIX Timestamp (UTC) de la dernière observation |
+/// | `ssids`        | `Array<String>` | SSIDs de toutes les adresses du groupe       |
+/// | `fingerprints` | `Array<String>` | Empreintes d'IEs de toutes les adresses du groupe |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    /// Identifiant du groupe
+    pub id: String,
+    /// Adresses MAC
+    pub macs: Vec<String>,
+    /// Confiance
+    pub confidence: f64,
+    /// Première observation
+    pub first_seen: i64,
+    /// Dernière observation
+    pub last_seen: i64,
+    /// SSIDs
+    pub ssids: Vec<String>,
+    /// Empreintes d'IEs
+    pub fingerprints: Vec<String>,
+}
+
+// Résumé des observations d'une adresse
+struct Summary {
+    mac: String,
+    first_seen: i64,
+    last_seen: i64,
+    first_sn: Option<u16>,
+    last_sn: Option<u16>,
+    ssids: BTreeSet<String>,
+    fingerprints: BTreeSet<String>,
+}
+
+impl Summary {
+    fn new(obs: &Observation) -> Self {
+        Summary {
+            mac: obs.mac.clone(),
+            first_seen: obs.ts,
+            last_seen: obs.ts,
+            first_sn: obs.sn,
+            last_sn: obs.sn,
+            ssids: BTreeSet::new(),
+            fingerprints: BTreeSet::new(),
+        }
+    }
+
+    fn add(&mut self, obs: &Observation) {
+        self.last_seen = obs.ts;
+        if obs.sn.is_some() {
+            self.first_sn = self.first_sn.or(obs.sn);
+            self.last_sn = obs.sn;
+        }
+        if !obs.ssid.is_empty() {
+            self.ssids.insert(obs.ssid.clone());
+        }
+        if !obs.fingerprint.is_empty() {
+            self.fingerprints.insert(obs.fingerprint.clone());
+        }
+    }
+}
+
+// Score de la paire (a, b), a étant apparue en premier
+fn score(a: &Summary, b: &Summary, config: &GroupConfig) -> f64 {
+    let mut score = 0.0;
+    if !a.fingerprints.is_disjoint(&b.fingerprints) {
+        score += 0.5;
+    }
+    let union = a.ssids.union(&b.ssids).count();
+    if union > 0 {
+        score += 0.3 * a.ssids.intersection(&b.ssids).count() as f64 / union as f64;
+    }
+    let gap = b.first_seen - a.last_seen;
+    if gap >= 0 {
+        if gap <= config.max_gap {
+            score += 0.1;
+        }
+        if let (Some(last), Some(first)) = (a.last_sn, b.first_sn) {
+            let delta = (first + SN_MODULO - last) % SN_MODULO;
+            if delta > 0 && delta <= config.sn_window {
+                score += 0.3;
+            }
+        }
+    } else if a.last_seen.min(b.last_seen) - b.first_seen > config.max_overlap {
+        // Deux appareils distincts émettent en même temps
+        score *= 0.3;
+    }
+    score
+}
+
+// Racine d'une adresse dans l'union-find
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Regroupe les adresses MAC de l'historique avec les paramètres par défaut
+pub fn device_groups() -> Vec<DeviceGroup> {
+    device_groups_with(&GroupConfig::default())
+}
+
+/// Regroupe les adresses MAC de l'historique
+///
+/// Les groupes sont triés par première observation. Ils ne sont recalculés que si l'historique
+/// ou les paramètres ont changé depuis le dernier appel
+pub fn device_groups_with(config: &GroupConfig) -> Vec<DeviceGroup> {
+    // Version lue avant l'historique : un ajout pendant le calcul invalide le résultat
+    let version = observation_version();
+    if let Some((cached, cached_config, groups)) = CACHE.lock().unwrap().as_ref() {
+        if *cached == version && cached_config == config {
+            return groups.clone();
+        }
+    }
+    let groups = compute(&observations(), config);
+    *CACHE.lock().unwrap() = Some((version, config.clone(), groups.clone()));
+    groups
+}
+
+// Regroupe les adresses MAC des observations
+fn compute(observations: &[Observation], config: &GroupConfig) -> Vec<DeviceGroup> {
+    // Résumé par adresse, dans l'ordre d'apparition
+    let mut summaries: Vec<Summary> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for obs in observations.iter().filter(|obs| !obs.mac.is_empty()) {
+        match index.get(&obs.mac) {
+            Some(i) => summaries[*i].add(obs),
+            None => {
+                index.insert(obs.mac.clone(), summaries.len());
+                let mut summary = Summary::new(obs);
+                summary.add(obs);
+                summaries.push(summary);
+            }
+        }
+    }
+
+    // Liens entre adresses aléatoires
+    let mut parent: Vec<usize> = (0..summaries.len()).collect();
+    let mut confidence: Vec<f64> = vec![1.0; summaries.len()];
+    let randomized: Vec<usize> = (0..summaries.len())
+        .filter(|i| oui::is_randomized(&summaries[*i].mac))
+        .collect();
+    for (n, a) in randomized.iter().enumerate() {
+        for b in &randomized[n + 1..] {
+            let score = score(&summaries[*a], &summaries[*b], config);
+            if score < config.threshold {
+                continue;
+            }
+            let (root_a, root_b) = (find(&mut parent, *a), find(&mut parent, *b));
+            let weakest = confidence[root_a].min(confidence[root_b]).min(score);
+            if root_a != root_b {
+                // La racine reste l'adresse la plus ancienne
+                let (root, child) = (root_a.min(root_b), root_a.max(root_b));
+                parent[child] = root;
+                confidence[root] = weakest;
+            }
+        }
+    }
+
+    let mut groups: Vec<DeviceGroup> = Vec::new();
+    let mut group_index: HashMap<usize, usize> = HashMap::new();
+    for i in 0..summaries.len() {
+        let root = find(&mut parent, i);
+        let summary = &summaries[i];
+        let group = match group_index.get(&root) {
+            Some(g) => &mut groups[*g],
+            None => {
+                group_index.insert(root, groups.len());
+                groups.push(DeviceGroup {
+                    id: summaries[root].mac.clone(),
+                    macs: Vec::new(),
+                    confidence: (confidence[root] * 100.0).round() / 100.0,
+                    first_seen: summary.first_seen,
+                    last_seen: summary.last_seen,
+                    ssids: Vec::new(),
+                    fingerprints: Vec::new(),
+                });
+                groups.last_mut().unwrap()
+            }
+        };
+        group.macs.push(summary.mac.clone());
+        group.first_seen = group.first_seen.min(summary.first_seen);
+        group.last_seen = group.last_seen.max(summary.last_seen);
+        for ssid in &summary.ssids {
+            if !group.ssids.contains(ssid) {
+                group.ssids.push(ssid.clone());
+            }
+        }
+        for fingerprint in &summary.fingerprints {
+            if !group.fingerprints.contains(fingerprint) {
+                group.fingerprints.push(fingerprint.clone());
+            }
+        }
+    }
+    groups
+}
+
+/// Groupe contenant une adresse MAC (insensible à la casse)
+pub fn group_of(mac: &str) -> Option<DeviceGroup> {
+    device_groups()
+        .into_iter()
+        .find(|group| group.macs.iter().any(|m| m.eq_ignore_ascii_case(mac)))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Bundle des appareils physiques probables pour la génération d'un fichier JSON (voir [`DeviceGroup`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_device_groups<'a>() -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&device_groups()).unwrap_or(String::from("")))
+}
@@ -0,0 +1,522 @@
+//! API HTTP servant les données enregistrées en JSON
+//!
+//! Disponible avec la feature `http`
+//!
+//! ## Routes
+//!
+//! | Route | Description |
+//! | ----- | ----------- |
+//! | `GET /devices` | Tous les appareils ([`Data`]) |
+//! | `GET /devices/{mac}` | Un appareil ([`Data`]), `404` si inconnu |
+//! | `GET /devices/{mac}/history` | Observations d'un appareil ([`Observation`]) |
+//! | `GET /locations` | Positions estimées des appareils ([`Location`]) |
+//! | `GET /locations.csv` | Positions estimées au format CSV |
+//! | `GET /alerts` | Alertes de la liste de surveillance ([`Alert`]) |
+//! | `GET /zones` | Zone de proximité actuelle des appareils ([`DeviceZone`]) |
+//! | `GET /occupancy` | Série d'occupation ([`OccupancyPoint`]) |
+//! | `GET /occupancy.csv` | Série d'occupation au format CSV |
+//! | `GET /sessions` | Sessions de présence ([`Session`]), triées par début |
+//! | `GET /ssids` | SSIDs recherchés, les plus recherchés en premier ([`SsidStats`]) |
+//! | `GET /ssids.dot` | Graphe appareils–SSIDs au format DOT (voir [`crate::ssids`]) |
+//! | `GET /ssids.graphml` | Graphe appareils–SSIDs au format GraphML |
+//! | `GET /channels` | Trames par canal et par fenêtre d'activité ([`ChannelWindow`]) |
+//! | `GET /channels/schedule` | Parcours des canaux recommandé ([`ChannelDwell`]) |
+//! | `GET /stats` | Statistiques de capture ([`Stats`]) |
+//! | `GET /metrics` | Métriques au format texte Prometheus (voir [`crate::metrics`]) |
+//! | `GET /stream` | Flux [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html) des évènements ([`Event`]) |
+//!
+//! ## Paramètres de requête
+//!
+//! Tous optionnels, ignorés par `/stats`
+//!
+//! | Paramètre | Description |
+//! | --------- | ----------- |
+//! | `since` | UNIX Timestamp (UTC) minimal, inclus |
+//! | `until` | UNIX Timestamp (UTC) maximal, inclus |
+//! | `mac` | Préfixe de l'adresse MAC (insensible à la casse) |
+//! | `ssid` | SSID broadcasté |
+//! | `min_rssi` | RSSI minimal |
+//! | `sensor` | Nom du capteur (`/zones` et changements de zone de `/stream` uniquement) |
+//!
+//! Pour `/devices`, la fenêtre de temps s'applique à la dernière fois où l'appareil a été vu.
+//!
+//! Pour `/ssids`, seuls `since`, `until` (dernière trame du SSID) et `ssid` s'appliquent.
+//!
+//! Pour `/locations`, seuls `since`, `until` (dernière mesure) et `mac` s'appliquent.
+//!
+//! Pour `/sessions`, une session est retenue si elle chevauche la fenêtre de temps,
+//! `min_rssi` s'applique au RSSI maximal et `ssid` est ignoré.
+//!
+//! Pour `/ssids.dot` et `/ssids.graphml`, seul `min_devices` s'applique : SSIDs recherchés par
+//! au moins ce nombre d'appareils (défaut : `1`, `2` pour les SSIDs partagés).
+//!
+//! Pour `/channels`, seuls `since` et `until` s'appliquent : fenêtres chevauchant la période.
+//!
+//! Pour `/channels/schedule`, les paramètres sont ceux de [`ScheduleConfig`] (`window`,
+//! `cycle_ms`, `min_dwell_ms`).
+//!
+//! Pour `/occupancy`, les paramètres sont ceux de [`OccupancyQuery`] (`bucket=minute|hour`,
+//! `since`, `until`, `min_rssi`, `randomized=count|exclude|group`).
+//!
+//! Pour `/stream`, les filtres s'appliquent à l'observation, la session, le changement de zone
+//! ou l'alerte porté par chaque évènement (`min_rssi` s'applique au RSSI lissé du changement de zone),
+//! le nom de l'évènement SSE est le champ `type` du JSON.
+//!
+//! ## Test local
+//!
+//! ```ps1
+//! cd esp-sniffer\wifisnipe-rs
+//! cargo run -- http --replay capture.txt
+//! curl "http://127.0.0.1:8080/devices?min_rssi=-60"
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use axum::extract::{Path, Query};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::stream::{self, Stream, StreamExt};
+use interoptopus::ffi_function;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::channels::{self, ChannelDwell, ChannelWindow, ScheduleConfig};
+use crate::events::{subscribe, Event};
+use crate::localization::{self, Location};
+use crate::occupancy::{occupancy, to_csv, OccupancyPoint, OccupancyQuery};
+use crate::sensor::{self, SensorStatus};
+use crate::sessions::{sessions, Session};
+use crate::ssids::{self, GraphFormat, SsidStats};
+use crate::watchlist::{self, Alert};
+use crate::zones::{self, DeviceZone, ZoneChange};
+use crate::{
+    channel_activity, device, frame_count, history, is_started, observation_count, serial_error,
+    snapshot, Data, Observation,
+};
+use crate::{clock, metrics};
+
+lazy_static! {
+    /// Erreur du dernier serveur arrêté
+    static ref SERVER_ERROR: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Filtres communs aux routes
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Filter {
+    /// UNIX Timestamp (UTC) minimal, inclus
+    pub since: Option<i64>,
+    /// UNIX Timestamp (UTC) maximal, inclus
+    pub until: Option<i64>,
+    /// Préfixe de l'adresse MAC
+    pub mac: Option<String>,
+    /// SSID broadcasté
+    pub ssid: Option<String>,
+    /// RSSI minimal
+    pub min_rssi: Option<i32>,
+    /// Nom du capteur
+    pub sensor: Option<String>,
+}
+
+impl Filter {
+    fn in_window(&self, ts: i64) -> bool {
+        self.since.map_or(true, |since| ts >= since) && self.until.map_or(true, |until| ts <= until)
+    }
+
+    fn sensor_matches(&self, sensor: &str) -> bool {
+        self.sensor.as_ref().map_or(true, |name| name == sensor)
+    }
+
+    fn mac_matches(&self, mac: &str) -> bool {
+        self.mac.as_ref().map_or(true, |prefix| {
+            mac.to_lowercase().starts_with(&prefix.to_lowercase())
+        })
+    }
+
+    /// Vérifie qu'un appareil correspond au filtre
+    pub fn matches_device(&self, data: &Data) -> bool {
+        self.in_window(data.ts)
+            && self.mac_matches(&data.mac)
+            && self.min_rssi.map_or(true, |min| data.rssi >= min)
+            && self
+                .ssid
+                .as_ref()
+                .map_or(true, |ssid| data.ssids.contains(ssid))
+    }
+
+    /// Vérifie qu'une observation correspond au filtre
+    pub fn matches_observation(&self, obs: &Observation) -> bool {
+        self.in_window(obs.ts)
+            && self.mac_matches(&obs.mac)
+            && self.min_rssi.map_or(true, |min| obs.rssi >= min)
+            && self.ssid.as_ref().map_or(true, |ssid| obs.ssid == *ssid)
+    }
+
+    /// Vérifie qu'un changement de zone correspond au filtre
+    pub fn matches_zone(&self, change: &ZoneChange) -> bool {
+        self.in_window(change.ts)
+            && self.mac_matches(&change.mac)
+            && self.sensor_matches(&change.sensor)
+            && self.min_rssi.map_or(true, |min| change.rssi >= min as f64)
+    }
+
+    /// Vérifie qu'une alerte correspond au filtre
+    pub fn matches_alert(&self, alert: &Alert) -> bool {
+        self.in_window(alert.ts)
+            && self.mac_matches(&alert.mac)
+            && self.min_rssi.map_or(true, |min| alert.rssi >= min)
+            && self.ssid.as_ref().map_or(true, |ssid| alert.ssid == *ssid)
+    }
+
+    /// Vérifie qu'une session correspond au filtre
+    pub fn matches_session(&self, session: &Session) -> bool {
+        self.since.map_or(true, |since| session.end >= since)
+            && self.until.map_or(true, |until| session.start <= until)
+            && self.mac_matches(&session.mac)
+            && self.min_rssi.map_or(true, |min| session.peak_rssi >= min)
+    }
+}
+
+/// Paramètre des graphes appareils–SSIDs
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GraphQuery {
+    /// Nombre minimal d'appareils par SSID
+    pub min_devices: Option<usize>,
+}
+
+/// Statistiques de capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    /// Enregistrement en cours
+    pub started: bool,
+    /// Dernière erreur du port série ou de la relecture
+    pub error: Option<String>,
+    /// Nombre total de trames traitées
+    pub frames: u64,
+    /// Nombre d'appareils connus
+    pub devices: usize,
+    /// Nombre d'observations dans l'historique
+    pub observations: usize,
+    /// Nombre de trames par canal
+    pub channels: BTreeMap<u32, u64>,
+    /// Dernier état reçu de la carte
+    pub sensor: Option<SensorStatus>,
+    /// Trames perdues entre la carte et le PC
+    pub lost: u64,
+    /// Décalage estimé (ms) entre l'horloge de la carte et l'heure UNIX du PC
+    pub clock_offset: Option<i64>,
+}
+
+/// Construit le routeur de l'API
+pub fn router() -> Router {
+    Router::new()
+        .route("/devices", get(get_devices))
+        .route("/devices/{mac}", get(get_device))
+        .route("/devices/{mac}/history", get(get_history))
+        .route("/locations", get(get_locations))
+        .route("/locations.csv", get(get_locations_csv))
+        .route("/alerts", get(get_alerts))
+        .route("/zones", get(get_zones))
+        .route("/occupancy", get(get_occupancy))
+        .route("/occupancy.csv", get(get_occupancy_csv))
+        .route("/sessions", get(get_sessions))
+        .route("/ssids", get(get_ssids))
+        .route("/ssids.dot", get(get_ssids_dot))
+        .route("/ssids.graphml", get(get_ssids_graphml))
+        .route("/channels", get(get_channels))
+        .route("/channels/schedule", get(get_channel_schedule))
+        .route("/stats", get(get_stats))
+        .route("/metrics", get(get_metrics))
+        .route("/stream", get(get_stream))
+}
+
+/// Démarre dans un thread un serveur ne servant que `/metrics` sur l'adresse donnée
+///
+/// Retourne l'adresse effectivement écoutée (utile avec le port `0`)
+pub fn serve_metrics(addr: &str) -> io::Result<SocketAddr> {
+    spawn(addr, Router::new().route("/metrics", get(get_metrics)))
+}
+
+/// Démarre l'API dans un thread sur l'adresse donnée (ex. `127.0.0.1:8080`)
+///
+/// Retourne l'adresse effectivement écoutée (utile avec le port `0`)
+pub fn serve(addr: &str) -> io::Result<SocketAddr> {
+    spawn(addr, router())
+}
+
+/// Erreur ayant arrêté un serveur démarré par [`serve()`] ou [`serve_metrics()`], `None` si
+/// tous fonctionnent
+pub fn server_error() -> Option<String> {
+    SERVER_ERROR.lock().unwrap().clone()
+}
+
+// Ouvre le port et lance le serveur dans un thread
+//
+// Les erreurs de démarrage sont retournées, celles du serveur en marche sont conservées pour
+// `server_error()`
+fn spawn(addr: &str, router: Router) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+    let (ready_tx, ready_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let started = tokio::runtime::Runtime::new().and_then(|runtime| {
+            let listener = {
+                let _context = runtime.enter();
+                tokio::net::TcpListener::from_std(listener)?
+            };
+            Ok((runtime, listener))
+        });
+        let (runtime, listener) = match started {
+            Ok(started) => {
+                let _ = ready_tx.send(Ok(()));
+                started
+            }
+            Err(err) => {
+                let _ = ready_tx.send(Err(err));
+                return;
+            }
+        };
+        if let Err(err) = runtime.block_on(async { axum::serve(listener, router).await }) {
+            *SERVER_ERROR.lock().unwrap() = Some(format!("{local_addr} : {err}"));
+        }
+    });
+    ready_rx
+        .recv()
+        .unwrap_or_else(|_| Err(io::Error::other("thread du serveur arrêté")))?;
+    Ok(local_addr)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Fonction publique pour démarrer l'API HTTP sur `127.0.0.1`
+///
+/// Paramètres :
+///
+/// | Nom du paramètre | Usage |
+/// | ---------------- | ----- |
+/// | `port` | Port TCP d'écoute |
+///
+/// Retourne
+///   - `true` : Si l'API a démarré
+///   - `false` : Si le port n'a pas pu être ouvert
+pub extern "C" fn start_http(port: u16) -> bool {
+    serve(&format!("127.0.0.1:{port}")).is_ok()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Fonction publique pour démarrer l'export des métriques Prometheus sur `127.0.0.1:<port>/metrics`
+///
+/// Paramètres :
+///
+/// | Nom du paramètre | Usage |
+/// | ---------------- | ----- |
+/// | `port` | Port TCP d'écoute |
+///
+/// Retourne
+///   - `true` : Si l'export a démarré
+///   - `false` : Si le port n'a pas pu être ouvert
+pub extern "C" fn start_metrics(port: u16) -> bool {
+    serve_metrics(&format!("127.0.0.1:{port}")).is_ok()
+}
+
+async fn get_devices(Query(filter): Query<Filter>) -> Json<Vec<Data>> {
+    Json(
+        snapshot()
+            .into_iter()
+            .filter(|data| filter.matches_device(data))
+            .collect(),
+    )
+}
+
+async fn get_device(Path(mac): Path<String>) -> Result<Json<Data>, StatusCode> {
+    device(&mac).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_history(
+    Path(mac): Path<String>,
+    Query(filter): Query<Filter>,
+) -> Result<Json<Vec<Observation>>, StatusCode> {
+    let observations = history(&mac);
+    if observations.is_empty() && device(&mac).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(
+        observations
+            .into_iter()
+            .filter(|obs| filter.matches_observation(obs))
+            .collect(),
+    ))
+}
+
+async fn get_locations(Query(filter): Query<Filter>) -> Json<Vec<Location>> {
+    Json(filtered_locations(&filter))
+}
+
+async fn get_locations_csv(
+    Query(filter): Query<Filter>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        localization::to_csv(&filtered_locations(&filter)),
+    )
+}
+
+// Positions dans la fenêtre de temps et correspondant au préfixe MAC
+fn filtered_locations(filter: &Filter) -> Vec<Location> {
+    localization::locations()
+        .into_iter()
+        .filter(|location| filter.in_window(location.ts) && filter.mac_matches(&location.mac))
+        .collect()
+}
+
+async fn get_alerts(Query(filter): Query<Filter>) -> Json<Vec<Alert>> {
+    Json(
+        watchlist::alerts(filter.since.unwrap_or(0))
+            .into_iter()
+            .filter(|alert| filter.matches_alert(alert))
+            .collect(),
+    )
+}
+
+async fn get_zones(Query(filter): Query<Filter>) -> Json<Vec<DeviceZone>> {
+    Json(
+        zones::zones(filter.sensor.as_deref())
+            .into_iter()
+            .filter(|zone| filter.mac_matches(&zone.mac))
+            .collect(),
+    )
+}
+
+async fn get_occupancy(Query(query): Query<OccupancyQuery>) -> Json<Vec<OccupancyPoint>> {
+    Json(occupancy(&query))
+}
+
+async fn get_occupancy_csv(
+    Query(query): Query<OccupancyQuery>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        to_csv(&occupancy(&query)),
+    )
+}
+
+async fn get_sessions(Query(filter): Query<Filter>) -> Json<Vec<Session>> {
+    Json(
+        sessions(None, i64::MIN, i64::MAX)
+            .into_iter()
+            .filter(|session| filter.matches_session(session))
+            .collect(),
+    )
+}
+
+async fn get_ssids(Query(filter): Query<Filter>) -> Json<Vec<SsidStats>> {
+    Json(
+        ssids::ssids()
+            .into_iter()
+            .filter(|stats| {
+                filter.in_window(stats.last_seen)
+                    && filter
+                        .ssid
+                        .as_ref()
+                        .map_or(true, |ssid| &stats.ssid == ssid)
+            })
+            .collect(),
+    )
+}
+
+async fn get_ssids_dot(
+    Query(query): Query<GraphQuery>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")],
+        ssids::graph(GraphFormat::Dot, query.min_devices.unwrap_or(1)),
+    )
+}
+
+async fn get_ssids_graphml(
+    Query(query): Query<GraphQuery>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "application/graphml+xml; charset=utf-8",
+        )],
+        ssids::graph(GraphFormat::GraphMl, query.min_devices.unwrap_or(1)),
+    )
+}
+
+async fn get_channels(Query(filter): Query<Filter>) -> Json<Vec<ChannelWindow>> {
+    Json(
+        channels::windows(filter.since.unwrap_or(i64::MIN))
+            .into_iter()
+            .filter(|window| filter.until.map_or(true, |until| window.start <= until))
+            .collect(),
+    )
+}
+
+async fn get_channel_schedule(Query(config): Query<ScheduleConfig>) -> Json<Vec<ChannelDwell>> {
+    Json(channels::recommend(&config))
+}
+
+async fn get_stats() -> Json<Stats> {
+    Json(Stats {
+        started: is_started(),
+        error: serial_error(),
+        frames: frame_count(),
+        devices: snapshot().len(),
+        observations: observation_count(),
+        channels: channel_activity().into_iter().collect(),
+        sensor: sensor::status(),
+        lost: clock::frames_lost(),
+        clock_offset: clock::offset(),
+    })
+}
+
+async fn get_metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics::render(),
+    )
+}
+
+async fn get_stream(
+    Query(filter): Query<Filter>,
+) -> Sse<impl Stream<Item = Result<sse::Event, Infallible>>> {
+    let events = stream::unfold(subscribe(), |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((event, rx)),
+                // Client trop lent : les évènements perdus sont ignorés
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = events
+        .filter(move |event: &Event| {
+            std::future::ready(match event {
+                Event::Observation(obs) | Event::NewDevice(obs) => filter.matches_observation(obs),
+                Event::Arrive(session) | Event::Leave(session) => filter.matches_session(session),
+                Event::Zone(change) => filter.matches_zone(change),
+                Event::Alert(alert) => filter.matches_alert(alert),
+            })
+        })
+        .map(|event| {
+            Ok(sse::Event::default()
+                .event(event.name())
+                .json_data(&event)
+                .unwrap_or_default())
+        });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
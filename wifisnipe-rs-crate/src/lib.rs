@@ -9,6 +9,8 @@
 //! Si le SSID est présent
 //! - `\u{2}canal\u{31}mac\u{31}rssi\{31}ssid\{31}\u{3}`
 //!
+//! Ces champs peuvent aussi arriver au format version 2, avec nombre de champs et CRC (voir [`frame`])
+//!
 //! ## Partie NodeMCU
 //! 
 //! Voir [README.md](../../../../README.md)
@@ -61,16 +63,77 @@
 //!
 //! ### Utilisation
 //!
+//! Les chaînes retournées (`const char *`) appartiennent à la librairie : elles restent valides
+//! jusqu'au prochain appel, sur le même thread, d'une fonction retournant une chaîne.
+//!
 //! Vous pouvez utiliser le pseudo-code suivant comme base :
 //!
 //! ```lua
 //! load_dll()
+//! # Optionnel, avant le démarrage : capteur, conservation, confidentialité, sorties et alertes
+//! load_config("wifisnipe.toml")
+//! # Optionnel, avant le démarrage : adresses MAC pseudonymisées chaque jour, SSIDs tronqués à 4 caractères
+//! set_privacy("secret du site", true, 2, 4)
 //! # Démarrage de l'enregistrement
 //! start(<Numéro du port COM (entier sans COM)>)
 //! # Dernier appareil enregistré
 //! get_data_last()
 //! # Toute les données enregistrées
 //! get_data_all()
+//! # Optionnel : lissage de Kalman et calibration de la distance (RSSI à 1 m, exposant)
+//! set_rssi_kalman(1.0, 16.0)
+//! set_distance_model(-42.0, 2.5)
+//! # Optionnel : localisation avec plusieurs capteurs placés en mètres
+//! set_local_sensor("salle1")
+//! set_sensor_position("salle1", 0.0, 0.0)
+//! set_sensor_position("salle2", 8.0, 0.0)
+//! add_reading("salle2", "da:a1:19:00:00:01", 1700000000, -67)
+//! get_locations()
+//! # Zones de proximité : proche au-dessus de -50 dBm, moyenne au-dessus de -75 dBm, marge de 4 dB
+//! set_zone_thresholds(-50, -75, 4)
+//! get_zones("salle2")
+//! get_zone_changes(0)
+//! # Pseudonyme du jour d'une adresse MAC connue (mode confidentialité)
+//! get_pseudonym("da:a1:19:00:00:01")
+//! # Alertes : liste de surveillance, une alerte par appareil et par règle toutes les 10 minutes
+//! load_watchlist("watchlist.txt")
+//! add_watch("oui", "a4:5e:60", "apple")
+//! set_alert_cooldown(600)
+//! set_alert_log("alerts.jsonl")
+//! get_alerts(0)
+//! # Dernier état de la carte (uptime, canal, mémoire libre, version)
+//! get_sensor_status()
+//! # Optionnel : capturer aussi les beacons, puis lister les points d'accès
+//! set_capture_mode(2)
+//! get_aps_all()
+//! # Parcourir uniquement les canaux 1, 6 et 11
+//! set_channel_list("1,6,11")
+//! # Trames par canal et par minute, parcours pondéré par l'activité des 10 dernières minutes
+//! get_channel_activity(0)
+//! get_hop_schedule(600, 65000, 1000)
+//! push_hop_schedule(0, 0, 0)
+//! # Optionnel : recalculer et envoyer le parcours toutes les 5 minutes
+//! set_adaptive_hopping(300, 0, 0, 0)
+//! # Adresses MAC aléatoires regroupées par appareil probable
+//! get_device_groups()
+//! # Sessions de présence d'un appareil (adresse vide : tous) et durées de présence
+//! get_sessions("da:a1:19:00:00:01", 0, 0)
+//! get_dwell_stats("", 0, 0)
+//! # Appareils distincts par heure au-dessus de -70 dBm, adresses aléatoires regroupées, en CSV
+//! get_occupancy_csv(1, 0, 0, -70, 2)
+//! # SSIDs les plus recherchés, graphe GraphML des SSIDs recherchés par au moins 2 appareils
+//! get_ssids_all()
+//! get_ssid_graph(1, 2)
+//! # Optionnel (feature webhook) : alertes, état de la carte et résumé horaire par POST
+//! start_webhook("https://exemple.fr/alertes", "outbox.jsonl", 3600)
+//! # Conservation des données pendant 30 jours, effacement d'un appareil, journal d'audit
+//! set_retention(2592000)
+//! set_erasure_audit_log("audit.jsonl")
+//! set_erasure_audit_key("secret d'audit")
+//! erase_device("da:a1:19:00:00:01")
+//! get_audit_log(0)
+//! # Optionnel (feature http) : API HTTP sur le port 8080
+//! start_http(8080)
 //! # Arrêter l'enregistrement
 //! stop()
 //! ```
@@ -86,23 +149,69 @@ extern crate futures;
 use chrono::{DateTime, Utc};
 use interoptopus::patterns::string::*;
 use interoptopus::{ffi_function, function, Inventory, InventoryBuilder};
-use std::collections::HashMap;
-use std::io;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::mem::MaybeUninit;
 use std::str;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::SystemTime;
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
+use futures::sink::SinkExt;
 use futures::stream::StreamExt;
 use regex::Regex;
 use ringbuf::{Consumer, HeapRb, SharedRb};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use tokio_serial::SerialPortBuilderExt;
 use tokio_util::codec::{Decoder, Encoder};
 
+use command::Command;
+use events::Event;
+use frame::FrameError;
+
+pub mod ap;
+pub mod channels;
+pub mod clock;
+pub mod command;
+pub mod config;
+pub mod events;
+pub mod fingerprint;
+pub mod frame;
+pub mod groups;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod localization;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod occupancy;
+pub mod oui;
+pub mod privacy;
+pub mod retention;
+pub mod rssi;
+pub mod sensor;
+pub mod sessions;
+pub mod ssids;
+pub mod watchlist;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+pub mod zones;
+
+/// Débit par défaut du port série (bauds)
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+/// Capacité par défaut de la file d'attente entre la lecture du port série et le traitement
+pub const QUEUE_CAPACITY: usize = 255;
+
+/// Nombre maximal d'observations conservées dans l'historique
+pub const OBSERVATION_CAPACITY: usize = 100_000;
+
 // Toutes les instantations globales
 lazy_static! {
     /// Tableau des adresses MAC
@@ -120,6 +229,11 @@ lazy_static! {
         let s = HashMap::new();
         Arc::new(Mutex::new(s))
     };
+    /// HashMap avec les empreintes d'IEs récupérées
+    static ref FINGERPRINTS: Arc<Mutex<HashMap<String, Vec<String>>>> = {
+        let f = HashMap::new();
+        Arc::new(Mutex::new(f))
+    };
     /// HashMap avec le dernier RSSI
     static ref RSSIS: Arc<Mutex<HashMap<String, i32>>> = {
         let r = HashMap::new();
@@ -130,12 +244,34 @@ lazy_static! {
         let ts = HashMap::new();
         Arc::new(Mutex::new(ts))
     };
+    /// Historique des observations, de la plus ancienne à la plus récente
+    static ref OBSERVATIONS: Arc<Mutex<VecDeque<Observation>>> = {
+        let obs = VecDeque::new();
+        Arc::new(Mutex::new(obs))
+    };
+    /// HashMap avec le nombre de trames reçues par canal
+    static ref CHANNEL_FRAMES: Arc<Mutex<HashMap<u32, u64>>> = {
+        let cf = HashMap::new();
+        Arc::new(Mutex::new(cf))
+    };
+    /// Dernière erreur rencontrée sur le port série ou le fichier de relecture
+    static ref SERIAL_ERROR: Mutex<Option<String>> = Mutex::new(None);
+    /// Nombre total de trames traitées
+    static ref FRAME_COUNT: AtomicU64 = AtomicU64::new(0);
+    /// Version de l'historique des observations, incrémentée à chaque modification
+    static ref OBSERVATION_VERSION: AtomicU64 = AtomicU64::new(0);
     /// Regex pour la vérification syntaxique de l'addresse MAC
     static ref MAC_REGEX: Regex =
         Regex::new(r"^([0-9A-Fa-f]{2}[:-]){5}([0-9A-Fa-f]{2})$").unwrap();
     static ref STARTED: AtomicBool = AtomicBool::new(false);
     // Signal de stop
     static ref STOP: AtomicBool = AtomicBool::new(false);
+    /// Débit du port série (bauds)
+    static ref BAUD_RATE: AtomicU32 = AtomicU32::new(DEFAULT_BAUD_RATE);
+    /// Capacité de la file d'attente
+    static ref QUEUE_SIZE: AtomicUsize = AtomicUsize::new(QUEUE_CAPACITY);
+    /// RSSI retenu quand celui d'une trame est illisible
+    static ref DEFAULT_RSSI: AtomicI32 = AtomicI32::new(0);
 }
 
 // Structure utilisée pour scinder les informations reçues par lignes
@@ -151,7 +287,7 @@ impl Decoder for LineCodec {
             let line = src.split_to(n + 1);
             return match str::from_utf8(line.as_ref()) {
                 Ok(s) => Ok(Some(s.to_string())),
-                Err(_) => Err(io::Error::new(io::ErrorKind::Other, "Invalid String")),
+                Err(_) => Err(io::Error::other("Invalid String")),
             };
         }
         Ok(None)
@@ -161,10 +297,21 @@ impl Decoder for LineCodec {
 impl Encoder<String> for LineCodec {
     type Error = io::Error;
 
-    fn encode(&mut self, _item: String, _dst: &mut BytesMut) -> Result<(), Self::Error> {
+    fn encode(&mut self, item: String, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(item.len() + 1);
+        dst.put(item.as_bytes());
+        dst.put_u8(b'\n');
         Ok(())
     }
 }
+
+impl Encoder<Command> for LineCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Command, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(item.to_frame(), dst)
+    }
+}
 /// Structure de données pour la sérialisation en JSON
 ///
 /// Champs:
@@ -175,46 +322,126 @@ impl Encoder<String> for LineCodec {
 /// | `rssi`     | `int`           | RSSI                                  |
 /// | `channels` | `Array<int>`    | Canaux où le périphérique a été vu    |
 /// | `ssid`     | `Array<String>` | SSIDs broadcastés par le périphérique |
+/// | `fingerprints` | `Array<String>` | Empreintes d'IEs du périphérique ([`fingerprint`]) |
+/// | `rssi_smoothed` | `float` | RSSI lissé ([`rssi`]) |
+/// | `distance` | `float` | Distance estimée en mètres à partir du RSSI lissé |
 ///
 #[repr(C)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Data {
-    // Addresse MAC
-    mac: String,
-    // UNIX Timestamp (UTC)
-    ts: i64,
-    // RSSI
-    rssi: i32,
-    // Tableau de canaux
-    channels: Vec<u32>,
-    // Tableau des SSIDs
-    ssids: Vec<String>,
+    /// Addresse MAC
+    pub mac: String,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// RSSI
+    pub rssi: i32,
+    /// Tableau de canaux
+    pub channels: Vec<u32>,
+    /// Tableau des SSIDs
+    pub ssids: Vec<String>,
+    /// Tableau des empreintes d'IEs
+    pub fingerprints: Vec<String>,
+    /// RSSI lissé
+    pub rssi_smoothed: f64,
+    /// Distance estimée (mètres)
+    pub distance: f64,
+}
+
+/// RSSI lissé et distance estimée d'un appareil, arrondis
+fn smoothed_distance(mac: &str, rssi: i32) -> (f64, f64) {
+    let smoothed = rssi::smoothed(mac).unwrap_or(rssi as f64);
+    (
+        (smoothed * 10.0).round() / 10.0,
+        (rssi::distance(smoothed) * 100.0).round() / 100.0,
+    )
+}
+
+/// Observation unitaire d'un appareil, telle que reçue dans une trame
+///
+/// Champs:
+/// | Champ     | Type     | Description                  |
+/// |-----------|----------|------------------------------|
+/// | `mac`     | `String` | Adresse MAC de l'émetteur    |
+/// | `ts`      | `int`    | UNIX Timestamp (UTC)         |
+/// | `channel` | `int`    | Canal                        |
+/// | `rssi`    | `int`    | RSSI                         |
+/// | `ssid`    | `String` | SSID (vide si non broadcasté) |
+/// | `seq`     | `int`    | Numéro de trame de la carte (`null` en version 1) |
+/// | `dst`     | `String` | Adresse MAC du destinataire (vide en version 1) |
+/// | `bssid`   | `String` | BSSID (vide en version 1) |
+/// | `subtype` | `int`    | Sous-type de la trame de gestion 802.11 (`4` : probe request) |
+/// | `sn`      | `int`    | Numéro de séquence 802.11 |
+/// | `fingerprint` | `String` | Empreinte des IEs ([`fingerprint`], vide si absents) |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Observation {
+    /// Addresse MAC
+    pub mac: String,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// Canal
+    pub channel: u32,
+    /// RSSI
+    pub rssi: i32,
+    /// SSID
+    pub ssid: String,
+    /// Numéro de trame de la carte
+    pub seq: Option<u64>,
+    /// Addresse MAC du destinataire
+    pub dst: String,
+    /// BSSID
+    pub bssid: String,
+    /// Sous-type 802.11
+    pub subtype: Option<u8>,
+    /// Numéro de séquence 802.11
+    pub sn: Option<u16>,
+    /// Empreinte des IEs
+    pub fingerprint: String,
 }
 
 #[tokio::main]
 /// Bootstrap le traitement
 async fn serial_port(port_name: String) -> tokio_serial::Result<()> {
-    let port = tokio_serial::new(port_name, 115_200).open_native_async()?;
+    let port =
+        tokio_serial::new(port_name, BAUD_RATE.load(Ordering::SeqCst)).open_native_async()?;
     STARTED.store(true, Ordering::SeqCst);
-    let mut reader = LineCodec.framed(port);
+    let (mut writer, mut reader) = LineCodec.framed(port).split::<Command>();
+    // Commandes à destination de la carte
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+    command::attach(command_tx);
     // FIFO queue
-    let data_queue = HeapRb::<String>::new(255);
+    let data_queue = HeapRb::<String>::new(queue_capacity());
     // Recuperer Producteur et Consommateur
     let (mut data_queue_tx, data_queue_rx) = data_queue.split();
     // Envoi du consommateur dans le thread et dans la fonction parse_str pour le traitement
     thread::spawn(move || {
         parse_str(data_queue_rx);
     });
-    while let Some(line_result) = reader.next().await {
-        let line = line_result.expect("Failed to read line");
-        // Réponds au signal de stop
-        if STOP.load(Ordering::SeqCst) {
-            STARTED.store(false, Ordering::SeqCst);
-            STOP.store(false, Ordering::SeqCst);
-            break;
+    loop {
+        tokio::select! {
+            line_result = reader.next() => {
+                let Some(line_result) = line_result else {
+                    break;
+                };
+                let line = line_result.expect("Failed to read line");
+                // Réponds au signal de stop
+                if STOP.load(Ordering::SeqCst) {
+                    STARTED.store(false, Ordering::SeqCst);
+                    STOP.store(false, Ordering::SeqCst);
+                    break;
+                }
+                metrics::frame_received();
+                // Si la ligne n'est pas mauvaise, la push sur le FIFO
+                if data_queue_tx.push(line).is_err() {
+                    // File pleine, la ligne est perdue
+                    metrics::queue_dropped();
+                }
+                metrics::queue_depth(data_queue_tx.len());
+            }
+            Some(command) = command_rx.recv() => {
+                writer.send(command).await?;
+            }
         }
-        // Si la ligne n'est pas mauvaise, la push sur le FIFO
-        data_queue_tx.push(line).unwrap();
     }
     Ok(())
 }
@@ -234,13 +461,30 @@ async fn serial_port(port_name: String) -> tokio_serial::Result<()> {
 #[ffi_function]
 #[cfg(windows)]
 pub extern "C" fn start(tty_no: u32) -> bool {
+    let mut port_name: String = "COM".to_owned();
+    port_name.push_str(tty_no.to_string().as_str());
+    start_tty(port_name)
+}
+
+/// Démarre l'enregistrement à partir du chemin du port série
+///
+/// Utilisé par `start()` (Windows) et par les programmes Rust (ex. `/dev/ttyUSB0` ou `COM3`)
+///
+/// Retourne
+///   - `false` : Si le système était stoppé
+///   - `true` Si le système était déjà démarré
+pub fn start_tty(port_name: String) -> bool {
     if !STARTED.load(Ordering::SeqCst) {
-        let mut port_name: String = "COM".to_owned();
         // Signal d'arret de l'enregistrement
         STOP.store(false, Ordering::SeqCst);
-        port_name.push_str(tty_no.to_string().as_str());
+        *SERIAL_ERROR.lock().unwrap() = None;
         thread::spawn(move || {
-            serial_port(port_name).unwrap();
+            // Conserve l'erreur pour la consultation par serial_error()
+            if let Err(err) = serial_port(port_name) {
+                *SERIAL_ERROR.lock().unwrap() = Some(err.to_string());
+            }
+            // Plus aucune commande ne peut être envoyée
+            command::detach();
         });
         false
     } else {
@@ -248,6 +492,105 @@ pub extern "C" fn start(tty_no: u32) -> bool {
     }
 }
 
+/// Démarre la relecture d'un fichier de capture dans un thread
+///
+/// Voir [`replay()`]
+///
+/// Retourne
+///   - `false` : Si le système était stoppé
+///   - `true` Si le système était déjà démarré
+pub fn start_replay(path: String) -> bool {
+    if !STARTED.load(Ordering::SeqCst) {
+        STOP.store(false, Ordering::SeqCst);
+        *SERIAL_ERROR.lock().unwrap() = None;
+        thread::spawn(move || {
+            if let Err(err) = replay(&path) {
+                *SERIAL_ERROR.lock().unwrap() = Some(err.to_string());
+            }
+        });
+        false
+    } else {
+        true
+    }
+}
+
+/// Relit un fichier de capture et traite ses trames comme si elles arrivaient du port série
+///
+/// Le fichier contient les lignes telles qu'envoyées par la carte (ex. copie du terminal ESPlorer).
+///
+/// Les lignes qui ne sont pas des trames sont ignorées. Bloque jusqu'à la fin du fichier ou [`stop()`].
+pub fn replay(path: &str) -> io::Result<()> {
+    let file = File::open(path)?;
+    STARTED.store(true, Ordering::SeqCst);
+    let mut result = Ok(());
+    for line in BufReader::new(file).lines() {
+        // Réponds au signal de stop
+        if STOP.load(Ordering::SeqCst) {
+            STOP.store(false, Ordering::SeqCst);
+            break;
+        }
+        match line {
+            Ok(line) => {
+                metrics::frame_received();
+                parse_line(&line)
+            }
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        }
+    }
+    STARTED.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Indique si l'enregistrement est en cours
+pub fn is_started() -> bool {
+    STARTED.load(Ordering::SeqCst)
+}
+
+/// Change le débit du port série (bauds), pris en compte au prochain démarrage
+///
+/// Retourne `false` si le débit est nul
+pub fn set_baud_rate(baud_rate: u32) -> bool {
+    if baud_rate == 0 {
+        return false;
+    }
+    BAUD_RATE.store(baud_rate, Ordering::SeqCst);
+    true
+}
+
+/// Change la capacité de la file d'attente, prise en compte au prochain démarrage
+///
+/// Retourne `false` si la capacité est nulle
+pub fn set_queue_capacity(capacity: usize) -> bool {
+    if capacity == 0 {
+        return false;
+    }
+    QUEUE_SIZE.store(capacity, Ordering::SeqCst);
+    true
+}
+
+/// Capacité de la file d'attente
+pub fn queue_capacity() -> usize {
+    QUEUE_SIZE.load(Ordering::SeqCst)
+}
+
+/// Change le RSSI retenu quand celui d'une trame est illisible (défaut : `0`)
+pub fn set_default_rssi(rssi: i32) {
+    DEFAULT_RSSI.store(rssi, Ordering::SeqCst);
+}
+
+/// RSSI retenu quand celui d'une trame est illisible
+pub fn default_rssi() -> i32 {
+    DEFAULT_RSSI.load(Ordering::SeqCst)
+}
+
+/// Dernière erreur du port série (ex. port introuvable) depuis le dernier démarrage
+pub fn serial_error() -> Option<String> {
+    SERIAL_ERROR.lock().unwrap().clone()
+}
+
 /// Fonction publique pour stopper l'enregistrement
 ///
 /// Retourne :
@@ -275,94 +618,236 @@ fn parse_str(mut data_queue_rx: Consumer<String, Arc<SharedRb<String, Vec<MaybeU
         }
         // Recupere un element de la FIFO
         let frame: String = data_queue_rx.pop().unwrap();
-        // Clean la trame en enlevant tout les caractères spéciaux qui nous interesse pas
-        let cleaned_frame: String = frame.replace(&['\u{2}', '\u{3}', '\r', '\n'][..], "");
-        // Split au niveau des caracteres de controle
-        let splitted_frame: Vec<_> = cleaned_frame.split('\x1F').collect();
-        // Decode le channel
-        let channel: u32 = (splitted_frame[0]).parse::<u32>().unwrap_or(0);
-        // Verifie la syntaxe de l'addresse
-        let mac_address: String = if MAC_REGEX.is_match(splitted_frame[1]) {
-            splitted_frame[1].to_string()
-        } else {
-            // Si l'addresse MAC est invalide
-            "".to_string()
-        };
-        // Récupère le RSSI
-        let rssi: String = splitted_frame[2].to_string();
-        // Vérifie que le SSID n'est pas le RSSI
-        let ssid: String = if splitted_frame[splitted_frame.len() - 2] != rssi {
-            splitted_frame[splitted_frame.len() - 2].to_string()
-        } else {
-            splitted_frame[splitted_frame.len() - 1].to_string()
-        };
-        store(channel, mac_address, rssi, ssid);
+        metrics::queue_depth(data_queue_rx.len());
+        parse_line(&frame);
     }
 }
 
-/// Enregistre les données récupérées
-fn store(channel: u32, mac_address: String, rssi: String, ssid: String) {
-    // Récupérer le lock sur les hashmaps
-    let mut mac_table = MACS.lock().unwrap();
-    // Ajouter a la liste des Adresses MAC connues si non dupliquées
-    if !mac_table.contains(&mac_address) {
-        mac_table.push(mac_address.clone())
+/// Décompose une trame et enregistre ses données
+fn parse_line(frame: &str) {
+    // Clean la trame en enlevant tout les caractères spéciaux qui nous interesse pas
+    let cleaned_frame: String = frame.replace(&['\u{2}', '\u{3}', '\r', '\n'][..], "");
+    // Split au niveau des caracteres de controle, version 1 ou 2
+    let splitted_frame: Vec<&str> = match frame::decode(&cleaned_frame) {
+        Ok(decoded) => decoded.fields,
+        Err(err) => {
+            // Trame abimée sur la liaison série
+            if err != FrameError::Empty {
+                metrics::frame_corrupted();
+            }
+            metrics::frame_rejected();
+            return;
+        }
+    };
+    // Réponses aux commandes envoyées à la carte
+    match splitted_frame[0] {
+        "PONG" => return command::pong(splitted_frame.get(1).copied().unwrap_or("")),
+        "ACK" | "ERR" => return,
+        // Beacons et probe responses (mode ap)
+        "AP" => {
+            if !ap::store(&splitted_frame[1..]) {
+                metrics::frame_rejected();
+            }
+            return;
+        }
+        // État périodique de la carte
+        "STATUS" => {
+            if !sensor::store(&splitted_frame[1..]) {
+                metrics::frame_rejected();
+            }
+            return;
+        }
+        _ => {}
     }
+    // Ignorer les lignes qui ne sont pas des trames (ex. messages de démarrage)
+    if splitted_frame.len() < 3 {
+        metrics::frame_rejected();
+        return;
+    }
+    // Decode le channel
+    let channel: u32 = (splitted_frame[0]).parse::<u32>().unwrap_or(0);
+    // Verifie la syntaxe de l'addresse de l'émetteur (destinataire en version 1)
+    let mac_address: String = checked_mac(splitted_frame[1]);
+    // Récupère le RSSI
+    let rssi: i32 = splitted_frame[2]
+        .parse::<i32>()
+        .unwrap_or(DEFAULT_RSSI.load(Ordering::SeqCst));
+    // Le SSID est le quatrième champ, absent en version 1 s'il est vide
+    let ssid: String = splitted_frame.get(3).copied().unwrap_or("").to_string();
+    // Horloge et numéro de trame de la carte, si présents
+    let tick = splitted_frame
+        .get(4)
+        .and_then(|tick| tick.parse::<u64>().ok());
+    let seq = splitted_frame
+        .get(5)
+        .and_then(|seq| seq.parse::<u64>().ok());
+    let seen = match (tick, seq) {
+        (Some(tick), Some(seq)) => clock::sync(tick, seq, SystemTime::now()),
+        _ => SystemTime::now(),
+    };
+    // Champs 802.11 de la trame, si présents
+    let field = |index: usize| splitted_frame.get(index).copied().unwrap_or("");
+    let observation = Observation {
+        mac: mac_address,
+        ts: DateTime::<Utc>::from(seen).timestamp(),
+        channel,
+        rssi,
+        ssid,
+        seq,
+        dst: checked_mac(field(6)),
+        bssid: checked_mac(field(7)),
+        subtype: field(8).parse::<u8>().ok(),
+        // Le champ Sequence Control contient le numéro de fragment sur les 4 bits de poids faible
+        sn: field(9).parse::<u16>().ok().map(|control| control >> 4),
+        fingerprint: fingerprint::fingerprint(&fingerprint::parse_ies(field(10))),
+    };
+    // Pseudonymisation avant tout enregistrement (mode confidentialité)
+    store(privacy::apply(observation), seen);
+}
 
-    // Mets a jour le timestamp
-    let mut timestamp_table = LAST_SEEN.lock().unwrap();
-    timestamp_table
-        .entry(mac_address.clone())
-        .and_modify(|ts_tmp| *ts_tmp = SystemTime::now())
-        .or_insert(SystemTime::now());
+/// Vérifie la syntaxe d'une addresse MAC, vide si invalide
+pub(crate) fn checked_mac(mac: &str) -> String {
+    if MAC_REGEX.is_match(mac) {
+        mac.to_string()
+    } else {
+        "".to_string()
+    }
+}
 
-    let mut channel_table = CHANNELS.lock().unwrap();
-    // S'assurer de la présence du vecteur dans la table
-    if let Some(tmp_channel) = channel_table.get(&mac_address) {
-        // Déduplication
-        if !tmp_channel.contains(&channel) {
-            channel_table
-                .entry(mac_address.clone())
-                .or_insert_with(Vec::new)
-                .push(channel);
+/// Enregistre les données récupérées
+fn store(observation: Observation, seen: SystemTime) {
+    let (channel, rssi) = (observation.channel, observation.rssi);
+    let (mac_address, ssid) = (observation.mac.clone(), observation.ssid.clone());
+    // Compteurs d'activité
+    FRAME_COUNT.fetch_add(1, Ordering::SeqCst);
+    metrics::frame_parsed();
+    *CHANNEL_FRAMES.lock().unwrap().entry(channel).or_insert(0) += 1;
+    channels::record(channel, observation.ts);
+    // Tables des appareils, les locks sont relâchés à la fin du bloc : les routes d'alerte
+    // et les abonnés aux évènements peuvent relire les tables
+    let new_device = {
+        // Récupérer le lock sur les hashmaps
+        let mut mac_table = MACS.lock().unwrap();
+        // Ajouter a la liste des Adresses MAC connues si non dupliquées
+        let new_device = !mac_table.contains(&mac_address);
+        if new_device {
+            mac_table.push(mac_address.clone())
         }
-    } else {
-        // Le créer si pas présent
-        channel_table
+
+        // Mets a jour le timestamp
+        let mut timestamp_table = LAST_SEEN.lock().unwrap();
+        timestamp_table
             .entry(mac_address.clone())
-            .or_insert_with(Vec::new)
-            .push(channel);
-    }
+            .and_modify(|ts_tmp| *ts_tmp = seen)
+            .or_insert(seen);
 
-    // Ne rien faire si le SSID est vide
-    if !ssid.is_empty() {
-        // Récupérer le lock sur la table des SSIDs
-        let mut ssid_table = SSIDS.lock().unwrap();
+        let mut channel_table = CHANNELS.lock().unwrap();
         // S'assurer de la présence du vecteur dans la table
-        if let Some(tmp_ssid) = ssid_table.get(&mac_address) {
+        if let Some(tmp_channel) = channel_table.get(&mac_address) {
             // Déduplication
-            if !tmp_ssid.contains(&ssid) {
+            if !tmp_channel.contains(&channel) {
+                channel_table
+                    .entry(mac_address.clone())
+                    .or_default()
+                    .push(channel);
+            }
+        } else {
+            // Le créer si pas présent
+            channel_table
+                .entry(mac_address.clone())
+                .or_default()
+                .push(channel);
+        }
+
+        // Ne rien faire si le SSID est vide
+        if !ssid.is_empty() {
+            // Récupérer le lock sur la table des SSIDs
+            let mut ssid_table = SSIDS.lock().unwrap();
+            // S'assurer de la présence du vecteur dans la table
+            if let Some(tmp_ssid) = ssid_table.get(&mac_address) {
+                // Déduplication
+                if !tmp_ssid.contains(&ssid) {
+                    ssid_table
+                        .entry(mac_address.clone())
+                        .or_default()
+                        .push(ssid.clone());
+                }
+            }
+            // Le créer si pas présent
+            else {
                 ssid_table
                     .entry(mac_address.clone())
-                    .or_insert_with(Vec::new)
-                    .push(ssid);
+                    .or_default()
+                    .push(ssid.clone());
             }
         }
-        // Le créer si pas présent
-        else {
-            ssid_table
-                .entry(mac_address.clone())
-                .or_insert_with(Vec::new)
-                .push(ssid);
+        // Empreinte des IEs, dédupliquée
+        if !observation.fingerprint.is_empty() {
+            let mut fingerprint_table = FINGERPRINTS.lock().unwrap();
+            let fingerprints = fingerprint_table.entry(mac_address.clone()).or_default();
+            if !fingerprints.contains(&observation.fingerprint) {
+                fingerprints.push(observation.fingerprint.clone());
+            }
+        }
+        // RSSI
+        let mut rssi_table = RSSIS.lock().unwrap();
+        // Ajout du dernier RSSI vu
+        rssi_table
+            .entry(mac_address.clone())
+            .and_modify(|rssi_tmp| *rssi_tmp = rssi)
+            .or_insert(rssi);
+
+        // Historique
+        let mut observation_table = OBSERVATIONS.lock().unwrap();
+        if observation_table.len() >= OBSERVATION_CAPACITY {
+            observation_table.pop_front();
         }
+        observation_table.push_back(observation.clone());
+        OBSERVATION_VERSION.fetch_add(1, Ordering::SeqCst);
+        new_device
+    };
+    let smoothed = rssi::update(&mac_address, rssi);
+    localization::record(&observation);
+    ssids::record(&observation);
+
+    // Flux d'évènements
+    if new_device {
+        events::publish(Event::NewDevice(observation.clone()));
     }
-    // RSSI
-    let mut rssi_table = RSSIS.lock().unwrap();
-    // Ajout du dernier RSSI vu
-    rssi_table
-        .entry(mac_address)
-        .and_modify(|rssi_tmp| *rssi_tmp = (rssi).parse::<i32>().unwrap_or(0))
-        .or_insert((rssi).parse::<i32>().unwrap_or(0));
+    for event in sessions::record(&observation) {
+        events::publish(event);
+    }
+    if let Some(change) = zones::update(
+        &localization::local_sensor(),
+        &mac_address,
+        observation.ts,
+        smoothed,
+    ) {
+        events::publish(Event::Zone(change));
+    }
+    watchlist::check(&observation);
+    events::publish(Event::Observation(observation));
+}
+
+thread_local! {
+    /// Dernière chaîne retournée par la FFI sur le thread
+    static FFI_STRING: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Chaîne retournée par une fonction FFI, sans ses éventuels `\0`
+///
+/// La chaîne est conservée jusqu'au prochain appel sur le même thread
+pub(crate) fn ffi_string<'a>(text: String) -> AsciiPointer<'a> {
+    let text = CString::new(text).unwrap_or_else(|err| {
+        let mut bytes = err.into_vec();
+        bytes.retain(|byte| *byte != 0);
+        CString::new(bytes).unwrap_or_default()
+    });
+    FFI_STRING.with(|buffer| {
+        *buffer.borrow_mut() = text;
+        AsciiPointer::from_slice_with_nul(buffer.borrow().as_bytes_with_nul())
+            .unwrap_or(AsciiPointer::empty())
+    })
 }
 
 #[no_mangle]
@@ -385,6 +870,7 @@ pub extern "C" fn get_data_last<'a>() -> AsciiPointer<'a> {
     let seen_ssids = Arc::clone(&SSIDS);
     let seen_channels = Arc::clone(&CHANNELS);
     let seen_rssi = Arc::clone(&RSSIS);
+    let seen_fingerprints = Arc::clone(&FINGERPRINTS);
     let last_seen = Arc::clone(&LAST_SEEN);
     let to_return = thread::spawn(move || {
         let seen_macs = seen_macs.lock().unwrap().clone();
@@ -393,27 +879,29 @@ pub extern "C" fn get_data_last<'a>() -> AsciiPointer<'a> {
         let seen_ssids = seen_ssids.lock().unwrap().clone();
         let seen_channels = seen_channels.lock().unwrap().clone();
         let seen_rssi = seen_rssi.lock().unwrap().clone();
+        let seen_fingerprints = seen_fingerprints.lock().unwrap().clone();
         let seen_ts: DateTime<Utc> = (*last_seen.get(mac_str).unwrap()).into();
         let unix_utc_ts: i64 = seen_ts.timestamp();
+        let rssi = seen_rssi.get(mac_str).copied().unwrap_or(0);
+        let (rssi_smoothed, distance) = smoothed_distance(mac_str, rssi);
         let mac_vec: Data = Data {
             mac: mac_str.to_owned(),
             ts: unix_utc_ts,
-            rssi: seen_rssi.get(mac_str).unwrap_or(&0).to_owned(),
+            rssi,
             channels: seen_channels.get(mac_str).unwrap_or(&vec![0]).to_owned(),
             ssids: seen_ssids
                 .get(mac_str)
                 .unwrap_or(&vec![String::from("")])
                 .to_owned(),
+            fingerprints: seen_fingerprints.get(mac_str).cloned().unwrap_or_default(),
+            rssi_smoothed,
+            distance,
         };
         json_serialize(mac_vec)
     })
     .join()
     .unwrap();
-    let ret = to_return.unwrap_or(String::from(""));
-    let mut nulled_safe_ret = ret.replace('\0', "");
-    nulled_safe_ret.push('\0');
-    AsciiPointer::from_slice_with_nul(nulled_safe_ret.as_bytes())
-        .unwrap_or(AsciiPointer::from_slice_with_nul(String::from("\0").as_bytes()).unwrap())
+    ffi_string(to_return.unwrap_or(String::from("")))
 }
 
 #[cfg(feature = "json")]
@@ -433,41 +921,142 @@ pub extern "C" fn get_data_last<'a>() -> AsciiPointer<'a> {
 /// | channels | `Vec<u32>` | `[0]` |
 /// | ssids | `Vec<String>` | `[""]` |
 pub extern "C" fn get_data_all<'a>() -> AsciiPointer<'a> {
+    ffi_string(json_serialize(snapshot()).unwrap_or(String::from("")))
+}
+
+/// Récupère une copie des données de tous les appareils en mémoire
+///
+/// Utilisé par [`get_data_all()`] et par les programmes Rust
+///
+/// Les valeurs par défaut sont les mêmes que pour [`get_data_all()`]
+pub fn snapshot() -> Vec<Data> {
     let mut data_vec: Vec<Data> = Vec::new();
-    let seen_macs = Arc::clone(&MACS);
-    let seen_ssids = Arc::clone(&SSIDS);
-    let seen_channels = Arc::clone(&CHANNELS);
-    let seen_rssi = Arc::clone(&RSSIS);
-    let last_seen = Arc::clone(&LAST_SEEN);
-    let to_return = thread::spawn(move || {
-        let seen_macs = seen_macs.lock().unwrap().clone();
-        let last_seen = last_seen.lock().unwrap().clone();
-        let seen_ssids = seen_ssids.lock().unwrap().clone();
-        let seen_channels = seen_channels.lock().unwrap().clone();
-        let seen_rssi = seen_rssi.lock().unwrap().clone();
-        for mac in seen_macs.into_iter() {
-            let seen_ts: DateTime<Utc> = (*last_seen.get(&mac).unwrap()).into();
-            let unix_utc_ts: i64 = seen_ts.timestamp();
-            let mac_vec: Data = Data {
-                mac: mac.to_owned(),
-                ts: unix_utc_ts,
-                rssi: seen_rssi.get(&mac).unwrap_or(&0).to_owned(),
-                channels: seen_channels.get(&mac).unwrap_or(&vec![0]).to_owned(),
-                ssids: seen_ssids
-                    .get(&mac)
-                    .unwrap_or(&vec![String::from("")])
-                    .to_owned(),
-            };
-            data_vec.push(mac_vec);
-        }
-        json_serialize(data_vec)
-    })
-    .join()
-    .unwrap();
-    let mut ret = to_return.unwrap_or(String::from(""));
-    ret.push('\0');
-    AsciiPointer::from_slice_with_nul(ret.as_bytes())
-        .unwrap_or(AsciiPointer::from_slice_with_nul(String::from("\0").as_bytes()).unwrap())
+    let seen_macs = MACS.lock().unwrap().clone();
+    let last_seen = LAST_SEEN.lock().unwrap().clone();
+    let seen_ssids = SSIDS.lock().unwrap().clone();
+    let seen_channels = CHANNELS.lock().unwrap().clone();
+    let seen_rssi = RSSIS.lock().unwrap().clone();
+    let seen_fingerprints = FINGERPRINTS.lock().unwrap().clone();
+    for mac in seen_macs.into_iter() {
+        let seen_ts: DateTime<Utc> = (*last_seen.get(&mac).unwrap()).into();
+        let unix_utc_ts: i64 = seen_ts.timestamp();
+        let rssi = seen_rssi.get(&mac).copied().unwrap_or(0);
+        let (rssi_smoothed, distance) = smoothed_distance(&mac, rssi);
+        let mac_vec: Data = Data {
+            mac: mac.to_owned(),
+            ts: unix_utc_ts,
+            rssi,
+            channels: seen_channels.get(&mac).unwrap_or(&vec![0]).to_owned(),
+            ssids: seen_ssids
+                .get(&mac)
+                .unwrap_or(&vec![String::from("")])
+                .to_owned(),
+            fingerprints: seen_fingerprints.get(&mac).cloned().unwrap_or_default(),
+            rssi_smoothed,
+            distance,
+        };
+        data_vec.push(mac_vec);
+    }
+    data_vec
+}
+
+/// Récupère les données d'un appareil (adresse MAC insensible à la casse)
+pub fn device(mac: &str) -> Option<Data> {
+    snapshot()
+        .into_iter()
+        .find(|data| data.mac.eq_ignore_ascii_case(mac))
+}
+
+/// Récupère l'historique des observations d'un appareil (adresse MAC insensible à la casse)
+pub fn history(mac: &str) -> Vec<Observation> {
+    OBSERVATIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|obs| obs.mac.eq_ignore_ascii_case(mac))
+        .cloned()
+        .collect()
+}
+
+/// Récupère une copie de l'historique complet des observations
+pub fn observations() -> Vec<Observation> {
+    OBSERVATIONS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Efface les appareils et les observations sélectionnés
+///
+/// Un appareil est sélectionné selon sa dernière observation. Retourne le nombre d'appareils et
+/// d'observations effacés, et les adresses MAC restantes
+pub(crate) fn purge(purge: &retention::Purge) -> (usize, usize, HashSet<String>) {
+    // Même ordre de verrouillage que `store()`
+    let mut mac_table = MACS.lock().unwrap();
+    let mut timestamp_table = LAST_SEEN.lock().unwrap();
+    let mut channel_table = CHANNELS.lock().unwrap();
+    let mut ssid_table = SSIDS.lock().unwrap();
+    let mut fingerprint_table = FINGERPRINTS.lock().unwrap();
+    let mut rssi_table = RSSIS.lock().unwrap();
+    let mut observation_table = OBSERVATIONS.lock().unwrap();
+    let erased: Vec<String> = mac_table
+        .iter()
+        .filter(|mac| {
+            let seen = timestamp_table
+                .get(*mac)
+                .map_or(0, |seen| DateTime::<Utc>::from(*seen).timestamp());
+            purge.matches(mac, seen)
+        })
+        .cloned()
+        .collect();
+    for mac in &erased {
+        timestamp_table.remove(mac);
+        channel_table.remove(mac);
+        ssid_table.remove(mac);
+        fingerprint_table.remove(mac);
+        rssi_table.remove(mac);
+    }
+    mac_table.retain(|mac| !erased.contains(mac));
+    let count = observation_table.len();
+    observation_table.retain(|obs| !purge.matches(&obs.mac, obs.ts));
+    let observations = count - observation_table.len();
+    if observations > 0 {
+        OBSERVATION_VERSION.fetch_add(1, Ordering::SeqCst);
+    }
+    (
+        erased.len(),
+        observations,
+        mac_table.iter().cloned().collect(),
+    )
+}
+
+/// UNIX Timestamp (UTC) de la plus ancienne observation ou du plus ancien appareil
+pub(crate) fn oldest() -> Option<i64> {
+    let observation = OBSERVATIONS.lock().unwrap().front().map(|obs| obs.ts);
+    let seen = LAST_SEEN
+        .lock()
+        .unwrap()
+        .values()
+        .map(|seen| DateTime::<Utc>::from(*seen).timestamp())
+        .min();
+    observation.into_iter().chain(seen).min()
+}
+
+/// Nombre d'observations dans l'historique
+pub fn observation_count() -> usize {
+    OBSERVATIONS.lock().unwrap().len()
+}
+
+/// Version de l'historique des observations, change à chaque ajout ou effacement
+pub(crate) fn observation_version() -> u64 {
+    OBSERVATION_VERSION.load(Ordering::SeqCst)
+}
+
+/// Nombre total de trames traitées depuis le chargement de la librairie
+pub fn frame_count() -> u64 {
+    FRAME_COUNT.load(Ordering::SeqCst)
+}
+
+/// Nombre de trames traitées par canal
+pub fn channel_activity() -> HashMap<u32, u64> {
+    CHANNEL_FRAMES.lock().unwrap().clone()
 }
 
 #[cfg(feature = "json")]
@@ -480,9 +1069,77 @@ fn json_serialize(data_vec: impl Serialize) -> Result<String, serde_json::Error>
 ///
 /// Utilise [`interoptopus::InventoryBuilder`]
 pub fn ffi_inventory() -> Inventory {
-    InventoryBuilder::new()
-        .register(function!(start))
+    let builder = InventoryBuilder::new();
+    // La fonction start n'existe que sous Windows
+    #[cfg(windows)]
+    let builder = builder.register(function!(start));
+    // L'API HTTP n'existe qu'avec la feature http
+    #[cfg(feature = "http")]
+    let builder = builder
+        .register(function!(http::start_http))
+        .register(function!(http::start_metrics));
+    // La publication MQTT n'existe qu'avec la feature mqtt
+    #[cfg(feature = "mqtt")]
+    let builder = builder.register(function!(mqtt::start_mqtt));
+    // Les webhooks n'existent qu'avec la feature webhook
+    #[cfg(feature = "webhook")]
+    let builder = builder
+        .register(function!(watchlist::set_alert_webhook))
+        .register(function!(webhook::start_webhook))
+        .register(function!(webhook::get_webhook_pending));
+    builder
         .register(function!(stop))
+        .register(function!(ap::get_aps_all))
+        .register(function!(clock::get_frames_lost))
+        .register(function!(command::set_channel))
+        .register(function!(command::lock_channel))
+        .register(function!(command::set_hop_interval))
+        .register(function!(command::set_channel_list))
+        .register(function!(command::pause_capture))
+        .register(function!(command::resume_capture))
+        .register(function!(command::set_capture_mode))
+        .register(function!(command::ping))
+        .register(function!(command::request_status))
+        .register(function!(command::get_last_pong))
+        .register(function!(config::load_config))
+        .register(function!(groups::get_device_groups))
+        .register(function!(localization::set_sensor_position))
+        .register(function!(localization::set_local_sensor))
+        .register(function!(localization::add_reading))
+        .register(function!(localization::get_locations))
+        .register(function!(occupancy::get_occupancy))
+        .register(function!(occupancy::get_occupancy_csv))
+        .register(function!(privacy::set_privacy))
+        .register(function!(privacy::get_pseudonym))
+        .register(function!(retention::erase_device))
+        .register(function!(retention::set_retention))
+        .register(function!(retention::set_erasure_audit_log))
+        .register(function!(retention::set_erasure_audit_key))
+        .register(function!(retention::get_audit_log))
+        .register(function!(rssi::set_rssi_ema))
+        .register(function!(rssi::set_rssi_kalman))
+        .register(function!(rssi::set_distance_model))
+        .register(function!(sensor::get_sensor_status))
+        .register(function!(sessions::set_absence_timeout))
+        .register(function!(sessions::get_sessions))
+        .register(function!(sessions::get_dwell_stats))
+        .register(function!(ssids::get_ssids_all))
+        .register(function!(ssids::get_ssid_graph))
+        .register(function!(channels::get_channel_activity))
+        .register(function!(channels::get_hop_schedule))
+        .register(function!(channels::push_hop_schedule))
+        .register(function!(channels::set_adaptive_hopping))
+        .register(function!(watchlist::load_watchlist))
+        .register(function!(watchlist::add_watch))
+        .register(function!(watchlist::remove_watch))
+        .register(function!(watchlist::clear_watchlist))
+        .register(function!(watchlist::set_alert_cooldown))
+        .register(function!(watchlist::set_alert_log))
+        .register(function!(watchlist::set_alert_callback))
+        .register(function!(watchlist::get_alerts))
+        .register(function!(zones::set_zone_thresholds))
+        .register(function!(zones::get_zones))
+        .register(function!(zones::get_zone_changes))
         .register(function!(get_data_all))
         .register(function!(get_data_last))
         .inventory()
@@ -0,0 +1,362 @@
+//! Position approximative des appareils à partir de plusieurs capteurs
+//!
+//! Chaque capteur est identifié par son nom (le même que pour [MQTT](crate::mqtt)) et placé
+//! en coordonnées `x`, `y` (mètres, repère libre) avec [`set_sensor_position()`].
+//!
+//! Les observations du capteur local sont enregistrées automatiquement sous le nom donné à
+//! [`set_local_sensor()`]. Les mesures des autres capteurs (par exemple reçues sur leurs topics
+//! MQTT) sont ajoutées avec [`add_reading()`].
+//!
+//! Pour chaque appareil, le RSSI moyen de chaque capteur placé sur les [`LOCATION_WINDOW`]
+//! secondes précédant sa dernière mesure est converti en distance (voir [`crate::rssi`]).
+//! La position est obtenue par moindres carrés à partir de trois capteurs ou plus,
+//! sinon par le barycentre des capteurs pondéré par l'inverse du carré des distances.
+//!
+//! Le rayon d'incertitude est l'écart quadratique moyen entre les distances estimées et
+//! les distances de la position aux capteurs (la distance estimée avec un seul capteur).
+//!
+//! ## CSV
+//!
+//! [`to_csv()`] produit une ligne d'en-tête `mac,ts,x,y,radius,sensors` puis une ligne par appareil.
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::events::{self, Event};
+use crate::retention::Purge;
+use crate::{checked_mac, privacy, rssi, zones, Observation};
+
+/// Fenêtre (secondes) des mesures combinées pour une position
+pub const LOCATION_WINDOW: i64 = 10;
+
+// Mesures récentes `(ts, rssi)` d'un appareil par capteur
+type DeviceReadings = HashMap<String, VecDeque<(i64, i32)>>;
+
+lazy_static! {
+    /// Nom du capteur local
+    static ref LOCAL_SENSOR: Mutex<String> = Mutex::new(String::from("sensor"));
+    /// Position des capteurs par nom
+    static ref SENSORS: Mutex<HashMap<String, (f64, f64)>> = Mutex::new(HashMap::new());
+    /// Mesures récentes `(ts, rssi)` par adresse MAC puis par capteur
+    static ref READINGS: Mutex<HashMap<String, DeviceReadings>> = Mutex::new(HashMap::new());
+}
+
+/// Position estimée d'un appareil
+///
+/// Champs:
+/// | Champ     | Type     | Description                                      |
+/// |-----------|----------|--------------------------------------------------|
+/// | `mac`     | `String` | Adresse MAC                                      |
+/// | `ts`      | `int`    | UNIX Timestamp (UTC) de la dernière mesure       |
+/// | `x`       | `float`  | Abscisse (mètres)                                |
+/// | `y`       | `float`  | Ordonnée (mètres)                                |
+/// | `radius`  | `float`  | Rayon d'incertitude (mètres)                     |
+/// | `sensors` | `int`    | Nombre de capteurs utilisés                      |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    /// Adresse MAC
+    pub mac: String,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// Abscisse
+    pub x: f64,
+    /// Ordonnée
+    pub y: f64,
+    /// Rayon d'incertitude
+    pub radius: f64,
+    /// Nombre de capteurs
+    pub sensors: usize,
+}
+
+/// Place un capteur (coordonnées en mètres)
+///
+/// Retourne `false` si le nom est vide ou les coordonnées invalides
+pub fn set_position(sensor: &str, x: f64, y: f64) -> bool {
+    if sensor.is_empty() || !x.is_finite() || !y.is_finite() {
+        return false;
+    }
+    SENSORS.lock().unwrap().insert(sensor.to_owned(), (x, y));
+    true
+}
+
+/// Positions des capteurs par nom
+pub fn sensor_positions() -> HashMap<String, (f64, f64)> {
+    SENSORS.lock().unwrap().clone()
+}
+
+/// Nomme le capteur local
+///
+/// Retourne `false` si le nom est vide
+pub fn set_local(sensor: &str) -> bool {
+    if sensor.is_empty() {
+        return false;
+    }
+    *LOCAL_SENSOR.lock().unwrap() = sensor.to_owned();
+    true
+}
+
+/// Nom du capteur local
+pub fn local_sensor() -> String {
+    LOCAL_SENSOR.lock().unwrap().clone()
+}
+
+/// Ajoute une mesure d'un capteur
+///
+/// L'adresse MAC est enregistrée telle quelle, déjà pseudonymisée en mode confidentialité
+/// (voir [`crate::privacy`]). La zone de l'appareil pour ce capteur est mise à jour
+/// (voir [`crate::zones`]). Retourne `false` si l'adresse MAC ou le nom du capteur est invalide
+pub fn add(sensor: &str, mac: &str, ts: i64, rssi: i32) -> bool {
+    let mac = checked_mac(mac);
+    if mac.is_empty() || sensor.is_empty() {
+        return false;
+    }
+    insert(sensor, &mac, ts, rssi);
+    let smoothed = rssi::update_sensor(sensor, &mac, rssi);
+    if let Some(change) = zones::update(sensor, &mac, ts, smoothed) {
+        events::publish(Event::Zone(change));
+    }
+    true
+}
+
+// Ajoute une mesure valide aux mesures récentes de l'appareil
+fn insert(sensor: &str, mac: &str, ts: i64, rssi: i32) {
+    let mut readings = READINGS.lock().unwrap();
+    let device = readings.entry(mac.to_owned()).or_default();
+    // Seule la dernière fenêtre de chaque capteur est utile
+    let latest = device
+        .values()
+        .filter_map(|readings| readings.back().map(|(ts, _)| *ts))
+        .max()
+        .unwrap_or(ts)
+        .max(ts);
+    let sensor_readings = device.entry(sensor.to_owned()).or_default();
+    sensor_readings.push_back((ts, rssi));
+    for readings in device.values_mut() {
+        while readings
+            .front()
+            .is_some_and(|(ts, _)| latest - ts > LOCATION_WINDOW)
+        {
+            readings.pop_front();
+        }
+    }
+}
+
+/// Enregistre une observation du capteur local
+pub(crate) fn record(obs: &Observation) {
+    let mac = checked_mac(&obs.mac);
+    if !mac.is_empty() {
+        insert(&local_sensor(), &mac, obs.ts, obs.rssi);
+    }
+}
+
+// Position par moindres carrés, `None` si les capteurs sont alignés
+fn least_squares(points: &[((f64, f64), f64)]) -> Option<(f64, f64)> {
+    // Différence avec la dernière équation (x - xi)² + (y - yi)² = di²
+    let ((xn, yn), dn) = points[points.len() - 1];
+    let (mut a11, mut a12, mut a22, mut b1, mut b2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for ((xi, yi), di) in &points[..points.len() - 1] {
+        let (ax, ay) = (2.0 * (xn - xi), 2.0 * (yn - yi));
+        let b = di * di - dn * dn - xi * xi + xn * xn - yi * yi + yn * yn;
+        a11 += ax * ax;
+        a12 += ax * ay;
+        a22 += ay * ay;
+        b1 += ax * b;
+        b2 += ay * b;
+    }
+    let det = a11 * a22 - a12 * a12;
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    Some(((a22 * b1 - a12 * b2) / det, (a11 * b2 - a12 * b1) / det))
+}
+
+/// Estime une position à partir de `((x, y), distance)` par capteur
+///
+/// Retourne `(x, y, rayon)`, `None` sans capteur
+pub fn trilaterate(points: &[((f64, f64), f64)]) -> Option<(f64, f64, f64)> {
+    if points.is_empty() {
+        return None;
+    }
+    let centroid = || {
+        let weights: Vec<f64> = points
+            .iter()
+            .map(|(_, d)| 1.0 / d.max(0.1).powi(2))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        points
+            .iter()
+            .zip(&weights)
+            .fold((0.0, 0.0), |(x, y), (((xi, yi), _), w)| {
+                (x + xi * w / total, y + yi * w / total)
+            })
+    };
+    let (x, y) = if points.len() >= 3 {
+        least_squares(points).unwrap_or_else(centroid)
+    } else {
+        centroid()
+    };
+    let radius = if points.len() == 1 {
+        points[0].1
+    } else {
+        let sum: f64 = points
+            .iter()
+            .map(|((xi, yi), d)| ((x - xi).hypot(y - yi) - d).powi(2))
+            .sum();
+        (sum / points.len() as f64).sqrt()
+    };
+    Some((x, y, radius))
+}
+
+/// Position estimée d'un appareil (adresse MAC insensible à la casse)
+///
+/// `None` si aucun capteur placé n'a vu l'appareil
+pub fn locate(mac: &str) -> Option<Location> {
+    let sensors = sensor_positions();
+    let readings = READINGS.lock().unwrap();
+    let (mac, device) = readings
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(mac))?;
+    locate_device(mac, device, &sensors)
+}
+
+fn locate_device(
+    mac: &str,
+    device: &DeviceReadings,
+    sensors: &HashMap<String, (f64, f64)>,
+) -> Option<Location> {
+    let ts = device
+        .values()
+        .filter_map(|readings| readings.back().map(|(ts, _)| *ts))
+        .max()?;
+    let mut points: Vec<((f64, f64), f64)> = Vec::new();
+    let mut names: Vec<&String> = device.keys().collect();
+    names.sort();
+    for name in names {
+        let position = match sensors.get(name) {
+            Some(position) => *position,
+            None => continue,
+        };
+        let recent: Vec<i32> = device[name]
+            .iter()
+            .filter(|(seen, _)| ts - seen <= LOCATION_WINDOW)
+            .map(|(_, rssi)| *rssi)
+            .collect();
+        if recent.is_empty() {
+            continue;
+        }
+        let mean = recent.iter().sum::<i32>() as f64 / recent.len() as f64;
+        points.push((position, rssi::distance(mean)));
+    }
+    let (x, y, radius) = trilaterate(&points)?;
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+    Some(Location {
+        mac: mac.to_owned(),
+        ts,
+        x: round(x),
+        y: round(y),
+        radius: round(radius),
+        sensors: points.len(),
+    })
+}
+
+/// Positions estimées de tous les appareils vus par un capteur placé, triées par adresse MAC
+pub fn locations() -> Vec<Location> {
+    let sensors = sensor_positions();
+    let readings = READINGS.lock().unwrap();
+    let mut found: Vec<Location> = readings
+        .iter()
+        .filter_map(|(mac, device)| locate_device(mac, device, &sensors))
+        .collect();
+    found.sort_by(|a, b| a.mac.cmp(&b.mac));
+    found
+}
+
+/// Convertit des positions en CSV (séparateur `,`, fin de ligne `\n`)
+pub fn to_csv(locations: &[Location]) -> String {
+    let mut csv = String::from("mac,ts,x,y,radius,sensors\n");
+    for location in locations {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{}",
+            location.mac, location.ts, location.x, location.y, location.radius, location.sensors
+        );
+    }
+    csv
+}
+
+/// Date de la plus ancienne mesure
+pub(crate) fn oldest() -> Option<i64> {
+    READINGS
+        .lock()
+        .unwrap()
+        .values()
+        .flat_map(|device| device.values())
+        .flat_map(|readings| readings.iter().map(|(ts, _)| *ts))
+        .min()
+}
+
+/// Efface les mesures sélectionnées et celles des adresses MAC effacées
+///
+/// Retourne le nombre de mesures effacées
+pub(crate) fn purge(purge: &Purge, known: &HashSet<String>) -> usize {
+    let mut readings = READINGS.lock().unwrap();
+    let mut count = 0;
+    readings.retain(|mac, device| {
+        let keep = known.contains(mac);
+        for sensor_readings in device.values_mut() {
+            let before = sensor_readings.len();
+            sensor_readings.retain(|(ts, _)| keep && !purge.matches(mac, *ts));
+            count += before - sensor_readings.len();
+        }
+        device.retain(|_, sensor_readings| !sensor_readings.is_empty());
+        !device.is_empty()
+    });
+    count
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Place un capteur en coordonnées `x`, `y` (mètres)
+///
+/// Retourne `false` si le nom est vide ou les coordonnées invalides
+pub extern "C" fn set_sensor_position(sensor: AsciiPointer, x: f64, y: f64) -> bool {
+    sensor
+        .as_str()
+        .is_ok_and(|sensor| set_position(sensor, x, y))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Nomme le capteur local, dont les observations sont utilisées pour la localisation
+///
+/// Retourne `false` si le nom est vide
+pub extern "C" fn set_local_sensor(sensor: AsciiPointer) -> bool {
+    sensor.as_str().is_ok_and(set_local)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Ajoute une mesure d'un autre capteur (UNIX Timestamp UTC et RSSI)
+///
+/// L'adresse MAC brute est pseudonymisée en mode confidentialité.
+/// Retourne `false` si l'adresse MAC ou le nom du capteur est invalide
+pub extern "C" fn add_reading(sensor: AsciiPointer, mac: AsciiPointer, ts: i64, rssi: i32) -> bool {
+    match (sensor.as_str(), mac.as_str()) {
+        (Ok(sensor), Ok(mac)) => add(sensor, &privacy::mac(&checked_mac(mac), ts), ts, rssi),
+        _ => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Positions estimées des appareils pour la génération d'un fichier JSON (voir [`Location`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_locations<'a>() -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&locations()).unwrap_or(String::from("")))
+}
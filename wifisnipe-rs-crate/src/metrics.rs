@@ -0,0 +1,260 @@
+//! Métriques de santé du capteur au format texte [Prometheus](https://prometheus.io/docs/instrumenting/exposition_formats/)
+//!
+//! | Métrique | Type | Description |
+//! | -------- | ---- | ----------- |
+//! | `wifisnipe_capture_running` | gauge | `1` si l'enregistrement est en cours |
+//! | `wifisnipe_frames_received_total` | counter | Lignes reçues du port série ou de la relecture |
+//! | `wifisnipe_frames_parsed_total` | counter | Trames décodées et enregistrées |
+//! | `wifisnipe_frames_rejected_total` | counter | Lignes qui ne sont pas des trames |
+//! | `wifisnipe_frames_corrupted_total` | counter | Trames rejetées car abîmées (CRC ou nombre de champs) |
+//! | `wifisnipe_frames_lost_total` | counter | Trames perdues entre la carte et le PC (numéros de trame manquants) |
+//! | `wifisnipe_sensor_resets_total` | counter | Redémarrages de la carte détectés |
+//! | `wifisnipe_queue_depth` | gauge | Lignes en attente de traitement |
+//! | `wifisnipe_queue_capacity` | gauge | Capacité de la file d'attente |
+//! | `wifisnipe_queue_dropped_total` | counter | Lignes perdues car la file était pleine |
+//! | `wifisnipe_devices_known` | gauge | Adresses MAC en mémoire |
+//! | `wifisnipe_devices_present{window_seconds}` | gauge | Appareils vus dans la fenêtre |
+//! | `wifisnipe_access_points_known` | gauge | Points d'accès en mémoire |
+//! | `wifisnipe_channel_frames_total{channel}` | counter | Trames décodées par canal |
+//! | `wifisnipe_seconds_since_last_frame` | gauge | Absente tant qu'aucune trame n'a été décodée |
+//! | `wifisnipe_sensor_uptime_seconds` | gauge | Uptime de la carte, absente tant qu'aucun état n'a été reçu |
+//! | `wifisnipe_sensor_heap_bytes` | gauge | Mémoire libre de la carte |
+//! | `wifisnipe_sensor_frames_sent_total` | counter | Trames envoyées par la carte |
+//! | `wifisnipe_seconds_since_last_status` | gauge | Secondes depuis le dernier état de la carte |
+
+use chrono::Utc;
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::{ap, clock, sensor};
+use crate::{channel_activity, frame_count, is_started, queue_capacity, snapshot};
+
+/// Fenêtres (en secondes) pour le comptage des appareils présents
+pub const PRESENCE_WINDOWS: [i64; 4] = [60, 300, 900, 1800];
+
+lazy_static! {
+    /// Lignes reçues
+    static ref FRAMES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+    /// Lignes rejetées
+    static ref FRAMES_REJECTED: AtomicU64 = AtomicU64::new(0);
+    /// Trames abîmées
+    static ref FRAMES_CORRUPTED: AtomicU64 = AtomicU64::new(0);
+    /// Lignes en attente dans la file
+    static ref QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+    /// Lignes perdues, file pleine
+    static ref QUEUE_DROPPED: AtomicU64 = AtomicU64::new(0);
+    /// Timestamp de la dernière trame décodée
+    static ref LAST_FRAME: Mutex<Option<SystemTime>> = Mutex::new(None);
+}
+
+/// Compte une ligne reçue
+pub(crate) fn frame_received() {
+    FRAMES_RECEIVED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Compte une ligne rejetée
+pub(crate) fn frame_rejected() {
+    FRAMES_REJECTED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Compte une trame abîmée
+pub(crate) fn frame_corrupted() {
+    FRAMES_CORRUPTED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Note l'heure de la dernière trame décodée
+pub(crate) fn frame_parsed() {
+    *LAST_FRAME.lock().unwrap() = Some(SystemTime::now());
+}
+
+/// Mets à jour la profondeur de la file
+pub(crate) fn queue_depth(depth: usize) {
+    QUEUE_DEPTH.store(depth, Ordering::SeqCst);
+}
+
+/// Compte une ligne perdue
+pub(crate) fn queue_dropped() {
+    QUEUE_DROPPED.fetch_add(1, Ordering::SeqCst);
+}
+
+// Ajoute l'aide et le type d'une métrique
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+// Ajoute une métrique sans label
+fn single(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    header(out, name, kind, help);
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// Génère l'ensemble des métriques au format texte Prometheus
+pub fn render() -> String {
+    let mut out = String::new();
+    let (gauge, counter) = ("gauge", "counter");
+
+    single(
+        &mut out,
+        "wifisnipe_capture_running",
+        gauge,
+        "1 si l'enregistrement est en cours",
+        is_started() as u8,
+    );
+    single(
+        &mut out,
+        "wifisnipe_frames_received_total",
+        counter,
+        "Lignes reçues du port série ou de la relecture",
+        FRAMES_RECEIVED.load(Ordering::SeqCst),
+    );
+    single(
+        &mut out,
+        "wifisnipe_frames_parsed_total",
+        counter,
+        "Trames décodées et enregistrées",
+        frame_count(),
+    );
+    single(
+        &mut out,
+        "wifisnipe_frames_rejected_total",
+        counter,
+        "Lignes qui ne sont pas des trames",
+        FRAMES_REJECTED.load(Ordering::SeqCst),
+    );
+    single(
+        &mut out,
+        "wifisnipe_frames_corrupted_total",
+        counter,
+        "Trames rejetées car abîmées (CRC ou nombre de champs)",
+        FRAMES_CORRUPTED.load(Ordering::SeqCst),
+    );
+    single(
+        &mut out,
+        "wifisnipe_frames_lost_total",
+        counter,
+        "Trames perdues entre la carte et le PC",
+        clock::frames_lost(),
+    );
+    single(
+        &mut out,
+        "wifisnipe_sensor_resets_total",
+        counter,
+        "Redémarrages de la carte détectés",
+        clock::sensor_resets(),
+    );
+    single(
+        &mut out,
+        "wifisnipe_queue_depth",
+        gauge,
+        "Lignes en attente de traitement",
+        QUEUE_DEPTH.load(Ordering::SeqCst),
+    );
+    single(
+        &mut out,
+        "wifisnipe_queue_capacity",
+        gauge,
+        "Capacité de la file d'attente",
+        queue_capacity(),
+    );
+    single(
+        &mut out,
+        "wifisnipe_queue_dropped_total",
+        counter,
+        "Lignes perdues car la file était pleine",
+        QUEUE_DROPPED.load(Ordering::SeqCst),
+    );
+
+    let devices = snapshot();
+    let now = Utc::now().timestamp();
+    single(
+        &mut out,
+        "wifisnipe_devices_known",
+        gauge,
+        "Adresses MAC en mémoire",
+        devices.len(),
+    );
+    header(
+        &mut out,
+        "wifisnipe_devices_present",
+        gauge,
+        "Appareils vus dans la fenêtre",
+    );
+    for window in PRESENCE_WINDOWS {
+        let present = devices
+            .iter()
+            .filter(|data| now - data.ts <= window)
+            .count();
+        let _ = writeln!(
+            out,
+            "wifisnipe_devices_present{{window_seconds=\"{window}\"}} {present}"
+        );
+    }
+
+    single(
+        &mut out,
+        "wifisnipe_access_points_known",
+        gauge,
+        "Points d'accès en mémoire",
+        ap::snapshot().len(),
+    );
+
+    header(
+        &mut out,
+        "wifisnipe_channel_frames_total",
+        counter,
+        "Trames décodées par canal",
+    );
+    let mut channels: Vec<(u32, u64)> = channel_activity().into_iter().collect();
+    channels.sort_unstable();
+    for (channel, frames) in channels {
+        let _ = writeln!(
+            out,
+            "wifisnipe_channel_frames_total{{channel=\"{channel}\"}} {frames}"
+        );
+    }
+
+    if let Some(elapsed) = LAST_FRAME.lock().unwrap().and_then(|ts| ts.elapsed().ok()) {
+        single(
+            &mut out,
+            "wifisnipe_seconds_since_last_frame",
+            gauge,
+            "Secondes depuis la dernière trame décodée",
+            format!("{:.3}", elapsed.as_secs_f64()),
+        );
+    }
+
+    if let Some(status) = sensor::status() {
+        single(
+            &mut out,
+            "wifisnipe_sensor_uptime_seconds",
+            gauge,
+            "Secondes depuis le démarrage de la carte",
+            status.uptime,
+        );
+        single(
+            &mut out,
+            "wifisnipe_sensor_heap_bytes",
+            gauge,
+            "Mémoire libre de la carte",
+            status.heap,
+        );
+        single(
+            &mut out,
+            "wifisnipe_sensor_frames_sent_total",
+            counter,
+            "Trames envoyées par la carte depuis son démarrage",
+            status.frames,
+        );
+        single(
+            &mut out,
+            "wifisnipe_seconds_since_last_status",
+            gauge,
+            "Secondes depuis le dernier état de la carte",
+            status.age(),
+        );
+    }
+    out
+}
@@ -0,0 +1,278 @@
+//! Publication des observations sur un broker MQTT
+//!
+//! Disponible avec la feature `mqtt`
+//!
+//! | Topic (par défaut) | Contenu |
+//! | ------------------ | ------- |
+//! | `sniffer/{sensor}/device/{mac}` | Chaque observation acceptée ([`crate::Observation`]) |
+//! | `sniffer/{sensor}/presence` | Résumé périodique des appareils présents ([`Presence`]) |
+//!
+//! Les topics sont des modèles où `{sensor}` est remplacé par le nom du capteur
+//! et `{mac}` par l'adresse MAC de l'appareil.
+//!
+//! Avec [`MqttConfig::retain_observations`], l'effacement d'un appareil
+//! ([`crate::retention::erase()`]) publie un message retenu vide sur ses topics d'observation,
+//! ce qui efface le dernier message retenu par le broker.
+//!
+//! La connexion au broker est rétablie automatiquement.
+//! Les messages qui ne peuvent pas être mis en attente pendant une coupure sont perdus.
+//!
+//! ## Test local
+//!
+//! ```ps1
+//! mosquitto -v
+//! mosquitto_sub -t "sniffer/#" -v
+//! cd esp-sniffer\wifisnipe-rs
+//! cargo run -- --mqtt 127.0.0.1:1883 --sensor salle1 --replay capture.txt
+//! ```
+
+use chrono::Utc;
+use std::collections::HashSet;
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use rumqttc::{Client, MqttOptions, QoS};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events::{subscribe, Event};
+use crate::snapshot;
+
+/// Messages mis en attente pendant une coupure avec le broker
+const REQUEST_CAPACITY: usize = 1024;
+
+/// Délai avant une nouvelle tentative de connexion
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    /// Clients démarrés, pour effacer les messages retenus
+    static ref PUBLISHERS: Mutex<Vec<(Client, MqttConfig, QoS)>> = Mutex::new(Vec::new());
+}
+
+/// Configuration de la publication MQTT
+///
+/// Utiliser [`MqttConfig::default()`] pour les valeurs par défaut :
+///
+/// ```
+/// use libwifisnipe::mqtt::MqttConfig;
+///
+/// let config = MqttConfig {
+///     host: String::from("192.168.1.10"),
+///     sensor: String::from("salle1"),
+///     qos: 1,
+///     ..MqttConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// Hôte du broker (défaut : `127.0.0.1`)
+    pub host: String,
+    /// Port du broker (défaut : `1883`)
+    pub port: u16,
+    /// Identifiant de session, `wifisnipe-{sensor}` si vide
+    pub client_id: String,
+    /// Identifiants de connexion
+    pub credentials: Option<(String, String)>,
+    /// Nom du capteur (défaut : `sensor`)
+    pub sensor: String,
+    /// Modèle du topic des observations
+    pub observation_topic: String,
+    /// Modèle du topic des résumés de présence
+    pub presence_topic: String,
+    /// Période des résumés de présence (défaut : 60 s)
+    pub presence_interval: Duration,
+    /// Fenêtre (en secondes) définissant un appareil présent (défaut : 300 s)
+    pub presence_window: i64,
+    /// QoS MQTT (`0`, `1` ou `2`, défaut : `0`)
+    pub qos: u8,
+    /// Messages retenus pour les observations (défaut : `false`)
+    pub retain_observations: bool,
+    /// Messages retenus pour les résumés de présence (défaut : `true`)
+    pub retain_presence: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: String::from("127.0.0.1"),
+            port: 1883,
+            client_id: String::new(),
+            credentials: None,
+            sensor: String::from("sensor"),
+            observation_topic: String::from("sniffer/{sensor}/device/{mac}"),
+            presence_topic: String::from("sniffer/{sensor}/presence"),
+            presence_interval: Duration::from_secs(60),
+            presence_window: 300,
+            qos: 0,
+            retain_observations: false,
+            retain_presence: true,
+        }
+    }
+}
+
+impl MqttConfig {
+    // Remplace les variables d'un modèle de topic
+    fn topic(&self, template: &str, mac: &str) -> String {
+        template
+            .replace("{sensor}", &self.sensor)
+            .replace("{mac}", mac)
+    }
+}
+
+/// Résumé périodique des appareils présents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    /// Nom du capteur
+    pub sensor: String,
+    /// UNIX Timestamp (UTC) du résumé
+    pub ts: i64,
+    /// Fenêtre (en secondes) définissant un appareil présent
+    pub window: i64,
+    /// Nombre d'appareils présents
+    pub count: usize,
+    /// Adresses MAC des appareils présents
+    pub devices: Vec<String>,
+}
+
+/// Démarre la publication dans des threads
+///
+/// Retourne une erreur si la QoS est invalide
+pub fn start(config: MqttConfig) -> io::Result<()> {
+    let qos = match config.qos {
+        0 => QoS::AtMostOnce,
+        1 => QoS::AtLeastOnce,
+        2 => QoS::ExactlyOnce,
+        qos => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("QoS MQTT invalide : {qos}"),
+            ))
+        }
+    };
+    let client_id = if config.client_id.is_empty() {
+        format!("wifisnipe-{}", config.sensor)
+    } else {
+        config.client_id.clone()
+    };
+    let mut options = MqttOptions::new(client_id, config.host.clone(), config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some((user, password)) = &config.credentials {
+        options.set_credentials(user, password);
+    }
+    let (client, mut connection) = Client::new(options, REQUEST_CAPACITY);
+    PUBLISHERS
+        .lock()
+        .unwrap()
+        .push((client.clone(), config.clone(), qos));
+
+    // Maintiens la connexion au broker
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            if notification.is_err() {
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    });
+
+    // Publie chaque observation
+    let mut events = subscribe();
+    let observation_client = client.clone();
+    let observation_config = config.clone();
+    thread::spawn(move || loop {
+        match events.blocking_recv() {
+            Ok(Event::Observation(obs)) => {
+                let topic =
+                    observation_config.topic(&observation_config.observation_topic, &obs.mac);
+                if let Ok(payload) = serde_json::to_vec(&obs) {
+                    let _ = observation_client.try_publish(
+                        topic,
+                        qos,
+                        observation_config.retain_observations,
+                        payload,
+                    );
+                }
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    });
+
+    // Publie les résumés de présence
+    thread::spawn(move || loop {
+        thread::sleep(config.presence_interval);
+        let now = Utc::now().timestamp();
+        let devices: Vec<String> = snapshot()
+            .into_iter()
+            .filter(|data| now - data.ts <= config.presence_window)
+            .map(|data| data.mac)
+            .collect();
+        let presence = Presence {
+            sensor: config.sensor.clone(),
+            ts: now,
+            window: config.presence_window,
+            count: devices.len(),
+            devices,
+        };
+        if let Ok(payload) = serde_json::to_vec(&presence) {
+            let _ = client.try_publish(
+                config.topic(&config.presence_topic, ""),
+                qos,
+                config.retain_presence,
+                payload,
+            );
+        }
+    });
+    Ok(())
+}
+
+/// Efface les messages retenus des topics d'observation des adresses MAC
+///
+/// Retourne le nombre de messages vides publiés
+pub(crate) fn purge(macs: &HashSet<String>) -> usize {
+    let mut count = 0;
+    for (client, config, qos) in PUBLISHERS.lock().unwrap().iter() {
+        if !config.retain_observations {
+            continue;
+        }
+        for mac in macs {
+            let topic = config.topic(&config.observation_topic, mac);
+            if client.try_publish(topic, *qos, true, Vec::new()).is_ok() {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Fonction publique pour démarrer la publication MQTT avec les topics par défaut
+///
+/// Paramètres :
+///
+/// | Nom du paramètre | Usage |
+/// | ---------------- | ----- |
+/// | `host` | Hôte du broker |
+/// | `port` | Port du broker |
+/// | `sensor` | Nom du capteur utilisé dans les topics |
+///
+/// Retourne
+///   - `true` : Si la publication a démarré
+///   - `false` : Si les paramètres sont invalides
+pub extern "C" fn start_mqtt(host: AsciiPointer, port: u16, sensor: AsciiPointer) -> bool {
+    let (host, sensor) = match (host.as_str(), sensor.as_str()) {
+        (Ok(host), Ok(sensor)) => (host.to_owned(), sensor.to_owned()),
+        _ => return false,
+    };
+    start(MqttConfig {
+        host,
+        port,
+        sensor,
+        ..MqttConfig::default()
+    })
+    .is_ok()
+}
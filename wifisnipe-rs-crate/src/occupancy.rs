@@ -0,0 +1,218 @@
+//! Série temporelle d'occupation : nombre d'appareils distincts par minute ou par heure
+//!
+//! La série est calculée à partir de l'historique des observations ([`crate::observations()`]).
+//! Chaque point compte les appareils vus au moins une fois dans l'intervalle, éventuellement
+//! au-dessus d'un RSSI minimal. Les intervalles sans observation sont présents avec `0` appareil.
+//!
+//! Les adresses MAC aléatoires sont traitées selon [`RandomizedMode`] :
+//!
+//! | Mode | Comptage |
+//! | ---- | -------- |
+//! | `count` | Chaque adresse compte pour un appareil |
+//! | `exclude` | Les adresses aléatoires sont ignorées |
+//! | `group` | Les adresses regroupées par [`crate::groups`] comptent pour un seul appareil |
+//!
+//! ## CSV
+//!
+//! [`to_csv()`] produit une ligne d'en-tête `ts,devices,randomized` puis une ligne par intervalle.
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::{groups, observations, oui};
+
+/// Durée des intervalles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    /// Une minute
+    #[default]
+    Minute,
+    /// Une heure
+    Hour,
+}
+
+impl Bucket {
+    /// Durée en secondes
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Bucket::Minute => 60,
+            Bucket::Hour => 3600,
+        }
+    }
+}
+
+/// Traitement des adresses MAC aléatoires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomizedMode {
+    /// Chaque adresse compte pour un appareil
+    #[default]
+    Count,
+    /// Les adresses aléatoires sont ignorées
+    Exclude,
+    /// Les adresses d'un même groupe comptent pour un appareil
+    Group,
+}
+
+/// Paramètres de la série
+///
+/// Utiliser [`OccupancyQuery::default()`] pour les valeurs par défaut (par minute, sans filtre)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OccupancyQuery {
+    /// Durée des intervalles
+    pub bucket: Bucket,
+    /// UNIX Timestamp (UTC) minimal, inclus
+    pub since: Option<i64>,
+    /// UNIX Timestamp (UTC) maximal, inclus
+    pub until: Option<i64>,
+    /// RSSI minimal d'une observation pour être comptée
+    pub min_rssi: Option<i32>,
+    /// Traitement des adresses MAC aléatoires
+    pub randomized: RandomizedMode,
+}
+
+/// Point de la série
+///
+/// Champs:
+/// | Champ        | Type  | Description                                   |
+/// |--------------|-------|-----------------------------------------------|
+/// | `ts`         | `int` | UNIX Timestamp (UTC) du début de l'intervalle |
+/// | `devices`    | `int` | Appareils distincts vus dans l'intervalle     |
+/// | `randomized` | `int` | Dont appareils à adresse aléatoire            |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OccupancyPoint {
+    /// Début de l'intervalle
+    pub ts: i64,
+    /// Appareils distincts
+    pub devices: usize,
+    /// Appareils à adresse aléatoire
+    pub randomized: usize,
+}
+
+/// Calcule la série d'occupation
+pub fn occupancy(query: &OccupancyQuery) -> Vec<OccupancyPoint> {
+    let size = query.bucket.seconds();
+    // Identifiant d'appareil de chaque adresse en mode groupe
+    let group_ids: HashMap<String, String> = match query.randomized {
+        RandomizedMode::Group => groups::device_groups()
+            .into_iter()
+            .flat_map(|group| {
+                let id = group.id;
+                group.macs.into_iter().map(move |mac| (mac, id.clone()))
+            })
+            .collect(),
+        _ => HashMap::new(),
+    };
+
+    // Appareils vus par intervalle, avec leur caractère aléatoire
+    let mut buckets: BTreeMap<i64, HashSet<(String, bool)>> = BTreeMap::new();
+    for obs in observations() {
+        if obs.mac.is_empty()
+            || query.since.is_some_and(|since| obs.ts < since)
+            || query.until.is_some_and(|until| obs.ts > until)
+            || query.min_rssi.is_some_and(|min| obs.rssi < min)
+        {
+            continue;
+        }
+        let randomized = oui::is_randomized(&obs.mac);
+        if randomized && query.randomized == RandomizedMode::Exclude {
+            continue;
+        }
+        let device = group_ids.get(&obs.mac).cloned().unwrap_or(obs.mac);
+        buckets
+            .entry(obs.ts.div_euclid(size) * size)
+            .or_default()
+            .insert((device, randomized));
+    }
+
+    let (first, last) = match (buckets.keys().next(), buckets.keys().next_back()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Vec::new(),
+    };
+    (0..=(last - first) / size)
+        .map(|i| {
+            let ts = first + i * size;
+            let devices = buckets.get(&ts);
+            OccupancyPoint {
+                ts,
+                devices: devices.map_or(0, |devices| devices.len()),
+                randomized: devices.map_or(0, |devices| {
+                    devices.iter().filter(|(_, randomized)| *randomized).count()
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Convertit une série en CSV (séparateur `,`, fin de ligne `\n`)
+pub fn to_csv(points: &[OccupancyPoint]) -> String {
+    let mut csv = String::from("ts,devices,randomized\n");
+    for point in points {
+        let _ = writeln!(csv, "{},{},{}", point.ts, point.devices, point.randomized);
+    }
+    csv
+}
+
+// Paramètres FFI communs à get_occupancy et get_occupancy_csv
+fn ffi_query(bucket: u8, since: i64, until: i64, min_rssi: i32, randomized: u8) -> OccupancyQuery {
+    OccupancyQuery {
+        bucket: if bucket == 1 {
+            Bucket::Hour
+        } else {
+            Bucket::Minute
+        },
+        since: Some(since).filter(|since| *since != 0),
+        until: Some(until).filter(|until| *until != 0),
+        min_rssi: Some(min_rssi).filter(|min| *min != 0),
+        randomized: match randomized {
+            1 => RandomizedMode::Exclude,
+            2 => RandomizedMode::Group,
+            _ => RandomizedMode::Count,
+        },
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Série d'occupation pour la génération d'un fichier JSON (voir [`OccupancyPoint`])
+///
+/// | Paramètre | Valeurs |
+/// | --------- | ------- |
+/// | `bucket` | `0` : minute, `1` : heure |
+/// | `since`, `until` | UNIX Timestamps (UTC) de la fenêtre, sans limite si `0` |
+/// | `min_rssi` | RSSI minimal, sans limite si `0` |
+/// | `randomized` | `0` : compter, `1` : exclure, `2` : regrouper les adresses aléatoires |
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_occupancy<'a>(
+    bucket: u8,
+    since: i64,
+    until: i64,
+    min_rssi: i32,
+    randomized: u8,
+) -> AsciiPointer<'a> {
+    let query = ffi_query(bucket, since, until, min_rssi, randomized);
+    crate::ffi_string(serde_json::to_string(&occupancy(&query)).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Série d'occupation au format CSV, paramètres identiques à [`get_occupancy()`]
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_occupancy_csv<'a>(
+    bucket: u8,
+    since: i64,
+    until: i64,
+    min_rssi: i32,
+    randomized: u8,
+) -> AsciiPointer<'a> {
+    let query = ffi_query(bucket, since, until, min_rssi, randomized);
+    crate::ffi_string(to_csv(&occupancy(&query)))
+}
@@ -0,0 +1,89 @@
+//! Identification du constructeur à partir de l'adresse MAC
+//!
+//! Les trois premiers octets d'une adresse MAC (OUI) sont attribués par l'IEEE à un constructeur.
+//!
+//! La table embarquée ne contient que les constructeurs les plus courants.
+//!
+//! Si le bit « administré localement » du premier octet est à `1`, l'adresse est aléatoire
+//! et ne correspond à aucun constructeur.
+
+/// Libellé retourné pour une adresse aléatoire
+pub const RANDOMIZED: &str = "Aléatoire";
+
+/// Libellé retourné pour un OUI absent de la table
+pub const UNKNOWN: &str = "Inconnu";
+
+/// Table des OUI connus (en majuscules, séparés par `:`)
+const OUI_TABLE: &[(&str, &str)] = &[
+    ("00:03:93", "Apple"),
+    ("00:0A:95", "Apple"),
+    ("00:1B:63", "Apple"),
+    ("00:25:00", "Apple"),
+    ("28:CF:E9", "Apple"),
+    ("3C:15:C2", "Apple"),
+    ("70:56:81", "Apple"),
+    ("A4:5E:60", "Apple"),
+    ("AC:BC:32", "Apple"),
+    ("F0:18:98", "Apple"),
+    ("00:00:0C", "Cisco"),
+    ("18:FE:34", "Espressif"),
+    ("24:0A:C4", "Espressif"),
+    ("30:AE:A4", "Espressif"),
+    ("5C:CF:7F", "Espressif"),
+    ("60:01:94", "Espressif"),
+    ("84:F3:EB", "Espressif"),
+    ("A4:CF:12", "Espressif"),
+    ("BC:DD:C2", "Espressif"),
+    ("EC:FA:BC", "Espressif"),
+    ("3C:5A:B4", "Google"),
+    ("F4:F5:E8", "Google"),
+    ("00:E0:FC", "Huawei"),
+    ("00:1B:21", "Intel"),
+    ("00:50:F2", "Microsoft"),
+    ("00:09:BF", "Nintendo"),
+    ("00:17:AB", "Nintendo"),
+    ("B8:27:EB", "Raspberry Pi"),
+    ("DC:A6:32", "Raspberry Pi"),
+    ("E4:5F:01", "Raspberry Pi"),
+    ("00:15:99", "Samsung"),
+    ("00:16:32", "Samsung"),
+    ("50:C7:BF", "TP-Link"),
+    ("F4:F2:6D", "TP-Link"),
+    ("64:09:80", "Xiaomi"),
+];
+
+/// Indique si l'adresse MAC est administrée localement (aléatoire)
+///
+/// Retourne `false` si l'adresse est mal formée
+pub fn is_randomized(mac: &str) -> bool {
+    mac.get(0..2)
+        .and_then(|first| u8::from_str_radix(first, 16).ok())
+        .map(|first| first & 0b10 != 0)
+        .unwrap_or(false)
+}
+
+/// Retourne le nom du constructeur associé à l'adresse MAC
+///
+/// | Cas | Valeur |
+/// | --- | ------ |
+/// | Adresse aléatoire | [`RANDOMIZED`] |
+/// | OUI absent de la table | [`UNKNOWN`] |
+/// | Mode confidentialité (voir [`crate::privacy`]) | [`UNKNOWN`] |
+pub fn vendor(mac: &str) -> &'static str {
+    if is_randomized(mac) {
+        return RANDOMIZED;
+    }
+    // Le préfixe d'un pseudonyme n'est pas un OUI
+    if crate::privacy::is_enabled() {
+        return UNKNOWN;
+    }
+    let prefix = match mac.get(0..8) {
+        Some(prefix) => prefix.to_uppercase().replace('-', ":"),
+        None => return UNKNOWN,
+    };
+    OUI_TABLE
+        .iter()
+        .find(|(oui, _)| *oui == prefix)
+        .map(|(_, name)| *name)
+        .unwrap_or(UNKNOWN)
+}
@@ -0,0 +1,304 @@
+//! Mode confidentialité : pseudonymisation des adresses MAC et des SSIDs
+//!
+//! Une fois activé, chaque trame est transformée avant son enregistrement : aucune adresse MAC
+//! ni aucun SSID brut n'atteint les tables en mémoire, et donc ni les exports (JSON, CSV, HTTP,
+//! MQTT, webhooks), ni les journaux, ni les fonctions FFI.
+//!
+//! Les adresses MAC (émetteur, destinataire, BSSID, points d'accès) sont remplacées par un
+//! HMAC-SHA256 de l'adresse, tronqué à 6 octets et présenté comme une adresse MAC. Le bit
+//! « administrée localement » de l'adresse d'origine est conservé pour que les statistiques
+//! sur les adresses aléatoires restent justes ; le constructeur n'est plus connu. Les adresses
+//! de groupe (broadcast, multicast), qui n'identifient pas d'appareil, sont conservées.
+//!
+//! | Option | Effet |
+//! | ------ | ----- |
+//! | `key` | Clé du HMAC : une même clé donne les mêmes pseudonymes d'un lancement à l'autre et d'un capteur à l'autre. Aléatoire si vide |
+//! | `daily_salt` | La date (UTC) de la trame est ajoutée au HMAC : les pseudonymes changent chaque jour |
+//! | `ssid` | Traitement des SSIDs (voir [`SsidPolicy`]) |
+//!
+//! Le mode ne peut être changé qu'avant la première trame enregistrée, afin que les tables ne
+//! mélangent jamais adresses brutes et pseudonymes.
+//!
+//! Pour retrouver un appareil connu, calculer son pseudonyme avec [`mac()`] ou [`get_pseudonym()`].
+//! Les règles `mac` et `ssid` de la liste de surveillance sont comparées aux pseudonymes,
+//! les règles `prefix` ne correspondent plus à aucun appareil (voir [`crate::watchlist`]).
+
+use chrono::{TimeZone, Utc};
+use hmac_sha256::HMAC;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::{ap, is_started, observation_count, Observation};
+
+lazy_static! {
+    /// Configuration en cours, `None` si le mode est désactivé
+    static ref CONFIG: Mutex<Option<PrivacyConfig>> = Mutex::new(None);
+}
+
+/// Traitement des SSIDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsidPolicy {
+    /// SSID conservé tel quel
+    Keep,
+    /// SSID remplacé par `#` suivi de 12 caractères hexadécimaux du HMAC
+    Hash,
+    /// SSID tronqué à ce nombre de caractères
+    Truncate(usize),
+    /// SSID supprimé
+    Drop,
+}
+
+/// Configuration du mode confidentialité
+///
+/// Utiliser [`PrivacyConfig::default()`] pour les valeurs par défaut :
+///
+/// ```
+/// use libwifisnipe::privacy::{PrivacyConfig, SsidPolicy};
+///
+/// let config = PrivacyConfig {
+///     key: b"secret du site".to_vec(),
+///     daily_salt: true,
+///     ssid: SsidPolicy::Drop,
+/// };
+/// assert_ne!(config.mac("da:a1:19:00:00:01", 1700000000), "da:a1:19:00:00:01");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivacyConfig {
+    /// Clé du HMAC, aléatoire si vide (défaut)
+    pub key: Vec<u8>,
+    /// Pseudonymes renouvelés chaque jour (défaut : `false`)
+    pub daily_salt: bool,
+    /// Traitement des SSIDs (défaut : [`SsidPolicy::Hash`])
+    pub ssid: SsidPolicy,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        PrivacyConfig {
+            key: Vec::new(),
+            daily_salt: false,
+            ssid: SsidPolicy::Hash,
+        }
+    }
+}
+
+impl PrivacyConfig {
+    // HMAC d'une valeur, préfixée par son type et la date si le sel est quotidien
+    fn digest(&self, kind: &str, value: &str, ts: i64) -> [u8; 32] {
+        let salt = match Utc.timestamp_opt(ts, 0).single() {
+            Some(date) if self.daily_salt => date.format("%Y-%m-%d").to_string(),
+            _ => String::new(),
+        };
+        HMAC::mac(format!("{salt}|{kind}|{value}"), &self.key)
+    }
+
+    /// Pseudonyme d'une adresse MAC vue à `ts` (UNIX Timestamp UTC), vide si l'adresse est vide
+    pub fn mac(&self, mac: &str, ts: i64) -> String {
+        if mac.is_empty() {
+            return String::new();
+        }
+        let normalized = mac.to_lowercase().replace('-', ":");
+        let first = normalized
+            .get(0..2)
+            .and_then(|first| u8::from_str_radix(first, 16).ok())
+            .unwrap_or(0);
+        // Adresses de groupe conservées
+        if first & 1 != 0 {
+            return normalized;
+        }
+        let mut bytes = self.digest("mac", &normalized, ts);
+        // Adresse unicast, bit « administrée localement » de l'adresse d'origine
+        bytes[0] = (bytes[0] & 0b1111_1100) | (first & 0b10);
+        bytes[..6]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// SSID transformé selon [`PrivacyConfig::ssid`], vide si le SSID est vide
+    pub fn ssid(&self, ssid: &str, ts: i64) -> String {
+        if ssid.is_empty() {
+            return String::new();
+        }
+        match self.ssid {
+            SsidPolicy::Keep => ssid.to_owned(),
+            SsidPolicy::Hash => {
+                let bytes = self.digest("ssid", ssid, ts);
+                bytes[..6].iter().fold(String::from("#"), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                })
+            }
+            SsidPolicy::Truncate(len) => ssid.chars().take(len).collect(),
+            SsidPolicy::Drop => String::new(),
+        }
+    }
+}
+
+// Clé aléatoire de 32 octets, tirée du générateur aléatoire du système
+fn random_key() -> Option<Vec<u8>> {
+    let mut key = vec![0u8; 32];
+    getrandom::getrandom(&mut key).ok()?;
+    Some(key)
+}
+
+// Vérifie qu'aucune trame n'a encore été enregistrée
+fn is_pristine() -> bool {
+    !is_started() && observation_count() == 0 && ap::snapshot().is_empty()
+}
+
+/// Active le mode confidentialité
+///
+/// Retourne `false` si une trame a déjà été enregistrée, si l'enregistrement est en cours ou si
+/// la clé est vide et qu'aucune clé aléatoire n'a pu être tirée
+pub fn enable(config: PrivacyConfig) -> bool {
+    match prepare(config) {
+        Some(config) => {
+            *CONFIG.lock().unwrap() = Some(config);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Vérifie que le mode confidentialité peut être activé et tire la clé si elle est vide
+///
+/// Retourne `None` dans les cas où [`enable()`] échoue
+pub(crate) fn prepare(mut config: PrivacyConfig) -> Option<PrivacyConfig> {
+    if !is_pristine() {
+        return None;
+    }
+    if config.key.is_empty() {
+        config.key = random_key()?;
+    }
+    Some(config)
+}
+
+/// Désactive le mode confidentialité
+///
+/// Retourne `false` si une trame a déjà été enregistrée ou si l'enregistrement est en cours
+pub fn disable() -> bool {
+    if !is_pristine() {
+        return false;
+    }
+    *CONFIG.lock().unwrap() = None;
+    true
+}
+
+/// Indique si le mode confidentialité est activé
+pub fn is_enabled() -> bool {
+    CONFIG.lock().unwrap().is_some()
+}
+
+/// Pseudonyme d'une adresse MAC vue à `ts`, l'adresse elle-même si le mode est désactivé
+pub fn mac(mac: &str, ts: i64) -> String {
+    match CONFIG.lock().unwrap().as_ref() {
+        Some(config) => config.mac(mac, ts),
+        None => mac.to_owned(),
+    }
+}
+
+/// SSID transformé, le SSID lui-même si le mode est désactivé
+pub fn ssid(ssid: &str, ts: i64) -> String {
+    match CONFIG.lock().unwrap().as_ref() {
+        Some(config) => config.ssid(ssid, ts),
+        None => ssid.to_owned(),
+    }
+}
+
+/// Clé du HMAC en cours, `None` si le mode est désactivé
+pub(crate) fn key() -> Option<Vec<u8>> {
+    CONFIG
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|config| config.key.clone())
+}
+
+/// Pseudonymes d'une adresse MAC pour chaque jour entre `from` et `to` (UNIX Timestamps UTC)
+///
+/// Un seul pseudonyme sans sel quotidien, aucun si le mode est désactivé
+pub fn pseudonyms(mac: &str, from: i64, to: i64) -> Vec<String> {
+    let config = match CONFIG.lock().unwrap().clone() {
+        Some(config) => config,
+        None => return Vec::new(),
+    };
+    if !config.daily_salt {
+        return vec![config.mac(mac, to)];
+    }
+    let mut pseudonyms = Vec::new();
+    let mut day = from - from.rem_euclid(86400);
+    while day <= to {
+        pseudonyms.push(config.mac(mac, day));
+        day += 86400;
+    }
+    pseudonyms
+}
+
+/// Pseudonymise les identifiants d'une observation avant son enregistrement
+pub(crate) fn apply(obs: Observation) -> Observation {
+    let config = match CONFIG.lock().unwrap().clone() {
+        Some(config) => config,
+        None => return obs,
+    };
+    Observation {
+        mac: config.mac(&obs.mac, obs.ts),
+        ssid: config.ssid(&obs.ssid, obs.ts),
+        dst: config.mac(&obs.dst, obs.ts),
+        bssid: config.mac(&obs.bssid, obs.ts),
+        ..obs
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Active le mode confidentialité, avant le démarrage de l'enregistrement
+///
+/// Paramètres :
+///
+/// | Nom du paramètre | Usage |
+/// | ---------------- | ----- |
+/// | `key` | Clé du HMAC, vide pour une clé aléatoire |
+/// | `daily_salt` | Pseudonymes renouvelés chaque jour |
+/// | `ssid_mode` | `0` : conservés, `1` : hachés, `2` : tronqués à `ssid_len` caractères, `3` : supprimés |
+/// | `ssid_len` | Longueur des SSIDs tronqués |
+///
+/// Retourne `false` si les paramètres sont invalides ou si une trame a déjà été enregistrée
+pub extern "C" fn set_privacy(
+    key: AsciiPointer,
+    daily_salt: bool,
+    ssid_mode: u8,
+    ssid_len: u32,
+) -> bool {
+    let ssid = match ssid_mode {
+        0 => SsidPolicy::Keep,
+        1 => SsidPolicy::Hash,
+        2 => SsidPolicy::Truncate(ssid_len as usize),
+        3 => SsidPolicy::Drop,
+        _ => return false,
+    };
+    match key.as_str() {
+        Ok(key) => enable(PrivacyConfig {
+            key: key.as_bytes().to_vec(),
+            daily_salt,
+            ssid,
+        }),
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Pseudonyme d'une adresse MAC pour aujourd'hui, l'adresse elle-même si le mode est désactivé
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_pseudonym<'a>(mac: AsciiPointer) -> AsciiPointer<'a> {
+    crate::ffi_string(
+        mac.as_str()
+            .map(|mac| self::mac(mac, Utc::now().timestamp()))
+            .unwrap_or_default(),
+    )
+}
@@ -0,0 +1,364 @@
+//! Effacement des données d'un appareil, durée de conservation et journal d'audit
+//!
+//! ## Effacement
+//!
+//! [`erase()`] supprime toutes les données d'une adresse MAC : tables des appareils, historique
+//! des observations, sessions, index des SSIDs, zones, positions, alertes, points d'accès, notifications en
+//! attente (feature `webhook`) et lignes des journaux d'alertes
+//! ([`crate::watchlist::AlertRoute::Log`]). Les groupes et les séries d'occupation, calculés à
+//! partir de l'historique, ne la contiennent plus.
+//!
+//! L'adresse peut être donnée brute ou pseudonymisée. En mode confidentialité (voir
+//! [`crate::privacy`]), les pseudonymes de l'adresse brute sont recalculés pour chaque jour
+//! depuis la plus ancienne donnée conservée, toutes tables et journaux d'alertes confondus, et
+//! au moins sur la durée de conservation.
+//!
+//! Les messages retenus par le broker MQTT sur les topics d'observation de l'appareil sont
+//! effacés (feature `mqtt`, voir [`crate::mqtt`]). Les autres messages déjà publiés (MQTT,
+//! webhooks, flux d'évènements) ne peuvent pas être rappelés.
+//!
+//! ## Durée de conservation
+//!
+//! Avec [`set_max_age()`], un thread efface toutes les [`RETENTION_INTERVAL`] secondes les
+//! observations, sessions, changements de zone et alertes plus anciens que la durée de
+//! conservation, ainsi que les appareils qui n'ont pas été vus depuis.
+//!
+//! ## Journal d'audit
+//!
+//! Chaque effacement ayant supprimé des données est consigné ([`AuditEntry`]) en mémoire dans
+//! la limite de [`AUDIT_HISTORY`] entrées, et dans le fichier donné à [`set_audit_log()`]
+//! (une entrée JSON par ligne). L'adresse effacée n'y figure que sous forme d'empreinte
+//! HMAC-SHA256, calculée avec la clé donnée à [`set_audit_key()`] ou, à défaut, avec la clé du
+//! mode confidentialité. La clé n'est pas écrite dans le journal : sans elle, l'empreinte ne
+//! permet pas de retrouver l'adresse en essayant toutes les adresses possibles. Sans clé,
+//! l'adresse n'est pas consignée (`subject` vide).
+
+use chrono::Utc;
+use hmac_sha256::HMAC;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+use crate::{ap, checked_mac, localization, privacy, rssi, sessions};
+use crate::{ssids, watchlist, zones};
+
+/// Période du thread de conservation (secondes)
+pub const RETENTION_INTERVAL: u64 = 60;
+
+/// Nombre d'entrées du journal d'audit conservées en mémoire
+pub const AUDIT_HISTORY: usize = 1024;
+
+/// Durée de conservation en secondes, `0` pour aucune limite
+static MAX_AGE: AtomicI64 = AtomicI64::new(0);
+
+/// Démarrage unique du thread de conservation
+static RETENTION: Once = Once::new();
+
+lazy_static! {
+    /// Dernières entrées du journal d'audit
+    static ref AUDIT: Mutex<VecDeque<AuditEntry>> = Mutex::new(VecDeque::new());
+    /// Fichier du journal d'audit
+    static ref AUDIT_LOG: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// Clé des empreintes du journal d'audit, vide pour celle du mode confidentialité
+    static ref AUDIT_KEY: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+}
+
+/// Sélection des données à effacer
+pub(crate) enum Purge {
+    /// Adresses MAC, en minuscules
+    Macs(HashSet<String>),
+    /// Données antérieures à un UNIX Timestamp (UTC)
+    Before(i64),
+}
+
+impl Purge {
+    /// Vérifie qu'une donnée d'une adresse MAC datée de `ts` est à effacer
+    pub(crate) fn matches(&self, mac: &str, ts: i64) -> bool {
+        match self {
+            Purge::Macs(macs) => macs.contains(&mac.to_lowercase()),
+            Purge::Before(cutoff) => ts < *cutoff,
+        }
+    }
+}
+
+/// Type d'effacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Effacement des données d'un appareil
+    Erase,
+    /// Application de la durée de conservation
+    Retention,
+}
+
+/// Entrée du journal d'audit
+///
+/// Champs:
+/// | Champ     | Type     | Description                                                   |
+/// |-----------|----------|---------------------------------------------------------------|
+/// | `ts`      | `int`    | UNIX Timestamp (UTC) de l'effacement                          |
+/// | `action`  | `String` | `erase` ou `retention`                                        |
+/// | `subject` | `String` | Empreinte `hmac-sha256:` de l'adresse effacée (`erase` avec une clé, sinon `null`) |
+/// | `cutoff`  | `int`    | Date limite des données effacées (`retention`, sinon `null`)  |
+/// | `deleted` | `Object` | Nombre d'éléments effacés par table                           |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// Type d'effacement
+    pub action: AuditAction,
+    /// Empreinte de l'adresse effacée
+    pub subject: Option<String>,
+    /// Date limite des données effacées
+    pub cutoff: Option<i64>,
+    /// Nombre d'éléments effacés par table
+    pub deleted: BTreeMap<String, usize>,
+}
+
+impl AuditEntry {
+    /// Nombre total d'éléments effacés
+    pub fn total(&self) -> usize {
+        self.deleted.values().sum()
+    }
+}
+
+// Empreinte d'une adresse pour le journal d'audit, `None` sans clé
+fn fingerprint(mac: &str) -> Option<String> {
+    let key = {
+        let key = AUDIT_KEY.lock().unwrap();
+        if key.is_empty() {
+            privacy::key()?
+        } else {
+            key.clone()
+        }
+    };
+    let digest = HMAC::mac(mac.to_lowercase().replace('-', ":"), key);
+    let hex: String = digest[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Some(format!("hmac-sha256:{hex}"))
+}
+
+// Efface les données sélectionnées dans toutes les tables
+fn purge(purge: &Purge) -> BTreeMap<String, usize> {
+    let (devices, observations, known) = crate::purge(purge);
+    let mut deleted = BTreeMap::new();
+    deleted.insert(String::from("devices"), devices);
+    deleted.insert(String::from("observations"), observations);
+    deleted.insert(String::from("sessions"), sessions::purge(purge, &known));
+    deleted.insert(String::from("ssid_links"), ssids::purge(purge, &known));
+    deleted.insert(String::from("zone_changes"), zones::purge(purge, &known));
+    deleted.insert(String::from("readings"), localization::purge(purge, &known));
+    rssi::purge(&known);
+    let (alerts, logged) = watchlist::purge(purge, &known);
+    deleted.insert(String::from("alerts"), alerts);
+    deleted.insert(String::from("alert_log_lines"), logged);
+    deleted.insert(String::from("access_points"), ap::purge(purge));
+    #[cfg(feature = "webhook")]
+    deleted.insert(String::from("notifications"), crate::webhook::purge(purge));
+    #[cfg(feature = "mqtt")]
+    if let Purge::Macs(macs) = purge {
+        deleted.insert(String::from("retained_topics"), crate::mqtt::purge(macs));
+    }
+    deleted.retain(|_, count| *count > 0);
+    deleted
+}
+
+// Date de la plus ancienne donnée conservée, pour recalculer les pseudonymes
+fn oldest(now: i64) -> i64 {
+    let stored = [
+        crate::oldest(),
+        sessions::oldest(),
+        ssids::oldest(),
+        zones::oldest(),
+        localization::oldest(),
+        watchlist::oldest(),
+        ap::oldest(),
+        #[cfg(feature = "webhook")]
+        crate::webhook::oldest(),
+    ]
+    .into_iter()
+    .flatten()
+    .min()
+    .unwrap_or(now);
+    match max_age() {
+        0 => stored.min(now),
+        max_age => stored.min(now - max_age),
+    }
+}
+
+// Consigne un effacement
+fn audit(entry: AuditEntry) -> AuditEntry {
+    {
+        let mut audit = AUDIT.lock().unwrap();
+        if audit.len() >= AUDIT_HISTORY {
+            audit.pop_front();
+        }
+        audit.push_back(entry.clone());
+    }
+    if let Some(path) = AUDIT_LOG.lock().unwrap().as_ref() {
+        let line = serde_json::to_string(&entry).unwrap_or_default();
+        let _ = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{line}"));
+    }
+    entry
+}
+
+/// Efface toutes les données d'une adresse MAC, brute ou pseudonymisée
+///
+/// L'effacement est consigné dans le journal d'audit, même s'il n'a rien supprimé.
+/// Retourne `None` si l'adresse est invalide
+pub fn erase(mac: &str) -> Option<AuditEntry> {
+    let mac = checked_mac(mac).to_lowercase().replace('-', ":");
+    if mac.is_empty() {
+        return None;
+    }
+    let now = Utc::now().timestamp();
+    let mut macs: HashSet<String> = privacy::pseudonyms(&mac, oldest(now), now)
+        .into_iter()
+        .collect();
+    macs.insert(mac.clone());
+    let deleted = purge(&Purge::Macs(macs));
+    Some(audit(AuditEntry {
+        ts: now,
+        action: AuditAction::Erase,
+        subject: fingerprint(&mac),
+        cutoff: None,
+        deleted,
+    }))
+}
+
+/// Efface les données antérieures à `cutoff` (UNIX Timestamp UTC, exclu)
+///
+/// L'effacement n'est consigné dans le journal d'audit que s'il a supprimé des données
+pub fn purge_before(cutoff: i64) -> AuditEntry {
+    let entry = AuditEntry {
+        ts: Utc::now().timestamp(),
+        action: AuditAction::Retention,
+        subject: None,
+        cutoff: Some(cutoff),
+        deleted: purge(&Purge::Before(cutoff)),
+    };
+    if entry.total() > 0 {
+        audit(entry)
+    } else {
+        entry
+    }
+}
+
+/// Change la durée de conservation (secondes), `0` pour aucune limite
+///
+/// Démarre le thread de conservation au premier appel avec une durée
+pub fn set_max_age(seconds: i64) {
+    MAX_AGE.store(seconds.max(0), Ordering::SeqCst);
+    if seconds > 0 {
+        RETENTION.call_once(|| {
+            thread::spawn(|| loop {
+                let max_age = MAX_AGE.load(Ordering::SeqCst);
+                if max_age > 0 {
+                    purge_before(Utc::now().timestamp() - max_age);
+                }
+                thread::sleep(Duration::from_secs(RETENTION_INTERVAL));
+            });
+        });
+    }
+}
+
+/// Durée de conservation (secondes), `0` pour aucune limite
+pub fn max_age() -> i64 {
+    MAX_AGE.load(Ordering::SeqCst)
+}
+
+/// Consigne aussi les effacements dans un fichier, une entrée JSON par ligne
+pub fn set_audit_log(path: PathBuf) -> io::Result<()> {
+    // Vérifie que le fichier est accessible en écriture
+    OpenOptions::new().create(true).append(true).open(&path)?;
+    *AUDIT_LOG.lock().unwrap() = Some(path);
+    Ok(())
+}
+
+/// Change la clé des empreintes du journal d'audit, vide pour celle du mode confidentialité
+pub fn set_audit_key(key: &[u8]) {
+    *AUDIT_KEY.lock().unwrap() = key.to_vec();
+}
+
+/// Entrées du journal d'audit conservées en mémoire à partir de `since` (UNIX Timestamp UTC, inclus)
+pub fn audit_log(since: i64) -> Vec<AuditEntry> {
+    AUDIT
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.ts >= since)
+        .cloned()
+        .collect()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Efface toutes les données d'une adresse MAC, brute ou pseudonymisée
+///
+/// Retourne le nombre d'éléments effacés, `-1` si l'adresse est invalide
+pub extern "C" fn erase_device(mac: AsciiPointer) -> i32 {
+    mac.as_str()
+        .ok()
+        .and_then(erase)
+        .map_or(-1, |entry| entry.total() as i32)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Change la durée de conservation des données (secondes), `0` pour aucune limite
+pub extern "C" fn set_retention(max_age: u32) {
+    set_max_age(max_age as i64)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Consigne aussi les effacements dans un fichier, une entrée JSON par ligne
+///
+/// Retourne `false` si le fichier ne peut pas être ouvert en écriture
+pub extern "C" fn set_erasure_audit_log(path: AsciiPointer) -> bool {
+    match path.as_str() {
+        Ok(path) if !path.is_empty() => set_audit_log(PathBuf::from(path)).is_ok(),
+        _ => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Change la clé des empreintes des adresses effacées dans le journal d'audit, vide pour celle du
+/// mode confidentialité
+///
+/// Retourne `false` si la clé est illisible
+pub extern "C" fn set_erasure_audit_key(key: AsciiPointer) -> bool {
+    match key.as_str() {
+        Ok(key) => {
+            set_audit_key(key.as_bytes());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Journal d'audit des effacements depuis `since` (UNIX Timestamp UTC, `0` pour tout) pour la
+/// génération d'un fichier JSON (voir [`AuditEntry`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_audit_log<'a>(since: i64) -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&audit_log(since)).unwrap_or(String::from("")))
+}
@@ -0,0 +1,263 @@
+//! Lissage du RSSI et estimation de la distance
+//!
+//! Le RSSI brut d'un appareil varie de 10 à 20 dB d'une trame à l'autre. Chaque appareil
+//! a son propre filtre, appliqué à chaque observation (voir [`Smoothing`]). Les mesures des
+//! autres capteurs (voir [`crate::localization::add()`]) ont un filtre par capteur et par appareil.
+//!
+//! La distance est estimée à partir du RSSI lissé par le modèle log-distance :
+//!
+//! `distance = 10 ^ ((référence - rssi) / (10 × exposant))`
+//!
+//! | Paramètre | Description | Défaut |
+//! | --------- | ----------- | ------ |
+//! | `reference` | RSSI mesuré à 1 mètre (dBm) | `-40` |
+//! | `exponent` | Exposant d'affaiblissement : `2` en champ libre, `2.5` à `4` en intérieur | `2.7` |
+//!
+//! Les deux paramètres peuvent être mesurés sur place avec [`PathLoss::calibrate()`].
+
+use interoptopus::ffi_function;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+lazy_static! {
+    /// Filtre appliqué aux nouvelles valeurs
+    static ref SMOOTHING: Mutex<Smoothing> = Mutex::new(Smoothing::default());
+    /// Modèle de propagation
+    static ref PATH_LOSS: Mutex<PathLoss> = Mutex::new(PathLoss::default());
+    /// État du filtre de chaque adresse MAC
+    static ref FILTERS: Mutex<HashMap<String, FilterState>> = Mutex::new(HashMap::new());
+    /// État du filtre de chaque couple (capteur, adresse MAC) des autres capteurs
+    static ref SENSOR_FILTERS: Mutex<HashMap<(String, String), FilterState>> = Mutex::new(HashMap::new());
+}
+
+/// Filtre de lissage du RSSI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Moyenne mobile exponentielle : `lissé = alpha × rssi + (1 - alpha) × lissé`
+    Ema {
+        /// Poids de la nouvelle valeur, entre `0` (exclu) et `1`
+        alpha: f64,
+    },
+    /// Filtre de Kalman à une dimension (RSSI supposé constant entre deux trames)
+    Kalman {
+        /// Variance du déplacement de l'appareil entre deux trames (dB²)
+        process_noise: f64,
+        /// Variance de la mesure du RSSI (dB²)
+        measurement_noise: f64,
+    },
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Smoothing::Ema { alpha: 0.3 }
+    }
+}
+
+impl Smoothing {
+    /// Vérifie les paramètres du filtre
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Smoothing::Ema { alpha } => alpha > 0.0 && alpha <= 1.0,
+            Smoothing::Kalman {
+                process_noise,
+                measurement_noise,
+            } => process_noise >= 0.0 && measurement_noise > 0.0,
+        }
+    }
+}
+
+/// Modèle de propagation log-distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathLoss {
+    /// RSSI à 1 mètre (dBm)
+    pub reference: f64,
+    /// Exposant d'affaiblissement
+    pub exponent: f64,
+}
+
+impl Default for PathLoss {
+    fn default() -> Self {
+        PathLoss {
+            reference: -40.0,
+            exponent: 2.7,
+        }
+    }
+}
+
+impl PathLoss {
+    /// Distance estimée (mètres) pour un RSSI
+    pub fn distance(&self, rssi: f64) -> f64 {
+        10f64.powf((self.reference - rssi) / (10.0 * self.exponent))
+    }
+
+    /// Ajuste le modèle sur des mesures `(distance en mètres, RSSI)` par moindres carrés
+    ///
+    /// Retourne `None` s'il y a moins de deux distances différentes ou si l'exposant obtenu
+    /// n'est pas positif
+    pub fn calibrate(samples: &[(f64, f64)]) -> Option<PathLoss> {
+        // rssi = référence - 10 × exposant × log10(distance)
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .filter(|(distance, _)| *distance > 0.0)
+            .map(|(distance, rssi)| (-10.0 * distance.log10(), *rssi))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let var_x: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if points.len() < 2 || var_x < f64::EPSILON {
+            return None;
+        }
+        let cov: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let exponent = cov / var_x;
+        (exponent > 0.0).then_some(PathLoss {
+            reference: mean_y - exponent * mean_x,
+            exponent,
+        })
+    }
+}
+
+// État du filtre d'un appareil
+#[derive(Debug, Clone, Copy)]
+struct FilterState {
+    value: f64,
+    // Variance de l'estimation (Kalman uniquement)
+    variance: f64,
+}
+
+/// Applique le filtre d'un appareil à un nouveau RSSI
+///
+/// Retourne le RSSI lissé
+pub(crate) fn update(mac: &str, rssi: i32) -> f64 {
+    apply(&mut FILTERS.lock().unwrap(), mac.to_owned(), rssi)
+}
+
+/// Applique le filtre d'un appareil vu par un autre capteur à un nouveau RSSI
+///
+/// Retourne le RSSI lissé
+pub(crate) fn update_sensor(sensor: &str, mac: &str, rssi: i32) -> f64 {
+    apply(
+        &mut SENSOR_FILTERS.lock().unwrap(),
+        (sensor.to_owned(), mac.to_owned()),
+        rssi,
+    )
+}
+
+// Applique le filtre en cours à l'état de `key`
+fn apply<K: Eq + Hash>(filters: &mut HashMap<K, FilterState>, key: K, rssi: i32) -> f64 {
+    let smoothing = *SMOOTHING.lock().unwrap();
+    let rssi = rssi as f64;
+    let state = filters.entry(key).or_insert(FilterState {
+        value: rssi,
+        variance: match smoothing {
+            Smoothing::Kalman {
+                measurement_noise, ..
+            } => measurement_noise,
+            Smoothing::Ema { .. } => 0.0,
+        },
+    });
+    match smoothing {
+        Smoothing::Ema { alpha } => state.value = alpha * rssi + (1.0 - alpha) * state.value,
+        Smoothing::Kalman {
+            process_noise,
+            measurement_noise,
+        } => {
+            let variance = state.variance + process_noise;
+            let gain = variance / (variance + measurement_noise);
+            state.value += gain * (rssi - state.value);
+            state.variance = (1.0 - gain) * variance;
+        }
+    }
+    state.value
+}
+
+/// RSSI lissé d'un appareil
+pub fn smoothed(mac: &str) -> Option<f64> {
+    FILTERS.lock().unwrap().get(mac).map(|state| state.value)
+}
+
+/// Distance estimée (mètres) pour un RSSI, selon le modèle en cours
+pub fn distance(rssi: f64) -> f64 {
+    PATH_LOSS.lock().unwrap().distance(rssi)
+}
+
+/// Change le filtre de lissage
+///
+/// Les filtres de tous les appareils repartent de leur prochaine valeur.
+/// Retourne `false` si les paramètres sont invalides (voir [`Smoothing::is_valid()`])
+pub fn set_smoothing(smoothing: Smoothing) -> bool {
+    if !smoothing.is_valid() {
+        return false;
+    }
+    *SMOOTHING.lock().unwrap() = smoothing;
+    FILTERS.lock().unwrap().clear();
+    SENSOR_FILTERS.lock().unwrap().clear();
+    true
+}
+
+/// Change le modèle de propagation
+///
+/// Retourne `false` si l'exposant n'est pas un nombre positif ou si le RSSI de référence
+/// n'est pas un nombre
+pub fn set_path_loss(path_loss: PathLoss) -> bool {
+    if !path_loss.exponent.is_finite()
+        || path_loss.exponent <= 0.0
+        || !path_loss.reference.is_finite()
+    {
+        return false;
+    }
+    *PATH_LOSS.lock().unwrap() = path_loss;
+    true
+}
+
+/// Modèle de propagation en cours
+pub fn path_loss() -> PathLoss {
+    *PATH_LOSS.lock().unwrap()
+}
+
+/// Efface le filtre des adresses MAC effacées
+pub(crate) fn purge(known: &HashSet<String>) {
+    FILTERS.lock().unwrap().retain(|mac, _| known.contains(mac));
+    SENSOR_FILTERS
+        .lock()
+        .unwrap()
+        .retain(|(_, mac), _| known.contains(mac));
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Lisse le RSSI par moyenne mobile exponentielle de poids `alpha` (entre `0` exclu et `1`)
+///
+/// Retourne `false` si `alpha` est invalide
+pub extern "C" fn set_rssi_ema(alpha: f64) -> bool {
+    set_smoothing(Smoothing::Ema { alpha })
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Lisse le RSSI par filtre de Kalman (variances en dB²)
+///
+/// Retourne `false` si les variances sont invalides
+pub extern "C" fn set_rssi_kalman(process_noise: f64, measurement_noise: f64) -> bool {
+    set_smoothing(Smoothing::Kalman {
+        process_noise,
+        measurement_noise,
+    })
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Calibre l'estimation de distance : RSSI à 1 mètre (dBm) et exposant d'affaiblissement
+///
+/// Retourne `false` si l'exposant n'est pas un nombre positif ou si le RSSI de référence
+/// n'est pas un nombre
+pub extern "C" fn set_distance_model(reference: f64, exponent: f64) -> bool {
+    set_path_loss(PathLoss {
+        reference,
+        exponent,
+    })
+}
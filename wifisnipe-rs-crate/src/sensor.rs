@@ -0,0 +1,114 @@
+//! État de la carte, envoyé périodiquement par `sniper.lua`
+//!
+//! ## Schéma
+//!
+//! - `\u{2}STATUS\u{31}uptime\u{31}canal\u{31}heap\u{31}trames\u{31}version\u{31}\u{3}`
+//!
+//! | Champ | Description |
+//! | ----- | ----------- |
+//! | `uptime` | Secondes depuis le démarrage de la carte |
+//! | `canal` | Canal écouté |
+//! | `heap` | Mémoire libre en octets |
+//! | `trames` | Trames envoyées depuis le démarrage |
+//! | `version` | Version de `sniper.lua` |
+//!
+//! La carte envoie cette trame toutes les [`STATUS_INTERVAL`] secondes, ainsi qu'en réponse
+//! à la commande `STATUS`. L'absence de trame d'état signale une carte bloquée ou débranchée,
+//! et non l'absence d'appareils.
+
+use chrono::Utc;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Période d'envoi de l'état par la carte (en secondes)
+pub const STATUS_INTERVAL: i64 = 10;
+
+lazy_static! {
+    /// Dernier état reçu
+    static ref STATUS: Mutex<Option<SensorStatus>> = Mutex::new(None);
+}
+
+/// État de la carte
+///
+/// Champs:
+/// | Champ     | Type     | Description                                   |
+/// |-----------|----------|-----------------------------------------------|
+/// | `ts`      | `int`    | UNIX Timestamp (UTC) de réception sur le PC   |
+/// | `uptime`  | `int`    | Secondes depuis le démarrage de la carte      |
+/// | `channel` | `int`    | Canal écouté                                  |
+/// | `heap`    | `int`    | Mémoire libre en octets                       |
+/// | `frames`  | `int`    | Trames envoyées depuis le démarrage           |
+/// | `version` | `String` | Version de `sniper.lua`                       |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SensorStatus {
+    /// UNIX Timestamp (UTC) de réception
+    pub ts: i64,
+    /// Secondes depuis le démarrage de la carte
+    pub uptime: u64,
+    /// Canal écouté
+    pub channel: u32,
+    /// Mémoire libre en octets
+    pub heap: u64,
+    /// Trames envoyées depuis le démarrage
+    pub frames: u64,
+    /// Version de `sniper.lua`
+    pub version: String,
+}
+
+impl SensorStatus {
+    /// Secondes depuis la réception de l'état
+    pub fn age(&self) -> i64 {
+        Utc::now().timestamp() - self.ts
+    }
+
+    /// Indique si la carte n'a pas envoyé d'état depuis plus de trois périodes
+    pub fn is_stale(&self) -> bool {
+        self.age() > 3 * STATUS_INTERVAL
+    }
+}
+
+/// Enregistre une trame d'état (champs après `STATUS`)
+///
+/// Retourne `false` si la trame est mal formée
+pub(crate) fn store(fields: &[&str]) -> bool {
+    if fields.len() < 5 {
+        return false;
+    }
+    let status = match (
+        fields[0].parse(),
+        fields[1].parse(),
+        fields[2].parse(),
+        fields[3].parse(),
+    ) {
+        (Ok(uptime), Ok(channel), Ok(heap), Ok(frames)) => SensorStatus {
+            ts: Utc::now().timestamp(),
+            uptime,
+            channel,
+            heap,
+            frames,
+            version: fields[4].to_owned(),
+        },
+        _ => return false,
+    };
+    *STATUS.lock().unwrap() = Some(status);
+    true
+}
+
+/// Dernier état reçu de la carte
+pub fn status() -> Option<SensorStatus> {
+    STATUS.lock().unwrap().clone()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Dernier état reçu de la carte en JSON (voir [`SensorStatus`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+///
+/// Retourne `null` (JSON) si aucun état n'a été reçu
+pub extern "C" fn get_sensor_status<'a>() -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&status()).unwrap_or(String::from("")))
+}
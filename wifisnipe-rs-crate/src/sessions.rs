@@ -0,0 +1,325 @@
+//! Sessions de présence : arrivée, départ et durée de présence des appareils
+//!
+//! Une session commence à la première observation d'une adresse MAC et se termine lorsque
+//! l'adresse n'a plus été vue pendant le délai d'absence (voir [`set_absence_timeout()`]).
+//! Une nouvelle observation après ce délai ouvre une nouvelle session.
+//!
+//! Le départ d'un appareil est constaté à la réception d'une trame d'un autre appareil,
+//! à la consultation des sessions, par un thread démarré à la première observation (toutes
+//! les [`EXPIRY_INTERVAL`] secondes au plus) ou à la demande avec [`expire()`].
+//! Les évènements [`Event::Arrive`] et [`Event::Leave`] sont alors publiés (voir [`crate::events`]).
+//!
+//! Les sessions terminées sont conservées dans la limite de [`SESSION_CAPACITY`].
+
+use chrono::Utc;
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+use crate::events::{self, Event};
+use crate::retention::Purge;
+use crate::Observation;
+
+/// Délai d'absence par défaut (secondes)
+pub const DEFAULT_ABSENCE_TIMEOUT: i64 = 300;
+
+/// Intervalle maximal (secondes) entre deux recherches de sessions expirées par le thread
+///
+/// Le thread attend le délai d'absence s'il est plus court
+pub const EXPIRY_INTERVAL: i64 = 10;
+
+/// Nombre maximal de sessions terminées conservées
+pub const SESSION_CAPACITY: usize = 100_000;
+
+/// Délai d'absence (secondes)
+static ABSENCE_TIMEOUT: AtomicI64 = AtomicI64::new(DEFAULT_ABSENCE_TIMEOUT);
+
+/// Démarrage du thread d'expiration
+static EXPIRY: Once = Once::new();
+
+lazy_static! {
+    /// Sessions ouvertes et terminées
+    static ref SESSIONS: Mutex<Sessions> = Mutex::new(Sessions::default());
+    /// Verrou de l'attente du thread d'expiration
+    static ref EXPIRY_LOCK: Mutex<()> = Mutex::new(());
+    /// Réveille le thread d'expiration au changement du délai d'absence
+    static ref EXPIRY_WAKE: Condvar = Condvar::new();
+}
+
+#[derive(Default)]
+struct Sessions {
+    // Sessions en cours, par adresse MAC
+    open: HashMap<String, Session>,
+    // Sessions terminées, de la plus ancienne à la plus récente
+    closed: VecDeque<Session>,
+}
+
+impl Sessions {
+    // Termine les sessions sans observation depuis le délai d'absence
+    fn expire(&mut self, now: i64) -> Vec<Event> {
+        let timeout = absence_timeout();
+        let expired: Vec<String> = self
+            .open
+            .values()
+            .filter(|session| now - session.end > timeout)
+            .map(|session| session.mac.clone())
+            .collect();
+        let mut events = Vec::new();
+        for mac in expired {
+            if let Some(session) = self.open.remove(&mac) {
+                events.push(Event::Leave(self.close(session)));
+            }
+        }
+        events
+    }
+
+    fn close(&mut self, mut session: Session) -> Session {
+        session.open = false;
+        if self.closed.len() >= SESSION_CAPACITY {
+            self.closed.pop_front();
+        }
+        self.closed.push_back(session.clone());
+        session
+    }
+}
+
+/// Session de présence d'un appareil
+///
+/// Champs:
+/// | Champ       | Type     | Description                                   |
+/// |-------------|----------|-----------------------------------------------|
+/// | `mac`       | `String` | Adresse MAC                                   |
+/// | `start`     | `int`    | UNIX Timestamp (UTC) de la première observation |
+/// | `end`       | `int`    | UNIX Timestamp (UTC) de la dernière observation |
+/// | `duration`  | `int`    | Durée de présence en secondes (`end - start`) |
+/// | `peak_rssi` | `int`    | RSSI maximal                                  |
+/// | `frames`    | `int`    | Nombre d'observations                         |
+/// | `open`      | `bool`   | `true` si l'appareil est toujours présent     |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Adresse MAC
+    pub mac: String,
+    /// Début
+    pub start: i64,
+    /// Fin
+    pub end: i64,
+    /// Durée (secondes)
+    pub duration: i64,
+    /// RSSI maximal
+    pub peak_rssi: i32,
+    /// Nombre d'observations
+    pub frames: u64,
+    /// Session en cours
+    pub open: bool,
+}
+
+/// Statistiques de durée de présence
+///
+/// Champs:
+/// | Champ      | Type    | Description                          |
+/// |------------|---------|--------------------------------------|
+/// | `sessions` | `int`   | Nombre de sessions                   |
+/// | `devices`  | `int`   | Nombre d'adresses MAC distinctes     |
+/// | `total`    | `int`   | Somme des durées (secondes)          |
+/// | `mean`     | `float` | Durée moyenne (secondes)             |
+/// | `median`   | `int`   | Durée médiane (secondes)             |
+/// | `max`      | `int`   | Durée maximale (secondes)            |
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DwellStats {
+    /// Nombre de sessions
+    pub sessions: usize,
+    /// Nombre d'adresses MAC distinctes
+    pub devices: usize,
+    /// Somme des durées
+    pub total: i64,
+    /// Durée moyenne
+    pub mean: f64,
+    /// Durée médiane
+    pub median: i64,
+    /// Durée maximale
+    pub max: i64,
+}
+
+/// Délai d'absence (secondes) après lequel une session est terminée
+pub fn absence_timeout() -> i64 {
+    ABSENCE_TIMEOUT.load(Ordering::SeqCst)
+}
+
+/// Met à jour la session d'une observation
+///
+/// Retourne les évènements de départ des sessions expirées, puis l'évènement d'arrivée
+/// si l'observation ouvre une session
+pub(crate) fn record(obs: &Observation) -> Vec<Event> {
+    EXPIRY.call_once(|| {
+        thread::spawn(|| loop {
+            let interval = Duration::from_secs(absence_timeout().min(EXPIRY_INTERVAL) as u64);
+            let lock = EXPIRY_LOCK.lock().unwrap();
+            drop(EXPIRY_WAKE.wait_timeout(lock, interval).unwrap());
+            expire(Utc::now().timestamp());
+        });
+    });
+    let mut sessions = SESSIONS.lock().unwrap();
+    let mut events = sessions.expire(obs.ts);
+    match sessions.open.get_mut(&obs.mac) {
+        Some(session) => {
+            session.end = session.end.max(obs.ts);
+            session.duration = session.end - session.start;
+            session.peak_rssi = session.peak_rssi.max(obs.rssi);
+            session.frames += 1;
+        }
+        None => {
+            let session = Session {
+                mac: obs.mac.clone(),
+                start: obs.ts,
+                end: obs.ts,
+                duration: 0,
+                peak_rssi: obs.rssi,
+                frames: 1,
+                open: true,
+            };
+            sessions.open.insert(obs.mac.clone(), session.clone());
+            events.push(Event::Arrive(session));
+        }
+    }
+    events
+}
+
+/// Termine les sessions des appareils absents depuis le délai d'absence à `now`
+/// (UNIX Timestamp UTC) et publie leurs départs
+pub fn expire(now: i64) {
+    let expired = SESSIONS.lock().unwrap().expire(now);
+    for event in expired {
+        events::publish(event);
+    }
+}
+
+/// Récupère les sessions ayant chevauché `[from, to]`, triées par début
+///
+/// - `mac` : adresse MAC (insensible à la casse), toutes si `None`
+///
+/// Les sessions expirées sont terminées avant la recherche
+pub fn sessions(mac: Option<&str>, from: i64, to: i64) -> Vec<Session> {
+    expire(Utc::now().timestamp());
+    let table = SESSIONS.lock().unwrap();
+    let mut found: Vec<Session> = table
+        .closed
+        .iter()
+        .chain(table.open.values())
+        .filter(|session| session.end >= from && session.start <= to)
+        .filter(|session| mac.map_or(true, |mac| session.mac.eq_ignore_ascii_case(mac)))
+        .cloned()
+        .collect();
+    found.sort_by(|a, b| a.start.cmp(&b.start).then(a.mac.cmp(&b.mac)));
+    found
+}
+
+/// Calcule les statistiques de durée de présence des sessions ayant chevauché `[from, to]`
+pub fn dwell_stats(mac: Option<&str>, from: i64, to: i64) -> DwellStats {
+    let found = sessions(mac, from, to);
+    if found.is_empty() {
+        return DwellStats::default();
+    }
+    let mut durations: Vec<i64> = found.iter().map(|session| session.duration).collect();
+    durations.sort_unstable();
+    let mut macs: Vec<&str> = found.iter().map(|session| session.mac.as_str()).collect();
+    macs.sort_unstable();
+    macs.dedup();
+    let total: i64 = durations.iter().sum();
+    DwellStats {
+        sessions: found.len(),
+        devices: macs.len(),
+        total,
+        mean: total as f64 / found.len() as f64,
+        median: durations[durations.len() / 2],
+        max: durations[durations.len() - 1],
+    }
+}
+
+// Adresse MAC d'un paramètre FFI, toutes si vide
+fn ffi_mac(mac: &AsciiPointer) -> Option<String> {
+    mac.as_str()
+        .ok()
+        .filter(|mac| !mac.is_empty())
+        .map(str::to_owned)
+}
+
+// Fin de fenêtre d'un paramètre FFI, sans limite si `0`
+fn ffi_to(to: i64) -> i64 {
+    if to == 0 {
+        i64::MAX
+    } else {
+        to
+    }
+}
+
+/// Début de la plus ancienne session
+pub(crate) fn oldest() -> Option<i64> {
+    let table = SESSIONS.lock().unwrap();
+    table
+        .open
+        .values()
+        .chain(table.closed.iter())
+        .map(|session| session.start)
+        .min()
+}
+
+/// Efface les sessions sélectionnées et les sessions en cours des adresses MAC effacées
+///
+/// Retourne le nombre de sessions effacées
+pub(crate) fn purge(purge: &Purge, known: &HashSet<String>) -> usize {
+    let mut table = SESSIONS.lock().unwrap();
+    let count = table.open.len() + table.closed.len();
+    table
+        .open
+        .retain(|mac, session| known.contains(mac) && !purge.matches(mac, session.end));
+    table
+        .closed
+        .retain(|session| !purge.matches(&session.mac, session.end));
+    count - table.open.len() - table.closed.len()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Modifie le délai d'absence (secondes) après lequel une session est terminée
+///
+/// Retourne `false` si le délai est nul
+pub extern "C" fn set_absence_timeout(seconds: u32) -> bool {
+    if seconds == 0 {
+        return false;
+    }
+    ABSENCE_TIMEOUT.store(seconds as i64, Ordering::SeqCst);
+    EXPIRY_WAKE.notify_all();
+    true
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Bundle des sessions de présence pour la génération d'un fichier JSON (voir [`Session`])
+///
+/// - `mac` : adresse MAC, toutes si vide
+/// - `from`, `to` : UNIX Timestamps (UTC) de la fenêtre, `to` sans limite si `0`
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_sessions<'a>(mac: AsciiPointer, from: i64, to: i64) -> AsciiPointer<'a> {
+    let found = sessions(ffi_mac(&mac).as_deref(), from, ffi_to(to));
+    crate::ffi_string(serde_json::to_string(&found).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Statistiques de durée de présence pour la génération d'un fichier JSON (voir [`DwellStats`])
+///
+/// Paramètres identiques à [`get_sessions()`]
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_dwell_stats<'a>(mac: AsciiPointer, from: i64, to: i64) -> AsciiPointer<'a> {
+    let stats = dwell_stats(ffi_mac(&mac).as_deref(), from, ffi_to(to));
+    crate::ffi_string(serde_json::to_string(&stats).unwrap_or(String::from("")))
+}
@@ -0,0 +1,359 @@
+//! Index des SSIDs : réseaux recherchés et appareils qui les partagent
+//!
+//! Chaque observation portant un SSID met à jour l'index : appareils ayant recherché le SSID,
+//! première et dernière trame, nombre de trames. [`ssids()`] donne les SSIDs les plus
+//! recherchés en premier.
+//!
+//! Les relations appareil–SSID forment un graphe biparti, exporté au format DOT (Graphviz,
+//! [`to_dot()`]) ou GraphML (Gephi, yEd, [`to_graphml()`]). Deux appareils recherchant les
+//! mêmes SSIDs appartiennent souvent à la même personne ou au même foyer ; avec `min_devices`
+//! à `2`, seuls les SSIDs partagés sont exportés.
+//!
+//! | Nœud | Identifiant | Attributs |
+//! | ---- | ----------- | --------- |
+//! | Appareil | `mac:<adresse>` | constructeur |
+//! | SSID | `ssid:<ssid>` | nombre d'appareils |
+//!
+//! Chaque arête porte le nombre de trames et les dates de première et de dernière trame.
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::retention::Purge;
+use crate::{oui, Observation};
+
+lazy_static! {
+    /// Appareils de chaque SSID
+    static ref INDEX: Mutex<HashMap<String, HashMap<String, SsidLink>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Relation entre un appareil et un SSID
+///
+/// Champs:
+/// | Champ        | Type     | Description                                |
+/// |--------------|----------|--------------------------------------------|
+/// | `mac`        | `String` | Adresse MAC                                |
+/// | `ssid`       | `String` | SSID                                       |
+/// | `first_seen` | `int`    | UNIX Timestamp (UTC) de la première trame  |
+/// | `last_seen`  | `int`    | UNIX Timestamp (UTC) de la dernière trame  |
+/// | `frames`     | `int`    | Nombre de trames                           |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SsidLink {
+    /// Adresse MAC
+    pub mac: String,
+    /// SSID
+    pub ssid: String,
+    /// Première trame
+    pub first_seen: i64,
+    /// Dernière trame
+    pub last_seen: i64,
+    /// Nombre de trames
+    pub frames: u64,
+}
+
+/// Statistiques d'un SSID
+///
+/// Champs:
+/// | Champ        | Type     | Description                                          |
+/// |--------------|----------|------------------------------------------------------|
+/// | `ssid`       | `String` | SSID                                                 |
+/// | `devices`    | `int`    | Nombre d'appareils l'ayant recherché                 |
+/// | `first_seen` | `int`    | UNIX Timestamp (UTC) de la première trame            |
+/// | `last_seen`  | `int`    | UNIX Timestamp (UTC) de la dernière trame            |
+/// | `frames`     | `int`    | Nombre de trames                                     |
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SsidStats {
+    /// SSID
+    pub ssid: String,
+    /// Nombre d'appareils
+    pub devices: usize,
+    /// Première trame
+    pub first_seen: i64,
+    /// Dernière trame
+    pub last_seen: i64,
+    /// Nombre de trames
+    pub frames: u64,
+}
+
+/// Format d'export du graphe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz
+    Dot,
+    /// GraphML
+    GraphMl,
+}
+
+/// Met à jour l'index avec une observation
+pub(crate) fn record(obs: &Observation) {
+    if obs.ssid.is_empty() {
+        return;
+    }
+    let mut index = INDEX.lock().unwrap();
+    index
+        .entry(obs.ssid.clone())
+        .or_default()
+        .entry(obs.mac.clone())
+        .and_modify(|link| {
+            link.first_seen = link.first_seen.min(obs.ts);
+            link.last_seen = link.last_seen.max(obs.ts);
+            link.frames += 1;
+        })
+        .or_insert_with(|| SsidLink {
+            mac: obs.mac.clone(),
+            ssid: obs.ssid.clone(),
+            first_seen: obs.ts,
+            last_seen: obs.ts,
+            frames: 1,
+        });
+}
+
+/// Première trame de la plus ancienne relation
+pub(crate) fn oldest() -> Option<i64> {
+    INDEX
+        .lock()
+        .unwrap()
+        .values()
+        .flat_map(|links| links.values().map(|link| link.first_seen))
+        .min()
+}
+
+/// Efface les relations sélectionnées (selon leur dernière trame)
+///
+/// Retourne le nombre de relations effacées
+pub(crate) fn purge(purge: &Purge, known: &HashSet<String>) -> usize {
+    let mut index = INDEX.lock().unwrap();
+    let mut count = 0;
+    index.retain(|_, links| {
+        let before = links.len();
+        links.retain(|mac, link| known.contains(mac) && !purge.matches(mac, link.last_seen));
+        count += before - links.len();
+        !links.is_empty()
+    });
+    count
+}
+
+/// SSIDs de l'index, les plus recherchés en premier
+pub fn ssids() -> Vec<SsidStats> {
+    let mut ssids: Vec<SsidStats> = INDEX
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(ssid, links)| SsidStats {
+            ssid: ssid.clone(),
+            devices: links.len(),
+            first_seen: links
+                .values()
+                .map(|link| link.first_seen)
+                .min()
+                .unwrap_or(0),
+            last_seen: links.values().map(|link| link.last_seen).max().unwrap_or(0),
+            frames: links.values().map(|link| link.frames).sum(),
+        })
+        .collect();
+    ssids.sort_by(|a, b| b.devices.cmp(&a.devices).then(a.ssid.cmp(&b.ssid)));
+    ssids
+}
+
+/// Relations des SSIDs recherchés par au moins `min_devices` appareils, triées par SSID puis
+/// par adresse MAC
+pub fn links(min_devices: usize) -> Vec<SsidLink> {
+    let index = INDEX.lock().unwrap();
+    let mut links: Vec<SsidLink> = index
+        .values()
+        .filter(|links| links.len() >= min_devices)
+        .flat_map(|links| links.values().cloned())
+        .collect();
+    links.sort_by(|a, b| a.ssid.cmp(&b.ssid).then(a.mac.cmp(&b.mac)));
+    links
+}
+
+/// Appareils partageant au moins un SSID avec une adresse MAC, avec les SSIDs partagés
+pub fn shared_with(mac: &str) -> BTreeMap<String, Vec<String>> {
+    let index = INDEX.lock().unwrap();
+    let mut shared: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (ssid, links) in index.iter().filter(|(_, links)| links.contains_key(mac)) {
+        for other in links.keys().filter(|other| *other != mac) {
+            shared.entry(other.clone()).or_default().push(ssid.clone());
+        }
+    }
+    for ssids in shared.values_mut() {
+        ssids.sort();
+    }
+    shared
+}
+
+// Nœuds du graphe : appareils et SSIDs (avec leur nombre d'appareils) des relations
+fn nodes(links: &[SsidLink]) -> (BTreeSet<&str>, BTreeMap<&str, usize>) {
+    let mut devices = BTreeSet::new();
+    let mut ssids: BTreeMap<&str, usize> = BTreeMap::new();
+    for link in links {
+        devices.insert(link.mac.as_str());
+        *ssids.entry(link.ssid.as_str()).or_insert(0) += 1;
+    }
+    (devices, ssids)
+}
+
+// Échappe une chaîne entre guillemets DOT, caractères de contrôle compris (`\n`, `\x01`)
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_ascii_control() => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Échappe un texte XML
+//
+// Les caractères de contrôle interdits en XML 1.0 sont remplacés par `U+FFFD`, la tabulation
+// et les retours à la ligne sont encodés pour être conservés dans les attributs
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => {
+                let _ = write!(escaped, "&#{};", c as u32);
+            }
+            c if c < '\u{20}' => escaped.push(char::REPLACEMENT_CHARACTER),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Graphe appareils–SSIDs au format DOT
+pub fn to_dot(links: &[SsidLink]) -> String {
+    let (devices, ssids) = nodes(links);
+    let mut dot = String::from("graph ssids {\n");
+    for mac in devices {
+        let _ = writeln!(
+            dot,
+            "  \"mac:{mac}\" [shape=ellipse, label=\"{mac}\\n{}\"];",
+            dot_escape(oui::vendor(mac))
+        );
+    }
+    for (ssid, count) in ssids {
+        let ssid = dot_escape(ssid);
+        let _ = writeln!(
+            dot,
+            "  \"ssid:{ssid}\" [shape=box, label=\"{ssid}\", devices={count}];"
+        );
+    }
+    for link in links {
+        let _ = writeln!(
+            dot,
+            "  \"mac:{}\" -- \"ssid:{}\" [weight={}, first_seen={}, last_seen={}];",
+            link.mac,
+            dot_escape(&link.ssid),
+            link.frames,
+            link.first_seen,
+            link.last_seen
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Graphe appareils–SSIDs au format GraphML
+pub fn to_graphml(links: &[SsidLink]) -> String {
+    let (devices, ssids) = nodes(links);
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"vendor\" for=\"node\" attr.name=\"vendor\" attr.type=\"string\"/>\n",
+        "  <key id=\"devices\" for=\"node\" attr.name=\"devices\" attr.type=\"int\"/>\n",
+        "  <key id=\"frames\" for=\"edge\" attr.name=\"frames\" attr.type=\"long\"/>\n",
+        "  <key id=\"first_seen\" for=\"edge\" attr.name=\"first_seen\" attr.type=\"long\"/>\n",
+        "  <key id=\"last_seen\" for=\"edge\" attr.name=\"last_seen\" attr.type=\"long\"/>\n",
+        "  <graph id=\"ssids\" edgedefault=\"undirected\">\n",
+    ));
+    for mac in devices {
+        let _ = writeln!(
+            xml,
+            "    <node id=\"mac:{mac}\"><data key=\"type\">device</data><data key=\"label\">{mac}</data><data key=\"vendor\">{}</data></node>",
+            xml_escape(oui::vendor(mac))
+        );
+    }
+    for (ssid, count) in ssids {
+        let ssid = xml_escape(ssid);
+        let _ = writeln!(
+            xml,
+            "    <node id=\"ssid:{ssid}\"><data key=\"type\">ssid</data><data key=\"label\">{ssid}</data><data key=\"devices\">{count}</data></node>"
+        );
+    }
+    for link in links {
+        let _ = writeln!(
+            xml,
+            "    <edge source=\"mac:{}\" target=\"ssid:{}\"><data key=\"frames\">{}</data><data key=\"first_seen\">{}</data><data key=\"last_seen\">{}</data></edge>",
+            link.mac,
+            xml_escape(&link.ssid),
+            link.frames,
+            link.first_seen,
+            link.last_seen
+        );
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// Graphe des SSIDs recherchés par au moins `min_devices` appareils dans un format
+pub fn graph(format: GraphFormat, min_devices: usize) -> String {
+    let links = links(min_devices);
+    match format {
+        GraphFormat::Dot => to_dot(&links),
+        GraphFormat::GraphMl => to_graphml(&links),
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// SSIDs recherchés, les plus recherchés en premier, pour la génération d'un fichier JSON
+/// (voir [`SsidStats`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_ssids_all<'a>() -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&ssids()).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Graphe des relations appareils–SSIDs
+///
+/// | Paramètre | Valeurs |
+/// | --------- | ------- |
+/// | `format` | `0` : DOT, `1` : GraphML |
+/// | `min_devices` | SSIDs recherchés par au moins ce nombre d'appareils (`2` : SSIDs partagés) |
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_ssid_graph<'a>(format: u8, min_devices: u32) -> AsciiPointer<'a> {
+    let format = if format == 1 {
+        GraphFormat::GraphMl
+    } else {
+        GraphFormat::Dot
+    };
+    crate::ffi_string(graph(format, min_devices as usize))
+}
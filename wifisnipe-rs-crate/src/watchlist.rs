@@ -0,0 +1,591 @@
+//! Liste de surveillance : alertes à l'apparition d'appareils ou de SSIDs connus
+//!
+//! Chaque règle compare les observations à un motif :
+//!
+//! | Type | Motif | Exemple |
+//! | ---- | ----- | ------- |
+//! | `mac` | Adresse MAC exacte | `da:a1:19:00:00:01` |
+//! | `prefix` (ou `oui`) | Début de l'adresse MAC, par exemple un OUI | `a4:5e:60` |
+//! | `ssid` | SSID exact | `MaBox` |
+//! | `regex` | Expression régulière sur l'adresse MAC ou le SSID | `^Free` |
+//!
+//! Les motifs `mac` et `prefix` sont insensibles à la casse et acceptent `-` comme séparateur.
+//!
+//! Une règle déclenche au plus une alerte par appareil pendant le délai de répétition
+//! (voir [`set_cooldown()`], [`DEFAULT_COOLDOWN`] par défaut).
+//!
+//! En mode confidentialité (voir [`crate::privacy`]), les motifs `mac` et `ssid` sont
+//! pseudonymisés avant d'être comparés, et repris pseudonymisés dans les alertes. Les règles
+//! `prefix` ne correspondent alors à aucun appareil et les règles `regex` s'appliquent aux
+//! pseudonymes.
+//!
+//! ## Fichier
+//!
+//! Une règle par ligne : `type motif [nom]`, séparés par des espaces. Les lignes vides et celles
+//! commençant par `#` sont ignorées. Sans nom, le motif sert de nom ; en mode confidentialité,
+//! les alertes d'une telle règle portent le motif pseudonymisé (`mac`, `ssid`) ou `type#numéro`
+//! (numéro de la règle à partir de `1`) à la place du motif brut.
+//!
+//! ```text
+//! # Téléphone de la direction
+//! mac    da:a1:19:00:00:01  direction
+//! oui    a4:5e:60           apple
+//! ssid   MaBox
+//! regex  ^Free
+//! ```
+//!
+//! ## Destinations
+//!
+//! Les alertes sont publiées dans le flux d'évènements ([`crate::events::Event::Alert`]),
+//! conservées dans la limite de [`ALERT_HISTORY`] pour [`get_alerts()`], puis envoyées à chaque
+//! destination ajoutée avec [`add_route()`] (voir [`AlertRoute`]).
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::events::{self, Event};
+use crate::retention::Purge;
+use crate::{oui, privacy, Observation};
+
+/// Délai de répétition par défaut d'une alerte pour un même appareil (secondes)
+pub const DEFAULT_COOLDOWN: i64 = 300;
+
+/// Nombre d'alertes conservées
+pub const ALERT_HISTORY: usize = 1024;
+
+/// Délai de répétition (secondes)
+static COOLDOWN: AtomicI64 = AtomicI64::new(DEFAULT_COOLDOWN);
+
+// Le type généré par `callback!` n'est pas documentable
+mod callback {
+    #![allow(missing_docs)]
+    use interoptopus::callback;
+    use interoptopus::patterns::string::AsciiPointer;
+
+    callback!(AlertCallback(alert: AsciiPointer));
+}
+
+/// Fonction C recevant une alerte en JSON (`void (*)(const char *)`)
+pub use callback::AlertCallback;
+
+lazy_static! {
+    /// Règles de surveillance
+    static ref RULES: Mutex<Vec<WatchRule>> = Mutex::new(Vec::new());
+    /// Dernière alerte par règle et adresse MAC, pendant le délai de répétition
+    static ref LAST_ALERT: Mutex<HashMap<(String, String), i64>> = Mutex::new(HashMap::new());
+    /// Dernières alertes
+    static ref ALERTS: Mutex<VecDeque<Alert>> = Mutex::new(VecDeque::new());
+    /// Destinations des alertes
+    static ref ROUTES: Mutex<Vec<AlertRoute>> = Mutex::new(Vec::new());
+}
+
+/// Motif d'une règle
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Adresse MAC exacte
+    Mac(String),
+    /// Début de l'adresse MAC
+    Prefix(String),
+    /// SSID exact
+    Ssid(String),
+    /// Expression régulière sur l'adresse MAC ou le SSID
+    Regex(Regex),
+}
+
+impl Matcher {
+    /// Construit un motif à partir de son type (voir le tableau du module)
+    pub fn new(kind: &str, pattern: &str) -> Result<Matcher, String> {
+        match kind.to_lowercase().as_str() {
+            "mac" => Ok(Matcher::Mac(pattern.to_lowercase().replace('-', ":"))),
+            "prefix" | "oui" => Ok(Matcher::Prefix(pattern.to_lowercase().replace('-', ":"))),
+            "ssid" => Ok(Matcher::Ssid(pattern.to_owned())),
+            "regex" => Regex::new(pattern)
+                .map(Matcher::Regex)
+                .map_err(|err| format!("expression régulière invalide : {err}")),
+            _ => Err(format!("type de règle inconnu : {kind}")),
+        }
+    }
+
+    /// Type du motif
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Matcher::Mac(_) => "mac",
+            Matcher::Prefix(_) => "prefix",
+            Matcher::Ssid(_) => "ssid",
+            Matcher::Regex(_) => "regex",
+        }
+    }
+
+    /// Motif sous forme de texte
+    pub fn pattern(&self) -> &str {
+        match self {
+            Matcher::Mac(pattern) | Matcher::Prefix(pattern) | Matcher::Ssid(pattern) => pattern,
+            Matcher::Regex(regex) => regex.as_str(),
+        }
+    }
+
+    /// Motif tel qu'il apparaît dans les observations de `ts` (pseudonymisé en mode confidentialité)
+    pub fn observed_pattern(&self, ts: i64) -> String {
+        match self {
+            Matcher::Mac(mac) => privacy::mac(mac, ts),
+            Matcher::Ssid(ssid) => privacy::ssid(ssid, ts),
+            _ => self.pattern().to_owned(),
+        }
+    }
+
+    /// Vérifie qu'une observation correspond au motif
+    pub fn matches(&self, obs: &Observation) -> bool {
+        match self {
+            Matcher::Mac(_) => obs.mac.eq_ignore_ascii_case(&self.observed_pattern(obs.ts)),
+            Matcher::Prefix(prefix) => {
+                !privacy::is_enabled() && obs.mac.to_lowercase().starts_with(prefix)
+            }
+            Matcher::Ssid(_) => {
+                let ssid = self.observed_pattern(obs.ts);
+                !ssid.is_empty() && obs.ssid == ssid
+            }
+            Matcher::Regex(regex) => {
+                regex.is_match(&obs.mac) || (!obs.ssid.is_empty() && regex.is_match(&obs.ssid))
+            }
+        }
+    }
+}
+
+/// Règle de surveillance
+#[derive(Debug, Clone)]
+pub struct WatchRule {
+    /// Nom de la règle, repris dans les alertes
+    pub name: String,
+    /// Motif
+    pub matcher: Matcher,
+}
+
+impl WatchRule {
+    /// Construit une règle, nommée par son motif si `name` est vide
+    pub fn new(name: &str, matcher: Matcher) -> WatchRule {
+        WatchRule {
+            name: if name.is_empty() {
+                matcher.pattern().to_owned()
+            } else {
+                name.to_owned()
+            },
+            matcher,
+        }
+    }
+
+    /// Nom de la règle dans une alerte déclenchée à `ts`
+    ///
+    /// En mode confidentialité, une règle nommée par son motif est désignée par le motif
+    /// pseudonymisé (`mac`, `ssid`) ou par `type#numéro`, jamais par le motif brut
+    fn alert_name(&self, index: usize, ts: i64) -> String {
+        if !privacy::is_enabled() || self.name != self.matcher.pattern() {
+            return self.name.clone();
+        }
+        match self.matcher {
+            Matcher::Mac(_) | Matcher::Ssid(_) => self.matcher.observed_pattern(ts),
+            _ => format!("{}#{}", self.matcher.kind(), index + 1),
+        }
+    }
+}
+
+/// Alerte déclenchée par une règle
+///
+/// Champs:
+/// | Champ     | Type     | Description                            |
+/// |-----------|----------|----------------------------------------|
+/// | `rule`    | `String` | Nom de la règle                        |
+/// | `kind`    | `String` | Type de la règle (`mac`, `prefix`, `ssid`, `regex`) |
+/// | `pattern` | `String` | Motif de la règle                      |
+/// | `mac`     | `String` | Adresse MAC de l'appareil              |
+/// | `vendor`  | `String` | Constructeur ([`crate::oui::vendor()`]) |
+/// | `ssid`    | `String` | SSID de l'observation (vide si absent) |
+/// | `rssi`    | `int`    | RSSI                                   |
+/// | `channel` | `int`    | Canal                                  |
+/// | `ts`      | `int`    | UNIX Timestamp (UTC) de l'observation  |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    /// Nom de la règle
+    pub rule: String,
+    /// Type de la règle
+    pub kind: String,
+    /// Motif de la règle
+    pub pattern: String,
+    /// Adresse MAC
+    pub mac: String,
+    /// Constructeur
+    pub vendor: String,
+    /// SSID
+    pub ssid: String,
+    /// RSSI
+    pub rssi: i32,
+    /// Canal
+    pub channel: u32,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+}
+
+/// Destination des alertes
+#[derive(Clone)]
+pub enum AlertRoute {
+    /// Ajoute chaque alerte en JSON, une par ligne, à la fin d'un fichier
+    Log(PathBuf),
+    /// Appelle une fonction Rust (doit rendre la main rapidement)
+    Callback(Arc<dyn Fn(&Alert) + Send + Sync>),
+    /// Appelle une fonction C avec l'alerte en JSON (voir [`set_alert_callback()`])
+    Ffi(AlertCallback),
+    /// Envoie l'alerte en JSON par `POST` à une URL, avec nouvelles tentatives
+    /// (feature `webhook`, voir [`crate::webhook`])
+    #[cfg(feature = "webhook")]
+    Webhook(String),
+}
+
+impl AlertRoute {
+    fn send(&self, alert: &Alert) {
+        match self {
+            AlertRoute::Log(path) => {
+                let line = serde_json::to_string(alert).unwrap_or_default();
+                let _ = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut file| writeln!(file, "{line}"));
+            }
+            AlertRoute::Callback(callback) => callback(alert),
+            AlertRoute::Ffi(callback) => {
+                let mut json = serde_json::to_string(alert).unwrap_or_default();
+                json.push('\0');
+                if let Ok(pointer) = AsciiPointer::from_slice_with_nul(json.as_bytes()) {
+                    callback.call_if_some(pointer);
+                }
+            }
+            #[cfg(feature = "webhook")]
+            AlertRoute::Webhook(url) => {
+                crate::webhook::notify_url(url, crate::webhook::Payload::Alert(alert.clone()))
+            }
+        }
+    }
+}
+
+/// Lit des règles au format du fichier de surveillance
+///
+/// Retourne une erreur indiquant le numéro de la première ligne invalide
+pub fn parse(text: &str) -> Result<Vec<WatchRule>, String> {
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let (kind, pattern) = match (words.next(), words.next()) {
+            (Some(kind), Some(pattern)) => (kind, pattern),
+            _ => return Err(format!("ligne {} : motif manquant", number + 1)),
+        };
+        let matcher =
+            Matcher::new(kind, pattern).map_err(|err| format!("ligne {} : {err}", number + 1))?;
+        let name = words.collect::<Vec<_>>().join(" ");
+        rules.push(WatchRule::new(&name, matcher));
+    }
+    Ok(rules)
+}
+
+/// Remplace les règles par celles d'un fichier
+///
+/// Retourne le nombre de règles chargées
+pub fn load(path: &str) -> io::Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    let rules = parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let count = rules.len();
+    *RULES.lock().unwrap() = rules;
+    Ok(count)
+}
+
+/// Ajoute une règle
+pub fn add_rule(rule: WatchRule) {
+    RULES.lock().unwrap().push(rule);
+}
+
+/// Supprime les règles d'un nom
+///
+/// Retourne `false` si aucune règle ne porte ce nom
+pub fn remove_rule(name: &str) -> bool {
+    let mut rules = RULES.lock().unwrap();
+    let count = rules.len();
+    rules.retain(|rule| rule.name != name);
+    rules.len() != count
+}
+
+/// Supprime toutes les règles
+pub fn clear_rules() {
+    RULES.lock().unwrap().clear();
+}
+
+/// Copie des règles en cours
+pub fn rules() -> Vec<WatchRule> {
+    RULES.lock().unwrap().clone()
+}
+
+/// Change le délai de répétition d'une alerte pour un même appareil (secondes)
+pub fn set_cooldown(seconds: i64) {
+    COOLDOWN.store(seconds.max(0), Ordering::SeqCst);
+}
+
+/// Ajoute une destination aux alertes
+pub fn add_route(route: AlertRoute) {
+    ROUTES.lock().unwrap().push(route);
+}
+
+/// Supprime toutes les destinations
+pub fn clear_routes() {
+    ROUTES.lock().unwrap().clear();
+}
+
+/// Alertes conservées à partir de `since` (UNIX Timestamp UTC, inclus)
+pub fn alerts(since: i64) -> Vec<Alert> {
+    ALERTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|alert| alert.ts >= since)
+        .cloned()
+        .collect()
+}
+
+/// Compare une observation aux règles, publie et envoie les alertes déclenchées
+pub(crate) fn check(obs: &Observation) {
+    let cooldown = COOLDOWN.load(Ordering::SeqCst);
+    let triggered: Vec<Alert> = {
+        let rules = RULES.lock().unwrap();
+        let mut last_alert = LAST_ALERT.lock().unwrap();
+        rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matcher.matches(obs))
+            .filter(|(_, rule)| {
+                let key = (rule.name.clone(), obs.mac.clone());
+                match last_alert.get(&key) {
+                    Some(last) if obs.ts - last < cooldown => false,
+                    _ => {
+                        // Les alertes dont le délai de répétition est écoulé sont oubliées
+                        last_alert.retain(|_, last| obs.ts - *last < cooldown);
+                        last_alert.insert(key, obs.ts);
+                        true
+                    }
+                }
+            })
+            .map(|(index, rule)| Alert {
+                rule: rule.alert_name(index, obs.ts),
+                kind: rule.matcher.kind().to_owned(),
+                pattern: rule.matcher.observed_pattern(obs.ts),
+                mac: obs.mac.clone(),
+                vendor: oui::vendor(&obs.mac).to_owned(),
+                ssid: obs.ssid.clone(),
+                rssi: obs.rssi,
+                channel: obs.channel,
+                ts: obs.ts,
+            })
+            .collect()
+    };
+    if triggered.is_empty() {
+        return;
+    }
+    let routes = ROUTES.lock().unwrap().clone();
+    for alert in triggered {
+        {
+            let mut alerts = ALERTS.lock().unwrap();
+            if alerts.len() >= ALERT_HISTORY {
+                alerts.pop_front();
+            }
+            alerts.push_back(alert.clone());
+        }
+        for route in &routes {
+            route.send(&alert);
+        }
+        events::publish(Event::Alert(alert));
+    }
+}
+
+/// Date de la plus ancienne alerte, en mémoire ou dans les journaux d'alertes
+pub(crate) fn oldest() -> Option<i64> {
+    let memory = ALERTS.lock().unwrap().iter().map(|alert| alert.ts).min();
+    let logs: Vec<PathBuf> = ROUTES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|route| match route {
+            AlertRoute::Log(path) => Some(path.clone()),
+            _ => None,
+        })
+        .collect();
+    let logged = logs
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|text| {
+            text.lines()
+                .filter_map(|line| serde_json::from_str::<Alert>(line).ok())
+                .map(|alert| alert.ts)
+                .collect::<Vec<_>>()
+        })
+        .min();
+    memory.into_iter().chain(logged).min()
+}
+
+/// Efface les alertes sélectionnées, en mémoire et dans les journaux d'alertes, et le délai de
+/// répétition des adresses MAC effacées
+///
+/// Retourne le nombre d'alertes effacées en mémoire et de lignes effacées dans les journaux
+pub(crate) fn purge(purge: &Purge, known: &HashSet<String>) -> (usize, usize) {
+    LAST_ALERT
+        .lock()
+        .unwrap()
+        .retain(|(_, mac), _| known.contains(mac));
+    let alerts = {
+        let mut alerts = ALERTS.lock().unwrap();
+        let count = alerts.len();
+        alerts.retain(|alert| !purge.matches(&alert.mac, alert.ts));
+        count - alerts.len()
+    };
+    let mut lines = 0;
+    for route in ROUTES.lock().unwrap().iter() {
+        if let AlertRoute::Log(path) = route {
+            lines += purge_log(path, purge).unwrap_or(0);
+        }
+    }
+    (alerts, lines)
+}
+
+// Réécrit un journal d'alertes sans les alertes sélectionnées
+fn purge_log(path: &PathBuf, purge: &Purge) -> io::Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    let mut kept = String::new();
+    let mut count = 0;
+    for line in text.lines() {
+        match serde_json::from_str::<Alert>(line) {
+            Ok(alert) if purge.matches(&alert.mac, alert.ts) => count += 1,
+            _ => {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+    }
+    if count > 0 {
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, kept)?;
+        std::fs::rename(&temp, path)?;
+    }
+    Ok(count)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Remplace les règles de surveillance par celles d'un fichier
+///
+/// Retourne le nombre de règles chargées, `-1` si le fichier est illisible ou invalide
+pub extern "C" fn load_watchlist(path: AsciiPointer) -> i32 {
+    path.as_str()
+        .ok()
+        .and_then(|path| load(path).ok())
+        .map_or(-1, |count| count as i32)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Ajoute une règle de surveillance (`kind` : `mac`, `prefix`, `oui`, `ssid` ou `regex`)
+///
+/// Sans nom, le motif sert de nom. Retourne `false` si le type ou le motif est invalide
+pub extern "C" fn add_watch(kind: AsciiPointer, pattern: AsciiPointer, name: AsciiPointer) -> bool {
+    let (kind, pattern) = match (kind.as_str(), pattern.as_str()) {
+        (Ok(kind), Ok(pattern)) if !pattern.is_empty() => (kind, pattern),
+        _ => return false,
+    };
+    let name = name.as_str().unwrap_or("");
+    match Matcher::new(kind, pattern) {
+        Ok(matcher) => {
+            add_rule(WatchRule::new(name, matcher));
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Supprime les règles de surveillance d'un nom
+///
+/// Retourne `false` si aucune règle ne porte ce nom
+pub extern "C" fn remove_watch(name: AsciiPointer) -> bool {
+    name.as_str().is_ok_and(remove_rule)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Supprime toutes les règles de surveillance
+pub extern "C" fn clear_watchlist() {
+    clear_rules()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Change le délai (secondes) avant qu'une règle puisse alerter à nouveau pour un même appareil
+pub extern "C" fn set_alert_cooldown(seconds: u32) {
+    set_cooldown(seconds as i64)
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Ajoute chaque alerte en JSON, une par ligne, à la fin d'un fichier
+///
+/// Retourne `false` si le chemin est invalide
+pub extern "C" fn set_alert_log(path: AsciiPointer) -> bool {
+    match path.as_str() {
+        Ok(path) if !path.is_empty() => {
+            add_route(AlertRoute::Log(PathBuf::from(path)));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Appelle une fonction pour chaque alerte, avec l'alerte en JSON (`const char *` valable pendant l'appel)
+///
+/// La fonction est appelée depuis le thread de traitement et doit rendre la main rapidement
+pub extern "C" fn set_alert_callback(callback: AlertCallback) {
+    add_route(AlertRoute::Ffi(callback))
+}
+
+#[cfg(feature = "webhook")]
+#[no_mangle]
+#[ffi_function]
+/// Envoie chaque alerte en JSON par `POST` à une URL (feature `webhook`, voir [`crate::webhook`])
+///
+/// Retourne `false` si l'URL est invalide
+pub extern "C" fn set_alert_webhook(url: AsciiPointer) -> bool {
+    match url.as_str() {
+        Ok(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            add_route(AlertRoute::Webhook(url.to_owned()));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Alertes depuis `since` (UNIX Timestamp UTC, `0` pour toutes) pour la génération d'un fichier
+/// JSON (voir [`Alert`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_alerts<'a>(since: i64) -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&alerts(since)).unwrap_or(String::from("")))
+}
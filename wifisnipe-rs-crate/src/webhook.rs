@@ -0,0 +1,700 @@
+//! Notifications JSON envoyées par `POST` à des webhooks
+//!
+//! Disponible avec la feature `webhook`
+//!
+//! Chaque notification est envoyée à chaque URL configurée, dans une enveloppe commune :
+//!
+//! | Champ | Description |
+//! | ----- | ----------- |
+//! | `id` | Identifiant unique, identique d'une tentative à l'autre (dédoublonnage) |
+//! | `sensor` | Nom du capteur |
+//! | `ts` | UNIX Timestamp (UTC) de création |
+//! | `type` | Type de notification (voir ci-dessous) |
+//! | `data` | Contenu |
+//!
+//! | `type` | `data` |
+//! | ------ | ------ |
+//! | `alert` | Alerte de la liste de surveillance ([`crate::watchlist::Alert`]) |
+//! | `sensor_offline` | Carte muette ou port série en erreur ([`Payload::SensorOffline`]) |
+//! | `sensor_online` | Retour de la carte ([`Payload::SensorOnline`]) |
+//! | `summary` | Résumé périodique ([`Summary`]) |
+//!
+//! ## Nouvelles tentatives
+//!
+//! Une notification est retirée dès que l'URL répond par un statut `2xx`, ou par un statut `4xx`
+//! autre que `408` et `429` (requête refusée, inutile de réessayer). Sinon elle est renvoyée après
+//! [`WebhookConfig::retry_delay`], doublé à chaque échec jusqu'à [`WebhookConfig::max_retry_delay`],
+//! dans la limite de [`WebhookConfig::max_attempts`] tentatives.
+//!
+//! Les notifications en attente sont enregistrées dans la boîte d'envoi
+//! ([`WebhookConfig::outbox`]) et reprises au démarrage suivant. Le fichier est un journal JSON,
+//! une ligne par notification ajoutée, nouvelle tentative ou envoi terminé ; le thread d'envoi le
+//! réécrit avec les seules notifications en attente quand il contient plus de
+//! [`COMPACT_LINES`] lignes périmées ou qu'il n'y a plus rien à envoyer.
+//!
+//! ## Test local
+//!
+//! ```ps1
+//! cd esp-sniffer\wifisnipe-rs
+//! cargo run --features webhook -- --webhook http://127.0.0.1:9000/ --outbox outbox.jsonl --replay capture.txt
+//! ```
+
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+
+use crate::retention::Purge;
+use crate::sensor::{self, SensorStatus, STATUS_INTERVAL};
+use crate::watchlist::{self, Alert, AlertRoute};
+use crate::{clock, is_started, localization, observations, serial_error, snapshot};
+
+/// Nombre maximal de notifications en attente, les plus anciennes sont abandonnées au-delà
+pub const OUTBOX_CAPACITY: usize = 10_000;
+
+/// Lignes périmées de la boîte d'envoi au-delà desquelles le fichier est réécrit
+pub const COMPACT_LINES: usize = 1000;
+
+/// Période de vérification de l'état de la carte
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Identifiant de la prochaine notification
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Démarrage unique du thread d'envoi
+static DELIVERY: Once = Once::new();
+
+lazy_static! {
+    /// Configuration en cours, `None` avant [`start()`]
+    static ref CONFIG: Mutex<Option<WebhookConfig>> = Mutex::new(None);
+    /// Notifications en attente
+    static ref OUTBOX: Mutex<VecDeque<Pending>> = Mutex::new(VecDeque::new());
+    /// Fichier de la boîte d'envoi, verrouillé avant `OUTBOX`
+    static ref JOURNAL: Mutex<Journal> = Mutex::new(Journal::default());
+    /// Réveille le thread d'envoi à l'ajout d'une notification
+    static ref WAKE: Condvar = Condvar::new();
+}
+
+/// Configuration des notifications
+///
+/// Utiliser [`WebhookConfig::default()`] pour les valeurs par défaut :
+///
+/// ```
+/// use libwifisnipe::webhook::WebhookConfig;
+///
+/// let config = WebhookConfig {
+///     urls: vec![String::from("https://exemple.fr/alertes")],
+///     outbox: Some("outbox.jsonl".into()),
+///     ..WebhookConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// URLs recevant les notifications
+    pub urls: Vec<String>,
+    /// Nom du capteur, celui de [`crate::localization::local_sensor()`] si vide
+    pub sensor: String,
+    /// Fichier de la boîte d'envoi, aucune persistance si `None` (défaut)
+    pub outbox: Option<PathBuf>,
+    /// Envoie les alertes de la liste de surveillance (défaut : `true`)
+    pub alerts: bool,
+    /// Signale l'arrêt et le retour de la carte (défaut : `true`)
+    pub sensor_status: bool,
+    /// Période des résumés, aucun résumé si `None` (défaut : 1 heure)
+    pub summary_interval: Option<Duration>,
+    /// Délai maximal d'une requête (défaut : 10 s)
+    pub timeout: Duration,
+    /// Délai avant la première nouvelle tentative (défaut : 1 s)
+    pub retry_delay: Duration,
+    /// Délai maximal entre deux tentatives (défaut : 5 minutes)
+    pub max_retry_delay: Duration,
+    /// Nombre maximal de tentatives par notification et par URL, `0` pour aucune limite (défaut : `10`)
+    pub max_attempts: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            urls: Vec::new(),
+            sensor: String::new(),
+            outbox: None,
+            alerts: true,
+            sensor_status: true,
+            summary_interval: Some(Duration::from_secs(3600)),
+            timeout: Duration::from_secs(10),
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(300),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Délai avant la tentative suivant `attempts` échecs
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempts.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.retry_delay
+            .checked_mul(factor)
+            .map_or(self.max_retry_delay, |delay| {
+                delay.min(self.max_retry_delay)
+            })
+    }
+}
+
+/// Contenu d'une notification, sérialisé dans les champs `type` et `data`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Payload {
+    /// Alerte de la liste de surveillance
+    Alert(Alert),
+    /// Carte muette ou port série en erreur
+    SensorOffline {
+        /// Cause : erreur du port série ou absence de trame d'état
+        reason: String,
+        /// UNIX Timestamp (UTC) du dernier état reçu
+        last_status: Option<i64>,
+    },
+    /// Retour de la carte
+    SensorOnline {
+        /// UNIX Timestamp (UTC) de la notification d'arrêt
+        offline_since: i64,
+    },
+    /// Résumé périodique
+    Summary(Summary),
+}
+
+/// Résumé de l'activité sur une période
+///
+/// Champs:
+/// | Champ          | Type  | Description                                   |
+/// |----------------|-------|-----------------------------------------------|
+/// | `since`        | `int` | UNIX Timestamp (UTC) du début de la période   |
+/// | `until`        | `int` | UNIX Timestamp (UTC) de la fin de la période  |
+/// | `devices`      | `int` | Appareils vus pendant la période              |
+/// | `observations` | `int` | Observations pendant la période               |
+/// | `alerts`       | `int` | Alertes pendant la période                    |
+/// | `frames_lost`  | `int` | Trames perdues depuis le démarrage            |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    /// Début de la période
+    pub since: i64,
+    /// Fin de la période
+    pub until: i64,
+    /// Appareils vus
+    pub devices: usize,
+    /// Observations
+    pub observations: usize,
+    /// Alertes
+    pub alerts: usize,
+    /// Trames perdues
+    pub frames_lost: u64,
+}
+
+impl Summary {
+    /// Résumé de la période `[since, until]`
+    pub fn between(since: i64, until: i64) -> Summary {
+        let in_period = |ts: i64| ts >= since && ts <= until;
+        Summary {
+            since,
+            until,
+            devices: snapshot().iter().filter(|data| in_period(data.ts)).count(),
+            observations: observations()
+                .iter()
+                .filter(|obs| in_period(obs.ts))
+                .count(),
+            alerts: watchlist::alerts(since)
+                .iter()
+                .filter(|alert| alert.ts <= until)
+                .count(),
+            frames_lost: clock::frames_lost(),
+        }
+    }
+}
+
+/// Notification envoyée à un webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Identifiant unique
+    pub id: u64,
+    /// Nom du capteur
+    pub sensor: String,
+    /// UNIX Timestamp (UTC) de création
+    pub ts: i64,
+    /// Contenu
+    #[serde(flatten)]
+    pub payload: Payload,
+}
+
+impl Notification {
+    /// Crée une notification datée de maintenant
+    pub fn new(sensor: &str, payload: Payload) -> Notification {
+        let now = Utc::now();
+        // Identifiants croissants, même après un redémarrage
+        let _ = NEXT_ID.compare_exchange(
+            0,
+            now.timestamp_millis() as u64 * 1000,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+        Notification {
+            id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+            sensor: sensor.to_owned(),
+            ts: now.timestamp(),
+            payload,
+        }
+    }
+}
+
+// Notification en attente pour une URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pending {
+    url: String,
+    attempts: u32,
+    // Prochaine tentative (UNIX Timestamp en millisecondes)
+    next_attempt: i64,
+    notification: Notification,
+}
+
+// Ligne du fichier de la boîte d'envoi
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Record {
+    // Notification ajoutée ou nouvelle tentative
+    Pending(Box<Pending>),
+    // Notification envoyée, refusée ou abandonnée
+    Done { done: u64, url: String },
+}
+
+// Fichier de la boîte d'envoi ouvert en ajout
+#[derive(Debug, Default)]
+struct Journal {
+    path: Option<PathBuf>,
+    file: Option<File>,
+    // Lignes écrites depuis la dernière réécriture
+    lines: usize,
+}
+
+impl Journal {
+    // Ajoute une ligne à la fin du fichier
+    fn append(&mut self, record: &Record) {
+        if let Some(file) = &mut self.file {
+            let line = serde_json::to_string(record).unwrap_or_default();
+            if writeln!(file, "{line}").is_ok() {
+                self.lines += 1;
+            }
+        }
+    }
+
+    // Réécrit le fichier avec les notifications en attente (fichier temporaire puis renommage)
+    fn compact(&mut self, outbox: &VecDeque<Pending>) {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        let temp = path.with_extension("tmp");
+        let written = File::create(&temp).and_then(|mut file| {
+            for pending in outbox {
+                writeln!(
+                    file,
+                    "{}",
+                    serde_json::to_string(pending).unwrap_or_default()
+                )?;
+            }
+            file.sync_all()
+        });
+        if written.is_ok() && fs::rename(&temp, &path).is_ok() {
+            self.lines = outbox.len();
+        }
+        self.file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)
+            .ok();
+    }
+}
+
+/// Démarre les notifications
+///
+/// Reprend les notifications de la boîte d'envoi, puis surveille la carte et envoie les résumés
+/// dans un thread. Retourne une erreur si aucune URL n'est valide, si les notifications sont
+/// déjà démarrées ou si la boîte d'envoi est illisible.
+pub fn start(mut config: WebhookConfig) -> io::Result<()> {
+    if config.urls.is_empty() || !config.urls.iter().all(|url| is_valid_url(url)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "URL de webhook invalide",
+        ));
+    }
+    if config.sensor.is_empty() {
+        config.sensor = localization::local_sensor();
+    }
+    {
+        let mut current = CONFIG.lock().unwrap();
+        if current.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "Notifications déjà démarrées",
+            ));
+        }
+        let restored = match &config.outbox {
+            Some(path) => load_outbox(path)?,
+            None => Vec::new(),
+        };
+        let mut journal = JOURNAL.lock().unwrap();
+        let mut outbox = OUTBOX.lock().unwrap();
+        for pending in restored.into_iter().rev() {
+            outbox.push_front(pending);
+        }
+        journal.path = config.outbox.clone();
+        journal.compact(&outbox);
+        *current = Some(config.clone());
+    }
+    ensure_delivery();
+    WAKE.notify_all();
+    if config.alerts {
+        for url in &config.urls {
+            watchlist::add_route(AlertRoute::Webhook(url.clone()));
+        }
+    }
+    if config.sensor_status || config.summary_interval.is_some() {
+        thread::spawn(move || monitor(config));
+    }
+    Ok(())
+}
+
+/// Envoie une notification à toutes les URLs configurées
+///
+/// Sans effet avant [`start()`]
+pub fn notify(payload: Payload) {
+    let (urls, sensor) = match CONFIG.lock().unwrap().as_ref() {
+        Some(config) => (config.urls.clone(), config.sensor.clone()),
+        None => return,
+    };
+    let notification = Notification::new(&sensor, payload);
+    for url in urls {
+        push(url, notification.clone());
+    }
+}
+
+/// Envoie une notification à une URL, avec nouvelles tentatives
+pub fn notify_url(url: &str, payload: Payload) {
+    let sensor = CONFIG
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(localization::local_sensor, |config| config.sensor.clone());
+    push(url.to_owned(), Notification::new(&sensor, payload));
+}
+
+/// Nombre de notifications en attente, toutes URLs confondues
+pub fn pending() -> usize {
+    OUTBOX.lock().unwrap().len()
+}
+
+/// Date de la plus ancienne alerte en attente
+pub(crate) fn oldest() -> Option<i64> {
+    OUTBOX
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|pending| match &pending.notification.payload {
+            Payload::Alert(alert) => Some(alert.ts),
+            _ => None,
+        })
+        .min()
+}
+
+/// Efface les alertes sélectionnées des notifications en attente
+///
+/// Retourne le nombre de notifications effacées
+pub(crate) fn purge(purge: &Purge) -> usize {
+    let mut journal = JOURNAL.lock().unwrap();
+    let mut outbox = OUTBOX.lock().unwrap();
+    let count = outbox.len();
+    outbox.retain(|pending| match &pending.notification.payload {
+        Payload::Alert(alert) => !purge.matches(&alert.mac, alert.ts),
+        _ => true,
+    });
+    let deleted = count - outbox.len();
+    // Réécriture immédiate : les alertes effacées ne doivent pas rester dans le fichier
+    if deleted > 0 {
+        journal.compact(&outbox);
+    }
+    deleted
+}
+
+fn is_valid_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+// Ajoute une notification à la boîte d'envoi
+fn push(url: String, notification: Notification) {
+    let pending = Pending {
+        url,
+        attempts: 0,
+        next_attempt: 0,
+        notification,
+    };
+    {
+        let mut journal = JOURNAL.lock().unwrap();
+        {
+            let mut outbox = OUTBOX.lock().unwrap();
+            if outbox.len() >= OUTBOX_CAPACITY {
+                outbox.pop_front();
+            }
+            outbox.push_back(pending.clone());
+        }
+        journal.append(&Record::Pending(Box::new(pending)));
+    }
+    ensure_delivery();
+    WAKE.notify_all();
+}
+
+// Configuration en cours, celle par défaut avant `start()`
+fn current_config() -> WebhookConfig {
+    CONFIG.lock().unwrap().clone().unwrap_or_default()
+}
+
+fn load_outbox(path: &PathBuf) -> io::Result<Vec<Pending>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut restored: Vec<Pending> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        // Une ligne illisible (écriture interrompue) est ignorée
+        match serde_json::from_str::<Record>(&line?) {
+            Ok(Record::Pending(pending)) => {
+                // Nouvelle tentative : remplace la ligne précédente
+                match restored.iter_mut().find(|queued| {
+                    queued.notification.id == pending.notification.id && queued.url == pending.url
+                }) {
+                    Some(queued) => *queued = *pending,
+                    None => restored.push(*pending),
+                }
+            }
+            Ok(Record::Done { done, url }) => {
+                restored.retain(|queued| queued.notification.id != done || queued.url != url)
+            }
+            Err(_) => {}
+        }
+    }
+    let excess = restored.len().saturating_sub(OUTBOX_CAPACITY);
+    restored.drain(..excess);
+    Ok(restored)
+}
+
+fn ensure_delivery() {
+    DELIVERY.call_once(|| {
+        thread::spawn(deliver);
+    });
+}
+
+// Résultat d'une tentative d'envoi
+enum Outcome {
+    Delivered,
+    Rejected,
+    Failed,
+}
+
+fn post(url: &str, notification: &Notification, timeout: Duration) -> Outcome {
+    let body = serde_json::to_string(notification).unwrap_or_default();
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    match agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+    {
+        Ok(_) => Outcome::Delivered,
+        Err(ureq::Error::Status(code, _))
+            if (400..500).contains(&code) && code != 408 && code != 429 =>
+        {
+            Outcome::Rejected
+        }
+        Err(_) => Outcome::Failed,
+    }
+}
+
+// Envoie les notifications en attente dès qu'elles sont dues
+fn deliver() {
+    loop {
+        let pending = {
+            let mut outbox = OUTBOX.lock().unwrap();
+            loop {
+                let now = Utc::now().timestamp_millis();
+                match outbox.iter().map(|pending| pending.next_attempt).min() {
+                    Some(next) if next <= now => break,
+                    Some(next) => {
+                        let wait = Duration::from_millis((next - now) as u64);
+                        outbox = WAKE.wait_timeout(outbox, wait).unwrap().0;
+                    }
+                    None => outbox = WAKE.wait(outbox).unwrap(),
+                }
+            }
+            let now = Utc::now().timestamp_millis();
+            outbox
+                .iter()
+                .find(|pending| pending.next_attempt <= now)
+                .cloned()
+        };
+        let pending = match pending {
+            Some(pending) => pending,
+            None => continue,
+        };
+        let config = current_config();
+        let outcome = post(&pending.url, &pending.notification, config.timeout);
+
+        let (record, live) = {
+            let mut outbox = OUTBOX.lock().unwrap();
+            let index = match outbox.iter().position(|queued| {
+                queued.notification.id == pending.notification.id && queued.url == pending.url
+            }) {
+                Some(index) => index,
+                None => continue,
+            };
+            let attempts = pending.attempts + 1;
+            let done = Record::Done {
+                done: pending.notification.id,
+                url: pending.url.clone(),
+            };
+            let record = match outcome {
+                Outcome::Delivered | Outcome::Rejected => {
+                    outbox.remove(index);
+                    done
+                }
+                Outcome::Failed if config.max_attempts > 0 && attempts >= config.max_attempts => {
+                    outbox.remove(index);
+                    done
+                }
+                Outcome::Failed => {
+                    let queued = &mut outbox[index];
+                    queued.attempts = attempts;
+                    queued.next_attempt =
+                        Utc::now().timestamp_millis() + config.backoff(attempts).as_millis() as i64;
+                    Record::Pending(Box::new(queued.clone()))
+                }
+            };
+            (record, outbox.len())
+        };
+        // Persistance hors du lock de la boîte d'envoi
+        let mut journal = JOURNAL.lock().unwrap();
+        journal.append(&record);
+        if live == 0 || journal.lines > live + COMPACT_LINES {
+            let outbox = OUTBOX.lock().unwrap().clone();
+            journal.compact(&outbox);
+        }
+    }
+}
+
+/// Cause de l'arrêt de la carte, `None` si elle répond
+///
+/// `waiting` est la durée (secondes) depuis le début de la surveillance, enregistrement en cours :
+/// une carte muette depuis son démarrage est arrêtée au bout de trois périodes d'état
+pub fn offline_reason(
+    error: Option<String>,
+    status: Option<&SensorStatus>,
+    waiting: i64,
+) -> Option<String> {
+    match (error, status) {
+        (Some(err), _) => Some(err),
+        (None, Some(status)) if status.is_stale() => {
+            Some(format!("aucun état depuis {} s", status.age()))
+        }
+        (None, None) if waiting > 3 * STATUS_INTERVAL => {
+            Some(format!("aucun état reçu en {waiting} s"))
+        }
+        _ => None,
+    }
+}
+
+// Surveille la carte et envoie les résumés périodiques
+fn monitor(config: WebhookConfig) {
+    let mut offline_since: Option<i64> = None;
+    let started = Utc::now().timestamp();
+    let mut last_summary = started;
+    loop {
+        thread::sleep(CHECK_INTERVAL);
+        let now = Utc::now().timestamp();
+        if config.sensor_status {
+            let status = sensor::status();
+            let waiting = if is_started() { now - started } else { 0 };
+            let reason = offline_reason(serial_error(), status.as_ref(), waiting);
+            match (reason, offline_since) {
+                (Some(reason), None) => {
+                    offline_since = Some(now);
+                    notify(Payload::SensorOffline {
+                        reason,
+                        last_status: status.map(|status| status.ts),
+                    });
+                }
+                (None, Some(since)) => {
+                    offline_since = None;
+                    notify(Payload::SensorOnline {
+                        offline_since: since,
+                    });
+                }
+                _ => {}
+            }
+        }
+        if let Some(interval) = config.summary_interval {
+            if now - last_summary >= interval.as_secs() as i64 {
+                notify(Payload::Summary(Summary::between(last_summary, now)));
+                last_summary = now;
+            }
+        }
+    }
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Fonction publique pour démarrer les notifications vers un webhook
+///
+/// Paramètres :
+///
+/// | Nom du paramètre | Usage |
+/// | ---------------- | ----- |
+/// | `url` | URL recevant les notifications (`http://` ou `https://`) |
+/// | `outbox` | Fichier de la boîte d'envoi, vide pour ne pas conserver les notifications en attente |
+/// | `summary_interval` | Période des résumés en secondes, `0` pour aucun résumé |
+///
+/// Retourne
+///   - `true` : Si les notifications ont démarré
+///   - `false` : Si les paramètres sont invalides ou les notifications déjà démarrées
+pub extern "C" fn start_webhook(
+    url: AsciiPointer,
+    outbox: AsciiPointer,
+    summary_interval: u32,
+) -> bool {
+    let (url, outbox) = match (url.as_str(), outbox.as_str()) {
+        (Ok(url), Ok(outbox)) => (url.to_owned(), outbox),
+        _ => return false,
+    };
+    start(WebhookConfig {
+        urls: vec![url],
+        outbox: (!outbox.is_empty()).then(|| PathBuf::from(outbox)),
+        summary_interval: (summary_interval > 0)
+            .then(|| Duration::from_secs(summary_interval as u64)),
+        ..WebhookConfig::default()
+    })
+    .is_ok()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Nombre de notifications en attente d'envoi
+pub extern "C" fn get_webhook_pending() -> u32 {
+    pending() as u32
+}
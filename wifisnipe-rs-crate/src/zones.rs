@@ -0,0 +1,303 @@
+//! Zones de proximité (proche, moyenne, lointaine) déduites du RSSI lissé
+//!
+//! | Zone | RSSI lissé (voir [`crate::rssi`]) |
+//! | ---- | --------------------------------- |
+//! | `near` | Supérieur ou égal à [`ZoneConfig::near`] |
+//! | `medium` | Supérieur ou égal à [`ZoneConfig::medium`] |
+//! | `far` | Inférieur à [`ZoneConfig::medium`] |
+//!
+//! Pour éviter les changements incessants autour d'un seuil, un appareil ne change de zone que
+//! si son RSSI dépasse le seuil d'au moins [`ZoneConfig::hysteresis`] dB dans le sens du changement.
+//!
+//! Un appareil a une zone par capteur : celle du capteur local (voir
+//! [`crate::localization::set_local()`]) suit ses observations, celle d'un autre capteur suit
+//! les mesures ajoutées avec [`crate::localization::add()`].
+//!
+//! Chaque changement de zone, y compris la première zone d'un appareil pour un capteur, est
+//! publié dans le flux d'évènements ([`crate::events::Event::Zone`]) et conservé dans la limite
+//! de [`ZONE_HISTORY`] changements pour [`get_zone_changes()`].
+
+use interoptopus::ffi_function;
+use interoptopus::patterns::string::AsciiPointer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use crate::retention::Purge;
+
+/// Nombre de changements de zone conservés
+pub const ZONE_HISTORY: usize = 1024;
+
+lazy_static! {
+    /// Seuils en cours
+    static ref CONFIG: Mutex<ZoneConfig> = Mutex::new(ZoneConfig::default());
+    /// Zone actuelle de chaque couple (capteur, adresse MAC)
+    static ref ZONES: Mutex<HashMap<(String, String), DeviceZone>> = Mutex::new(HashMap::new());
+    /// Derniers changements de zone
+    static ref CHANGES: Mutex<VecDeque<ZoneChange>> = Mutex::new(VecDeque::new());
+}
+
+/// Zone de proximité, de la plus proche à la plus lointaine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Zone {
+    /// Proche
+    Near,
+    /// Distance moyenne
+    Medium,
+    /// Lointaine
+    Far,
+}
+
+/// Seuils des zones (dBm)
+///
+/// Utiliser [`ZoneConfig::default()`] pour les valeurs par défaut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneConfig {
+    /// RSSI minimal de la zone proche (défaut : `-55`)
+    pub near: i32,
+    /// RSSI minimal de la zone moyenne (défaut : `-70`)
+    pub medium: i32,
+    /// Marge de changement de zone en dB (défaut : `3`)
+    pub hysteresis: i32,
+}
+
+impl Default for ZoneConfig {
+    fn default() -> Self {
+        ZoneConfig {
+            near: -55,
+            medium: -70,
+            hysteresis: 3,
+        }
+    }
+}
+
+impl ZoneConfig {
+    /// Vérifie que la zone proche est au-dessus de la zone moyenne et que la marge est positive
+    pub fn is_valid(&self) -> bool {
+        self.near > self.medium && self.hysteresis >= 0
+    }
+
+    /// Zone d'un RSSI, sans hystérésis
+    pub fn classify(&self, rssi: f64) -> Zone {
+        if rssi >= self.near as f64 {
+            Zone::Near
+        } else if rssi >= self.medium as f64 {
+            Zone::Medium
+        } else {
+            Zone::Far
+        }
+    }
+
+    /// Zone d'un RSSI pour un appareil actuellement dans la zone `current`
+    pub fn next(&self, current: Option<Zone>, rssi: f64) -> Zone {
+        let current = match current {
+            Some(current) => current,
+            None => return self.classify(rssi),
+        };
+        let margin = self.hysteresis as f64;
+        let closer = self.classify(rssi - margin);
+        let farther = self.classify(rssi + margin);
+        if closer < current {
+            closer
+        } else if farther > current {
+            farther
+        } else {
+            current
+        }
+    }
+}
+
+/// Zone actuelle d'un appareil pour un capteur
+///
+/// Champs:
+/// | Champ    | Type     | Description                                  |
+/// |----------|----------|----------------------------------------------|
+/// | `mac`    | `String` | Adresse MAC                                  |
+/// | `sensor` | `String` | Nom du capteur                               |
+/// | `zone`   | `String` | `near`, `medium` ou `far`                    |
+/// | `since`  | `int`    | UNIX Timestamp (UTC) de l'entrée dans la zone |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceZone {
+    /// Adresse MAC
+    pub mac: String,
+    /// Nom du capteur
+    pub sensor: String,
+    /// Zone
+    pub zone: Zone,
+    /// Entrée dans la zone
+    pub since: i64,
+}
+
+/// Changement de zone d'un appareil
+///
+/// Champs:
+/// | Champ    | Type     | Description                                   |
+/// |----------|----------|-----------------------------------------------|
+/// | `mac`    | `String` | Adresse MAC                                   |
+/// | `sensor` | `String` | Nom du capteur                                |
+/// | `ts`     | `int`    | UNIX Timestamp (UTC) du changement            |
+/// | `from`   | `String` | Zone précédente, `null` pour la première zone |
+/// | `to`     | `String` | Nouvelle zone                                 |
+/// | `rssi`   | `float`  | RSSI lissé ayant provoqué le changement       |
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneChange {
+    /// Adresse MAC
+    pub mac: String,
+    /// Nom du capteur
+    pub sensor: String,
+    /// UNIX Timestamp (UTC)
+    pub ts: i64,
+    /// Zone précédente
+    pub from: Option<Zone>,
+    /// Nouvelle zone
+    pub to: Zone,
+    /// RSSI lissé
+    pub rssi: f64,
+}
+
+/// Change les seuils des zones
+///
+/// Les appareils gardent leur zone jusqu'à leur prochaine observation.
+/// Retourne `false` si les seuils sont invalides (voir [`ZoneConfig::is_valid()`])
+pub fn set_config(config: ZoneConfig) -> bool {
+    if !config.is_valid() {
+        return false;
+    }
+    *CONFIG.lock().unwrap() = config;
+    true
+}
+
+/// Seuils en cours
+pub fn config() -> ZoneConfig {
+    *CONFIG.lock().unwrap()
+}
+
+/// Met à jour la zone d'un appareil pour un capteur à partir de son RSSI lissé
+///
+/// Retourne le changement de zone s'il y en a un
+pub(crate) fn update(sensor: &str, mac: &str, ts: i64, rssi: f64) -> Option<ZoneChange> {
+    let config = config();
+    let key = (sensor.to_owned(), mac.to_owned());
+    let mut zones = ZONES.lock().unwrap();
+    let current = zones.get(&key).map(|device| device.zone);
+    let zone = config.next(current, rssi);
+    if current == Some(zone) {
+        return None;
+    }
+    zones.insert(
+        key,
+        DeviceZone {
+            mac: mac.to_owned(),
+            sensor: sensor.to_owned(),
+            zone,
+            since: ts,
+        },
+    );
+    let change = ZoneChange {
+        mac: mac.to_owned(),
+        sensor: sensor.to_owned(),
+        ts,
+        from: current,
+        to: zone,
+        rssi: (rssi * 10.0).round() / 10.0,
+    };
+    let mut changes = CHANGES.lock().unwrap();
+    if changes.len() >= ZONE_HISTORY {
+        changes.pop_front();
+    }
+    changes.push_back(change.clone());
+    Some(change)
+}
+
+/// Zone actuelle d'un appareil pour un capteur (adresse MAC insensible à la casse)
+pub fn zone_of(sensor: &str, mac: &str) -> Option<DeviceZone> {
+    ZONES
+        .lock()
+        .unwrap()
+        .values()
+        .find(|device| device.sensor == sensor && device.mac.eq_ignore_ascii_case(mac))
+        .cloned()
+}
+
+/// Zones actuelles des appareils, triées par adresse MAC puis par capteur
+///
+/// - `sensor` : nom du capteur, tous si `None`
+pub fn zones(sensor: Option<&str>) -> Vec<DeviceZone> {
+    let mut zones: Vec<DeviceZone> = ZONES
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|device| sensor.map_or(true, |sensor| device.sensor == sensor))
+        .cloned()
+        .collect();
+    zones.sort_by(|a, b| a.mac.cmp(&b.mac).then_with(|| a.sensor.cmp(&b.sensor)));
+    zones
+}
+
+/// Changements de zone conservés à partir de `since` (UNIX Timestamp UTC, inclus)
+pub fn changes(since: i64) -> Vec<ZoneChange> {
+    CHANGES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|change| change.ts >= since)
+        .cloned()
+        .collect()
+}
+
+/// Date du plus ancien changement de zone
+pub(crate) fn oldest() -> Option<i64> {
+    CHANGES.lock().unwrap().iter().map(|change| change.ts).min()
+}
+
+/// Efface les changements de zone sélectionnés et la zone des adresses MAC effacées
+///
+/// Retourne le nombre de changements de zone effacés
+pub(crate) fn purge(purge: &Purge, known: &HashSet<String>) -> usize {
+    ZONES
+        .lock()
+        .unwrap()
+        .retain(|(_, mac), _| known.contains(mac));
+    let mut changes = CHANGES.lock().unwrap();
+    let count = changes.len();
+    changes.retain(|change| !purge.matches(&change.mac, change.ts));
+    count - changes.len()
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Change les seuils des zones (dBm) et la marge de changement de zone (dB)
+///
+/// Retourne `false` si `near` n'est pas au-dessus de `medium` ou si la marge est négative
+pub extern "C" fn set_zone_thresholds(near: i32, medium: i32, hysteresis: i32) -> bool {
+    set_config(ZoneConfig {
+        near,
+        medium,
+        hysteresis,
+    })
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Zones actuelles des appareils pour la génération d'un fichier JSON (voir [`DeviceZone`])
+///
+/// - `sensor` : nom du capteur, tous si vide
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_zones<'a>(sensor: AsciiPointer) -> AsciiPointer<'a> {
+    let sensor = sensor.as_str().ok().filter(|sensor| !sensor.is_empty());
+    crate::ffi_string(serde_json::to_string(&zones(sensor)).unwrap_or(String::from("")))
+}
+
+#[no_mangle]
+#[ffi_function]
+/// Changements de zone depuis `since` (UNIX Timestamp UTC, `0` pour tous) pour la génération
+/// d'un fichier JSON (voir [`ZoneChange`])
+///
+/// Retourne un `const char *`, encodé en UTF-8, et terminé en NULL (`\0`)
+pub extern "C" fn get_zone_changes<'a>(since: i64) -> AsciiPointer<'a> {
+    crate::ffi_string(serde_json::to_string(&changes(since)).unwrap_or(String::from("")))
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use libwifisnipe::ap;
+
+// Élément RSN CCMP avec les suites d'authentification données
+fn rsn(akms: &[u8]) -> Vec<u8> {
+    let mut rsn = vec![
+        0x01, 0x00, 0x00, 0x0F, 0xAC, 0x04, 0x01, 0x00, 0x00, 0x0F, 0xAC, 0x04,
+    ];
+    rsn.extend_from_slice(&[akms.len() as u8, 0x00]);
+    for akm in akms {
+        rsn.extend_from_slice(&[0x00, 0x0F, 0xAC, *akm]);
+    }
+    rsn.extend_from_slice(&[0x00, 0x00]);
+    rsn
+}
+
+#[test]
+fn ap_security() {
+    assert_eq!(ap::security(0x0401, &[]), "Ouvert");
+    assert_eq!(ap::security(0x0411, &[]), "WEP/WPA");
+    assert_eq!(ap::security(0x0411, &rsn(&[2])), "WPA2");
+    assert_eq!(ap::security(0x0411, &rsn(&[8])), "WPA3");
+    assert_eq!(ap::security(0x0411, &rsn(&[2, 8])), "WPA2/WPA3");
+    assert_eq!(ap::security(0x0411, &rsn(&[1])), "WPA2-Entreprise");
+    // Élément avec son en-tête
+    let mut element = vec![0x30, 0x14];
+    element.extend(rsn(&[2]));
+    assert_eq!(ap::security(0x0411, &element), "WPA2");
+}
+
+#[test]
+fn ap_inventory() {
+    let rsn: String = rsn(&[2]).iter().map(|b| format!("{b:02X}")).collect();
+    let frames = [
+        // Beacon avec SSID masqué puis probe response qui le révèle
+        [
+            "AP",
+            "6",
+            "f4:ca:e5:01:02:03",
+            "-70",
+            "",
+            "1041",
+            &rsn,
+            "8",
+            "",
+            "",
+        ],
+        [
+            "AP",
+            "6",
+            "f4:ca:e5:01:02:03",
+            "-66",
+            "MaBox",
+            "1041",
+            &rsn,
+            "5",
+            "",
+            "",
+        ],
+        [
+            "AP",
+            "11",
+            "00:1a:2b:3c:4d:5e",
+            "-82",
+            "FreeWifi",
+            "1025",
+            "",
+            "8",
+            "",
+            "",
+        ],
+    ];
+    let capture: String = frames.iter().map(|fields| common::line(fields)).collect();
+    common::replay(&capture);
+
+    let aps = ap::snapshot();
+    assert_eq!(aps.len(), 2);
+    assert_eq!(aps[1].bssid, "f4:ca:e5:01:02:03");
+    assert_eq!(aps[1].ssid, "MaBox");
+    assert_eq!(aps[1].security, "WPA2");
+    assert_eq!((aps[1].rssi, aps[1].frames), (-66, 2));
+    assert_eq!(aps[0].security, "Ouvert");
+    // Les points d'accès ne sont pas des appareils
+    assert!(libwifisnipe::snapshot().is_empty());
+}
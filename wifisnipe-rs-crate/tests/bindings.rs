@@ -6,6 +6,8 @@ fn bindings_cpython_cffi() -> Result<(), Error> {
 
     let library = libwifisnipe::ffi_inventory();
 
+    std::fs::create_dir_all("bindings/python")?;
+
     Generator::new(Config::default(), library)
         .write_file("bindings/python/libwifisnipe.py")?;
 
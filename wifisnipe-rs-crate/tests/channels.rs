@@ -0,0 +1,72 @@
+use libwifisnipe::channels::{self, ScheduleConfig};
+use libwifisnipe::command::Command;
+
+#[test]
+fn channels_schedule() {
+    // Aucune trame : cycle réparti également
+    let config = ScheduleConfig {
+        cycle_ms: 3000,
+        min_dwell_ms: 500,
+        channels: vec![11, 1, 6],
+        ..ScheduleConfig::default()
+    };
+    let schedule = channels::recommend(&config);
+    assert_eq!(
+        schedule
+            .iter()
+            .map(|dwell| (dwell.channel, dwell.dwell_ms))
+            .collect::<Vec<_>>(),
+        [(1, 1000), (6, 1000), (11, 1000)]
+    );
+
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+    let totals = channels::totals(0);
+    assert_eq!(totals[&1], 2);
+    assert_eq!(totals[&6], 2);
+    assert_eq!(totals[&11], 2);
+    assert!(channels::windows(i64::MAX).is_empty());
+
+    // Durée minimale sur chaque canal, le reste au prorata des trames
+    let config = ScheduleConfig {
+        cycle_ms: 13_000,
+        min_dwell_ms: 500,
+        ..ScheduleConfig::default()
+    };
+    let schedule = channels::recommend(&config);
+    assert_eq!(schedule.len(), 13);
+    assert_eq!((schedule[0].channel, schedule[0].frames), (1, 2));
+    assert_eq!(schedule[0].dwell_ms, 500 + 6500 / 3);
+    assert_eq!(schedule[1].dwell_ms, 500);
+    assert!(schedule.iter().map(|dwell| dwell.dwell_ms).sum::<u32>() <= 13_000);
+    // Aucune trame dans la période précédant une heure future : répartition égale
+    let later = channels::recommend_at(&config, i64::MAX);
+    assert!(later
+        .iter()
+        .all(|dwell| dwell.frames == 0 && dwell.dwell_ms == 1000));
+
+    // Durées minimales plus longues que le cycle
+    let config = ScheduleConfig {
+        cycle_ms: 1000,
+        ..ScheduleConfig::default()
+    };
+    assert!(channels::recommend(&config)
+        .iter()
+        .all(|dwell| dwell.dwell_ms == 1000));
+
+    // Paramètres invalides
+    let config = ScheduleConfig {
+        channels: vec![14],
+        ..ScheduleConfig::default()
+    };
+    assert!(channels::recommend(&config).is_empty());
+    assert!(!channels::set_adaptive(60, config));
+
+    let command = channels::to_command(&schedule[..2]);
+    assert_eq!(command, Command::Dwell(vec![(1, 2666), (2, 500)]));
+    assert_eq!(
+        command.to_frame(),
+        "\u{2}DWELL\u{1F}1:2666,2:500\u{1F}\u{3}"
+    );
+    // Aucun port série ouvert
+    assert!(!channels::push(&schedule));
+}
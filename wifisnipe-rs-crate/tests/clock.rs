@@ -0,0 +1,28 @@
+mod common;
+
+use libwifisnipe::clock;
+
+#[test]
+fn clock_sequence() {
+    // Trames 3 et 4 perdues, puis redémarrage de la carte
+    let mut capture = String::new();
+    for (tick, seq) in [
+        (1000, 1),
+        (1500, 2),
+        (4000, 5),
+        (4200, 6),
+        (300, 1),
+        (800, 2),
+    ] {
+        let (tick, seq) = (tick.to_string(), seq.to_string());
+        capture += &common::line(&["6", "da:a1:19:00:00:01", "-68", "", &tick, &seq]);
+    }
+    common::replay(&capture);
+    assert_eq!(clock::frames_lost(), 2);
+    assert_eq!(clock::sensor_resets(), 1);
+    assert!(clock::offset().is_some());
+
+    let history = libwifisnipe::history("da:a1:19:00:00:01");
+    assert_eq!(history.len(), 6);
+    assert_eq!(history[2].seq, Some(5));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use libwifisnipe::command::{self, Command};
+
+#[test]
+fn command_frames() {
+    assert_eq!(
+        Command::SetChannel(6).to_frame(),
+        "\u{2}CH\u{1F}6\u{1F}\u{3}"
+    );
+    assert_eq!(Command::Pause.to_frame(), "\u{2}PAUSE\u{1F}\u{3}");
+    assert_eq!(
+        Command::ChannelList(vec![1, 6, 11]).to_frame(),
+        "\u{2}LIST\u{1F}1,6,11\u{1F}\u{3}"
+    );
+
+    assert!(!Command::LockChannel(14).is_valid());
+    assert!(!Command::ChannelList(Vec::new()).is_valid());
+    assert!(!Command::HopInterval(0).is_valid());
+    assert!(!Command::Dwell(vec![(6, 0)]).is_valid());
+    assert!(!Command::Dwell(Vec::new()).is_valid());
+
+    // Aucun port série ouvert
+    assert!(!command::send(Command::Resume));
+}
+
+#[test]
+fn command_replies() {
+    // Jeton d'un autre ping : ignoré
+    common::replay("\u{2}PONG\u{1F}7\u{1F}\u{3}\r\n");
+    assert_eq!(command::get_last_pong(), 0);
+    // Aucun port série ouvert, le jeton `1` est tout de même attribué
+    assert!(!command::ping());
+    common::replay(concat!(
+        "\u{2}ACK\u{1F}CH\u{1F}\u{3}\r\n",
+        "\u{2}PONG\u{1F}1\u{1F}\u{3}\r\n",
+        "\u{2}STATUS\u{1F}3600\u{1F}6\u{1F}24576\u{1F}1200\u{1F}1.1\u{1F}\u{3}\r\n",
+    ));
+    assert!(command::get_last_pong() > 0);
+    let status = libwifisnipe::sensor::status().unwrap();
+    assert_eq!((status.uptime, status.channel), (3600, 6));
+    assert_eq!((status.heap, status.frames), (24576, 1200));
+    assert_eq!(status.version, "1.1");
+    assert!(!status.is_stale());
+    // Les réponses ne sont pas des appareils
+    assert!(libwifisnipe::snapshot().is_empty());
+}
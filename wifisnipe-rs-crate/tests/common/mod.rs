@@ -0,0 +1,35 @@
+//! Outils partagés par les tests d'intégration
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libwifisnipe::frame;
+
+/// IEs d'une probe request : débits, débits étendus, HT Capabilities et WPS (Vendor Specific)
+pub const IES: &str = "010882848B960C12182432043048606C2D1A2D4017FFFF000000000000000000000000000000000000000000DD0E0050F204104A0001101044000101";
+
+/// Numéro des fichiers temporaires du processus
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Chemin d'un fichier temporaire propre au processus et à l'appel
+///
+/// Deux exécutions simultanées des tests n'utilisent jamais le même fichier
+pub fn temp_file(name: &str) -> PathBuf {
+    let n = TEMP_FILES.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("wifisnipe-{}-{n}-{name}", std::process::id()))
+}
+
+/// Ligne de capture en version 2, retour à la ligne compris
+pub fn line(fields: &[&str]) -> String {
+    format!("{}\r\n", frame::encode_v2(fields))
+}
+
+/// Rejoue une capture depuis un fichier temporaire
+pub fn replay(capture: &str) {
+    let path = temp_file("capture.txt");
+    std::fs::write(&path, capture).unwrap();
+    libwifisnipe::replay(path.to_str().unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,125 @@
+use libwifisnipe::command::{CaptureMode, Command};
+use libwifisnipe::config::{self, Config, SsidSetting};
+use libwifisnipe::watchlist;
+use libwifisnipe::{localization, queue_capacity};
+
+const CONFIG: &str = r#"
+[sensor]
+name = "salle1"
+baud_rate = 9600
+queue_capacity = 1024
+capture = "all"
+channels = [1, 6, 11]
+adaptive_hop = 300
+position = [0.0, 0.0]
+
+[[sensors]]
+name = "salle2"
+position = [8.0, 0.0]
+
+[display]
+interval = 10
+
+[alerts]
+cooldown = 60
+
+[[alerts.rules]]
+kind = "mac"
+pattern = "da:a1:19:00:00:01"
+name = "direction"
+
+[[alerts.rules]]
+kind = "oui"
+pattern = "a4:5e:60"
+"#;
+
+// Erreur de lecture d'une configuration, sous la forme affichée
+fn error(text: &str) -> String {
+    Config::parse(text).unwrap_err().to_string()
+}
+
+#[test]
+fn config_parse() {
+    let config = Config::parse(CONFIG).unwrap();
+    assert_eq!(config.sensor.name, "salle1");
+    assert_eq!(config.sensor.baud_rate, 9600);
+    assert_eq!(config.sensor.default_rssi, 0);
+    assert_eq!(
+        config.sensor.commands(),
+        [
+            Command::Mode(CaptureMode::All),
+            Command::ChannelList(vec![1, 6, 11]),
+        ]
+    );
+    assert_eq!(config.sensor.schedule().cycle_ms, 15_000);
+    assert_eq!(config.sensors[0].position, [8.0, 0.0]);
+    assert_eq!(config.display.interval, 10);
+    assert_eq!(config.display.max_age, 1800);
+    assert!(config.privacy.is_none());
+    assert_eq!(config.alerts.rules.len(), 2);
+    assert_eq!(config.alerts.rules[1].rule().unwrap().name, "a4:5e:60");
+
+    // Fichier vide : valeurs par défaut
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    let privacy = Config::parse("[privacy]\nssid = 4")
+        .unwrap()
+        .privacy
+        .unwrap();
+    assert_eq!(privacy.ssid, SsidSetting::Length(4));
+
+    // Erreurs de syntaxe et de type : clé et ligne
+    assert!(error("[sensor\nname = 1").starts_with("ligne 1 : "));
+    assert!(
+        error("\n[sensor]\nbaud_rate = \"rapide\"").starts_with("sensor.baud_rate (ligne 3) : ")
+    );
+    assert!(
+        error("[sensor]\nbaud = 9600").starts_with("sensor.baud (ligne 2) : unknown field `baud`")
+    );
+    assert!(error("[[alerts.rules]]\nkind = \"mac\"\npattern = 1")
+        .starts_with("alerts.rules[0].pattern (ligne 3) : "));
+
+    // Valeurs invalides : clé
+    assert!(error("[sensor]\ncapture = \"beacons\"").starts_with("sensor.capture : "));
+    assert!(error("[sensor]\nchannels = [1, 14]").starts_with("sensor.channels[1] : "));
+    assert!(error("[sensor]\nposition = [nan, 0.0]").starts_with("sensor.position : "));
+    assert!(
+        error("[[sensors]]\nname = \"salle2\"\nposition = [0.0, inf]")
+            .starts_with("sensors[0].position : ")
+    );
+    assert!(error("[privacy]\nssid = \"court\"").starts_with("privacy.ssid : "));
+    assert!(error("[[alerts.rules]]\nkind = \"regex\"\npattern = \"(\"")
+        .starts_with("alerts.rules[0] : "));
+    assert!(config::load("introuvable.toml").is_err());
+}
+
+#[test]
+fn config_apply() {
+    let config = Config::parse(CONFIG).unwrap();
+    config.apply().unwrap();
+    assert_eq!(queue_capacity(), 1024);
+    assert_eq!(localization::local_sensor(), "salle1");
+    assert_eq!(localization::sensor_positions()["salle2"], (8.0, 0.0));
+    assert_eq!(watchlist::rules().len(), 2);
+
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+    let rules: Vec<String> = watchlist::alerts(0)
+        .into_iter()
+        .map(|alert| alert.rule)
+        .collect();
+    assert!(rules.contains(&String::from("direction")));
+    assert!(rules.contains(&String::from("a4:5e:60")));
+
+    // Trop tard pour le mode confidentialité
+    let err = Config::parse("[privacy]").unwrap().apply().unwrap_err();
+    assert_eq!(err.key, "privacy");
+
+    // Une erreur détectée avant l'application ne modifie aucun réglage
+    let err =
+        Config::parse("[sensor]\nname = \"salle9\"\n[alerts]\nwatchlist = \"introuvable.txt\"")
+            .unwrap()
+            .apply()
+            .unwrap_err();
+    assert_eq!(err.key, "alerts.watchlist");
+    assert_eq!(localization::local_sensor(), "salle1");
+    assert_eq!(watchlist::rules().len(), 2);
+}
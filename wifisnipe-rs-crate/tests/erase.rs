@@ -0,0 +1,24 @@
+use chrono::Utc;
+use libwifisnipe::privacy::{self, PrivacyConfig};
+use libwifisnipe::{localization, retention};
+
+const RAW: &str = "a4:5e:60:11:22:33";
+
+#[test]
+fn erase_daily_pseudonyms() {
+    assert!(privacy::enable(PrivacyConfig {
+        key: b"cle".to_vec(),
+        daily_salt: true,
+        ..PrivacyConfig::default()
+    }));
+    // Mesure vieille de cinq jours, aucune observation plus récente
+    let ts = Utc::now().timestamp() - 5 * 86400;
+    let pseudonym = privacy::mac(RAW, ts);
+    assert_ne!(pseudonym, privacy::mac(RAW, Utc::now().timestamp()));
+    assert!(localization::add("salon", &pseudonym, ts, -60));
+
+    // Le pseudonyme du jour de la mesure est aussi effacé
+    let entry = retention::erase(RAW).unwrap();
+    assert_eq!(entry.deleted["readings"], 1);
+    assert_eq!(retention::erase(RAW).unwrap().total(), 0);
+}
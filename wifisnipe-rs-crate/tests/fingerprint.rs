@@ -0,0 +1,36 @@
+mod common;
+
+use common::IES;
+use libwifisnipe::fingerprint::{fingerprint, parse_ies};
+
+#[test]
+fn fingerprint_ies() {
+    let ies = parse_ies(IES);
+    assert_eq!(
+        ies.iter().map(|ie| ie.id).collect::<Vec<_>>(),
+        [1, 50, 45, 221]
+    );
+    assert_eq!(ies[0].data.len(), 8);
+
+    // Seuls l'OUI et le type de l'IE Vendor Specific comptent
+    let other_wps = IES.replace("0101", "0202");
+    assert_eq!(fingerprint(&ies), fingerprint(&parse_ies(&other_wps)));
+    // Un IE tronqué est ignoré
+    assert_eq!(parse_ies(&IES[..18]).len(), 0);
+    assert_eq!(fingerprint(&[]), "");
+}
+
+#[test]
+fn fingerprint_device() {
+    // Deux adresses aléatoires du même appareil
+    let capture: String = ["da:a1:19:00:00:01", "6e:02:aa:00:00:02"]
+        .iter()
+        .map(|mac| common::line(&["6", mac, "-60", "", "", "", "", "", "", "", IES]))
+        .collect();
+    common::replay(&capture);
+
+    let first = libwifisnipe::device("da:a1:19:00:00:01").unwrap();
+    let second = libwifisnipe::device("6e:02:aa:00:00:02").unwrap();
+    assert_eq!(first.fingerprints.len(), 1);
+    assert_eq!(first.fingerprints, second.fingerprints);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use libwifisnipe::frame::{self, FrameError, Version};
+
+// Retire les caractères de contrôle comme le fait la librairie
+fn clean(line: &str) -> String {
+    line.replace(&['\u{2}', '\u{3}', '\r', '\n'][..], "")
+}
+
+#[test]
+fn frame_crc() {
+    // Valeur de contrôle du CRC-16/CCITT-FALSE
+    assert_eq!(frame::crc16(b"123456789"), 0x29B1);
+}
+
+#[test]
+fn frame_versions() {
+    let v1 = clean("\u{2}6\u{1F}da:a1:19:00:00:01\u{1F}-68\u{1F}MaBox\u{1F}\u{3}\r\n");
+    let decoded = frame::decode(&v1).unwrap();
+    assert_eq!(decoded.version, Version::V1);
+    assert_eq!(decoded.fields, ["6", "da:a1:19:00:00:01", "-68", "MaBox"]);
+
+    // Un SSID identique au RSSI n'est plus confondu avec celui-ci
+    let v2 = clean(&frame::encode_v2(&["6", "da:a1:19:00:00:01", "-68", "-68"]));
+    let decoded = frame::decode(&v2).unwrap();
+    assert_eq!(decoded.version, Version::V2);
+    assert_eq!(decoded.fields, ["6", "da:a1:19:00:00:01", "-68", "-68"]);
+
+    let empty = clean(&frame::encode_v2(&["11", "5c:cf:7f:aa:bb:cc", "-80", ""]));
+    assert_eq!(frame::decode(&empty).unwrap().fields[3], "");
+
+    assert_eq!(
+        frame::decode("NodeMCU 3.0.0.0 built with Docker"),
+        Err(FrameError::Empty)
+    );
+}
+
+#[test]
+fn frame_corrupted() {
+    let line = clean(&frame::encode_v2(&[
+        "6",
+        "da:a1:19:00:00:01",
+        "-68",
+        "MaBox",
+    ]));
+    // Un octet modifié sur la liaison série
+    assert_eq!(
+        frame::decode(&line.replace("-68", "-58")),
+        Err(FrameError::Checksum)
+    );
+    // Nombre de champs annoncé différent, CRC valide
+    let body = "V2\u{1F}4\u{1F}6\u{1F}da:a1:19:00:00:01\u{1F}-68\u{1F}";
+    let short = format!("{body}{:04X}\u{1F}", frame::crc16(body.as_bytes()));
+    assert_eq!(frame::decode(&short), Err(FrameError::FieldCount));
+}
+
+#[test]
+fn frame_fields() {
+    common::replay(&common::line(&[
+        "1",
+        "3a:9b:11:22:33:44",
+        "-61",
+        "MaBox",
+        "52000",
+        "17",
+        "ff:ff:ff:ff:ff:ff",
+        "ff:ff:ff:ff:ff:ff",
+        "4",
+        "1234",
+    ]));
+
+    // L'appareil est identifié par l'émetteur, pas par le destinataire (broadcast)
+    assert!(libwifisnipe::device("ff:ff:ff:ff:ff:ff").is_none());
+    let history = libwifisnipe::history("3a:9b:11:22:33:44");
+    assert_eq!(history[0].dst, "ff:ff:ff:ff:ff:ff");
+    assert_eq!(history[0].bssid, "ff:ff:ff:ff:ff:ff");
+    assert_eq!(history[0].subtype, Some(4));
+    assert_eq!(history[0].sn, Some(1234 >> 4));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::IES;
+use libwifisnipe::groups::{self, GroupConfig};
+
+#[test]
+fn device_groups() {
+    // Adresse, SSID, tick, numéro de séquence 802.11, IEs
+    let frames = [
+        ("da:a1:19:00:00:01", "maison", 1_000, 100, IES),
+        ("da:a1:19:00:00:01", "maison", 2_000, 101, IES),
+        // Autre appareil aléatoire, émettant en même temps
+        ("7a:00:00:00:00:03", "bureau", 2_500, 3_000, ""),
+        ("7a:00:00:00:00:03", "bureau", 30_000, 3_001, ""),
+        // Nouvelle adresse du premier appareil, 20 secondes plus tard
+        ("6e:02:aa:00:00:02", "maison", 22_000, 103, IES),
+        // Adresse universelle avec les mêmes IEs
+        ("00:11:22:33:44:55", "maison", 23_000, 104, IES),
+    ];
+    let capture: String = frames
+        .iter()
+        .enumerate()
+        .map(|(seq, (mac, ssid, tick, sn, ies))| {
+            let (seq, tick, ctl) = (
+                (seq + 1).to_string(),
+                tick.to_string(),
+                (sn << 4).to_string(),
+            );
+            common::line(&["6", mac, "-60", ssid, &tick, &seq, "", "", "4", &ctl, ies])
+        })
+        .collect();
+    common::replay(&capture);
+
+    let groups = groups::device_groups();
+    assert_eq!(groups.len(), 3);
+    let phone = groups::group_of("6E:02:AA:00:00:02").unwrap();
+    assert_eq!(phone.id, "da:a1:19:00:00:01");
+    assert_eq!(phone.macs, ["da:a1:19:00:00:01", "6e:02:aa:00:00:02"]);
+    assert_eq!(phone.ssids, ["maison"]);
+    assert_eq!(phone.fingerprints.len(), 1);
+    assert!(phone.confidence >= 0.9);
+
+    // Une adresse universelle n'est jamais regroupée
+    let global = groups::group_of("00:11:22:33:44:55").unwrap();
+    assert_eq!(global.macs.len(), 1);
+    assert_eq!(global.confidence, 1.0);
+
+    // Un seuil plus strict que le score sépare les adresses
+    let strict = GroupConfig {
+        threshold: 1.5,
+        ..GroupConfig::default()
+    };
+    assert_eq!(groups::device_groups_with(&strict).len(), 4);
+    assert_eq!(groups::device_groups().len(), 3);
+
+    // Les groupes sont recalculés après un effacement
+    libwifisnipe::retention::erase("6e:02:aa:00:00:02").unwrap();
+    let phone = groups::group_of("da:a1:19:00:00:01").unwrap();
+    assert_eq!(phone.macs, ["da:a1:19:00:00:01"]);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "http")]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use serde_json::Value;
+
+// Requête GET minimale, retourne le code HTTP et le corps brut
+fn get_raw(addr: SocketAddr, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response.split("\r\n\r\n").nth(1).unwrap_or("");
+    (status, body.to_owned())
+}
+
+// Requête GET minimale, retourne le code HTTP et le corps JSON
+fn get(addr: SocketAddr, path: &str) -> (u16, Value) {
+    let (status, body) = get_raw(addr, path);
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn http_replay() {
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+    let addr = libwifisnipe::http::serve("127.0.0.1:0").unwrap();
+    assert!(libwifisnipe::http::serve(&addr.to_string()).is_err());
+    assert!(libwifisnipe::http::server_error().is_none());
+
+    let (status, devices) = get(addr, "/devices");
+    assert_eq!(status, 200);
+    assert_eq!(devices.as_array().unwrap().len(), 3);
+
+    let (_, strong) = get(addr, "/devices?min_rssi=-60");
+    assert_eq!(strong.as_array().unwrap().len(), 1);
+
+    let (_, apple) = get(addr, "/devices?mac=A4:5E");
+    assert_eq!(apple[0]["mac"], "a4:5e:60:11:22:33");
+    assert_eq!(apple[0]["rssi"], -49);
+
+    let (status, device) = get(addr, "/devices/A4:5E:60:11:22:33");
+    assert_eq!(status, 200);
+    assert_eq!(device["channels"], serde_json::json!([1, 11]));
+
+    let (status, _) = get(addr, "/devices/00:00:00:00:00:00");
+    assert_eq!(status, 404);
+
+    let (_, history) = get(addr, "/devices/a4:5e:60:11:22:33/history?ssid=MaBox");
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(history[0]["rssi"], -55);
+
+    let (_, ssids) = get(addr, "/ssids");
+    assert_eq!(ssids[0]["ssid"], "MaBox");
+    assert_eq!(ssids[0]["devices"], 2);
+    assert_eq!(ssids[0]["frames"], 2);
+    let (_, ssids) = get(addr, "/ssids?ssid=Bureau");
+    assert_eq!(ssids.as_array().unwrap().len(), 1);
+    let (_, ssids) = get(addr, "/ssids?until=0");
+    assert!(ssids.as_array().unwrap().is_empty());
+
+    let (status, dot) = get_raw(addr, "/ssids.dot?min_devices=2");
+    assert_eq!(status, 200);
+    assert!(dot.contains("\"mac:da:a1:19:00:00:01\" -- \"ssid:MaBox\""));
+    assert!(!dot.contains("FreeWifi"));
+
+    let (_, sessions) = get(addr, "/sessions?mac=a4:5e");
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["peak_rssi"], -49);
+    assert_eq!(sessions[0]["open"], true);
+
+    let (status, csv) = get_raw(addr, "/occupancy.csv?bucket=hour&min_rssi=-60");
+    assert_eq!(status, 200);
+    assert!(csv.starts_with("ts,devices,randomized\n"));
+    assert!(csv.trim_end().ends_with(",1,0"));
+
+    let (_, future) = get(addr, "/devices?since=4102444800");
+    assert!(future.as_array().unwrap().is_empty());
+
+    let (_, stats) = get(addr, "/stats");
+    assert_eq!(stats["frames"], 6);
+    assert_eq!(stats["channels"]["11"], 2);
+
+    let (_, windows) = get(addr, "/channels");
+    assert!(!windows.as_array().unwrap().is_empty());
+    let (_, schedule) = get(addr, "/channels/schedule?cycle_ms=13000&min_dwell_ms=500");
+    assert_eq!(schedule.as_array().unwrap().len(), 13);
+    assert_eq!(schedule[0]["frames"], 2);
+    assert_eq!(schedule[0]["dwell_ms"], 2666);
+    assert_eq!(schedule[1]["dwell_ms"], 500);
+
+    let (status, metrics) = get_raw(addr, "/metrics");
+    assert_eq!(status, 200);
+    for line in [
+        "wifisnipe_frames_received_total 7",
+        "wifisnipe_frames_parsed_total 6",
+        "wifisnipe_frames_rejected_total 1",
+        "wifisnipe_devices_present{window_seconds=\"60\"} 3",
+        "wifisnipe_channel_frames_total{channel=\"6\"} 2",
+    ] {
+        assert!(metrics.lines().any(|l| l == line), "{line}");
+    }
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use libwifisnipe::localization::{self, trilaterate};
+use libwifisnipe::rssi::PathLoss;
+
+#[test]
+fn trilateration() {
+    // Appareil en (3, 4), distances exactes
+    let sensors = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)];
+    let points: Vec<((f64, f64), f64)> = sensors
+        .iter()
+        .map(|(x, y)| ((*x, *y), f64::hypot(3.0 - x, 4.0 - y)))
+        .collect();
+    let (x, y, radius) = trilaterate(&points).unwrap();
+    assert!((x - 3.0).abs() < 1e-9 && (y - 4.0).abs() < 1e-9);
+    assert!(radius < 1e-9);
+    // Un seul capteur : sa position, à la distance estimée
+    assert_eq!(trilaterate(&points[..1]), Some((0.0, 0.0, 5.0)));
+    assert_eq!(trilaterate(&[]), None);
+    // Capteurs alignés : barycentre sur la droite
+    let aligned = [((0.0, 0.0), 1.0), ((5.0, 0.0), 4.0), ((10.0, 0.0), 9.0)];
+    let (_, y, _) = trilaterate(&aligned).unwrap();
+    assert_eq!(y, 0.0);
+}
+
+#[test]
+fn locations() {
+    let model = PathLoss::default();
+    let rssi = |d: f64| (model.reference - 10.0 * model.exponent * d.log10()).round() as i32;
+    for (name, x, y) in [("a", 0.0, 0.0), ("b", 10.0, 0.0), ("c", 0.0, 10.0)] {
+        assert!(localization::set_position(name, x, y));
+        let distance = f64::hypot(3.0 - x, 4.0 - y);
+        assert!(localization::add(
+            name,
+            "00:11:22:33:44:55",
+            1000,
+            rssi(distance)
+        ));
+    }
+    // Mesure trop ancienne d'un capteur non placé, ignorée
+    assert!(localization::add("d", "00:11:22:33:44:55", 900, -30));
+    assert!(!localization::add("a", "invalide", 1000, -50));
+
+    let location = localization::locate("00:11:22:33:44:55").unwrap();
+    assert_eq!(location.sensors, 3);
+    assert_eq!(location.ts, 1000);
+    assert!(f64::hypot(location.x - 3.0, location.y - 4.0) < 1.0);
+    assert!(location.radius < 1.0);
+
+    // Observations du capteur local
+    assert!(localization::set_local("a"));
+    let fields = [
+        "6",
+        "00:11:22:33:44:66",
+        "-40",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "4",
+        "",
+        "",
+    ];
+    common::replay(&common::line(&fields));
+    let local = localization::locate("00:11:22:33:44:66").unwrap();
+    assert_eq!(
+        (local.x, local.y, local.radius, local.sensors),
+        (0.0, 0.0, 1.0, 1)
+    );
+
+    let csv = localization::to_csv(&localization::locations());
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.starts_with("mac,ts,x,y,radius,sensors\n00:11:22:33:44:55,1000,"));
+}
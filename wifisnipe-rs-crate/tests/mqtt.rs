@@ -0,0 +1,117 @@
+#![cfg(feature = "mqtt")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use libwifisnipe::mqtt::{self, MqttConfig};
+use serde_json::Value;
+
+// Paquet MQTT reçu : type, drapeaux et contenu
+fn read_packet(stream: &mut TcpStream) -> (u8, u8, Vec<u8>) {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header).unwrap();
+    // Longueur restante encodée sur 1 à 4 octets
+    let (mut length, mut shift) = (0usize, 0);
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        length |= ((byte[0] & 0x7F) as usize) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).unwrap();
+    (header[0] >> 4, header[0] & 0x0F, body)
+}
+
+#[test]
+fn mqtt_publish() {
+    // Broker minimal : accepte la connexion et lit les publications
+    let broker = TcpListener::bind("127.0.0.1:0").unwrap();
+    mqtt::start(MqttConfig {
+        port: broker.local_addr().unwrap().port(),
+        sensor: String::from("test"),
+        presence_interval: Duration::from_millis(500),
+        ..MqttConfig::default()
+    })
+    .unwrap();
+    let (mut stream, _) = broker.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let (kind, _, _) = read_packet(&mut stream);
+    assert_eq!(kind, 1, "CONNECT attendu");
+    stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+
+    let mut observations: Vec<(String, Value)> = Vec::new();
+    let mut presence: Option<(Value, bool)> = None;
+    while observations.len() < 6 || presence.is_none() {
+        let (kind, flags, body) = read_packet(&mut stream);
+        if kind != 3 {
+            continue;
+        }
+        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+        let topic = String::from_utf8(body[2..2 + topic_len].to_vec()).unwrap();
+        let payload: Value = serde_json::from_slice(&body[2 + topic_len..]).unwrap();
+        let retain = flags & 0x01 != 0;
+        if topic == "sniffer/test/presence" {
+            presence = Some((payload, retain));
+        } else {
+            assert!(!retain);
+            observations.push((topic, payload));
+        }
+    }
+
+    assert_eq!(observations[0].0, "sniffer/test/device/a4:5e:60:11:22:33");
+    assert_eq!(observations[0].1["rssi"], -52);
+    assert_eq!(observations[2].0, "sniffer/test/device/da:a1:19:00:00:01");
+    assert_eq!(observations[2].1["ssid"], "FreeWifi");
+
+    let (presence, retain) = presence.unwrap();
+    assert!(retain);
+    assert_eq!(presence["sensor"], "test");
+    assert_eq!(presence["count"], 3);
+}
+
+#[test]
+fn mqtt_erase() {
+    let broker = TcpListener::bind("127.0.0.1:0").unwrap();
+    mqtt::start(MqttConfig {
+        port: broker.local_addr().unwrap().port(),
+        sensor: String::from("erase"),
+        retain_observations: true,
+        ..MqttConfig::default()
+    })
+    .unwrap();
+    let (mut stream, _) = broker.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let (kind, _, _) = read_packet(&mut stream);
+    assert_eq!(kind, 1, "CONNECT attendu");
+    stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+
+    // Adresse inconnue : n'interfère pas avec les autres tests
+    let entry = libwifisnipe::retention::erase("02-00-00-00-00-99").unwrap();
+    assert_eq!(entry.deleted["retained_topics"], 1);
+
+    // Message retenu vide sur le topic d'observation de l'appareil
+    loop {
+        let (kind, flags, body) = read_packet(&mut stream);
+        if kind != 3 {
+            continue;
+        }
+        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+        let topic = String::from_utf8(body[2..2 + topic_len].to_vec()).unwrap();
+        if topic == "sniffer/erase/device/02:00:00:00:00:99" {
+            assert!(flags & 0x01 != 0);
+            assert!(body[2 + topic_len..].is_empty());
+            break;
+        }
+    }
+}
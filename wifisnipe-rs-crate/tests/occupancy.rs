@@ -0,0 +1,65 @@
+mod common;
+
+use common::IES;
+use libwifisnipe::occupancy::{occupancy, to_csv, Bucket, OccupancyQuery, RandomizedMode};
+
+#[test]
+fn occupancy_series() {
+    let frames = [
+        ("00:11:22:33:44:55", "-50", ""),
+        ("00:11:22:33:44:66", "-85", ""),
+        ("00:11:22:33:44:55", "-52", ""),
+        // Deux adresses aléatoires du même appareil
+        ("da:a1:19:00:00:01", "-60", IES),
+        ("6e:02:aa:00:00:02", "-62", IES),
+    ];
+    // Même tick de la carte pour toutes les trames : elles sont datées de la réception de la
+    // première (voir `clock`), donc dans le même intervalle
+    let capture: String = frames
+        .iter()
+        .enumerate()
+        .map(|(seq, (mac, rssi, ies))| {
+            let seq = (seq + 1).to_string();
+            common::line(&["6", mac, rssi, "", "1000", &seq, "", "", "4", "", ies])
+        })
+        .collect();
+    common::replay(&capture);
+    let ts = libwifisnipe::observations()[0].ts;
+    assert!(libwifisnipe::observations().iter().all(|obs| obs.ts == ts));
+
+    let count = |query: OccupancyQuery| {
+        let points = occupancy(&query);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].ts % query.bucket.seconds(), 0);
+        (points[0].devices, points[0].randomized)
+    };
+    assert_eq!(count(OccupancyQuery::default()), (4, 2));
+    let strong = OccupancyQuery {
+        min_rssi: Some(-70),
+        ..OccupancyQuery::default()
+    };
+    assert_eq!(count(strong), (3, 2));
+    let exclude = OccupancyQuery {
+        randomized: RandomizedMode::Exclude,
+        ..OccupancyQuery::default()
+    };
+    assert_eq!(count(exclude), (2, 0));
+    let group = OccupancyQuery {
+        bucket: Bucket::Hour,
+        randomized: RandomizedMode::Group,
+        ..OccupancyQuery::default()
+    };
+    assert_eq!(count(group), (3, 1));
+
+    let future = OccupancyQuery {
+        since: Some(4102444800),
+        ..OccupancyQuery::default()
+    };
+    assert!(occupancy(&future).is_empty());
+
+    let points = occupancy(&OccupancyQuery::default());
+    assert_eq!(
+        to_csv(&points),
+        format!("ts,devices,randomized\n{},4,2\n", points[0].ts)
+    );
+}
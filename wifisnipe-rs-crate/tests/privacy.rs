@@ -0,0 +1,145 @@
+mod common;
+
+use libwifisnipe::privacy::{self, PrivacyConfig, SsidPolicy};
+use libwifisnipe::watchlist::{self, AlertRoute, Matcher, WatchRule};
+use libwifisnipe::{oui, snapshot};
+
+const RAW_MACS: [&str; 3] = [
+    "a4:5e:60:11:22:33",
+    "da:a1:19:00:00:01",
+    "5c:cf:7f:aa:bb:cc",
+];
+
+#[test]
+fn privacy_pseudonyms() {
+    let config = PrivacyConfig {
+        key: b"cle".to_vec(),
+        ..PrivacyConfig::default()
+    };
+    // 2023-11-14 et 2023-11-15
+    let (day1, day2) = (1700000000, 1700000000 + 86400);
+    let pseudonym = config.mac("A4-5E-60-11-22-33", day1);
+    assert_eq!(pseudonym, config.mac("a4:5e:60:11:22:33", day2));
+    assert_ne!(pseudonym, "a4:5e:60:11:22:33");
+    assert!(!oui::is_randomized(&pseudonym));
+    assert!(oui::is_randomized(&config.mac("da:a1:19:00:00:01", day1)));
+    assert_eq!(config.mac("ff:ff:ff:ff:ff:ff", day1), "ff:ff:ff:ff:ff:ff");
+    assert_eq!(config.mac("", day1), "");
+
+    let daily = PrivacyConfig {
+        daily_salt: true,
+        ..config.clone()
+    };
+    assert_ne!(daily.mac("a4:5e:60:11:22:33", day1), pseudonym);
+    assert_ne!(
+        daily.mac("a4:5e:60:11:22:33", day1),
+        daily.mac("a4:5e:60:11:22:33", day2)
+    );
+    let other_key = PrivacyConfig {
+        key: b"autre".to_vec(),
+        ..config.clone()
+    };
+    assert_ne!(other_key.mac("a4:5e:60:11:22:33", day1), pseudonym);
+
+    let hashed = config.ssid("MaBox", day1);
+    assert!(hashed.starts_with('#') && hashed.len() == 13, "{hashed}");
+    assert_eq!(config.ssid("", day1), "");
+    let truncated = PrivacyConfig {
+        ssid: SsidPolicy::Truncate(4),
+        ..config.clone()
+    };
+    assert_eq!(truncated.ssid("FreeWifi", day1), "Free");
+    let dropped = PrivacyConfig {
+        ssid: SsidPolicy::Drop,
+        ..config
+    };
+    assert_eq!(dropped.ssid("FreeWifi", day1), "");
+}
+
+#[test]
+fn privacy_mode() {
+    assert!(privacy::enable(PrivacyConfig {
+        key: b"cle".to_vec(),
+        ssid: SsidPolicy::Truncate(4),
+        ..PrivacyConfig::default()
+    }));
+    watchlist::add_rule(WatchRule {
+        name: String::from("direction"),
+        matcher: Matcher::new("mac", "da:a1:19:00:00:01").unwrap(),
+    });
+    watchlist::add_rule(WatchRule {
+        name: String::from("apple"),
+        matcher: Matcher::new("oui", "a4:5e:60").unwrap(),
+    });
+    // Règles sans nom : le motif brut ne doit apparaître dans aucune alerte
+    for rule in watchlist::parse("mac DA:A1:19:00:00:01\nssid MaBox").unwrap() {
+        watchlist::add_rule(rule);
+    }
+    let alert_log = common::temp_file("alerts.jsonl");
+    watchlist::add_route(AlertRoute::Log(alert_log.clone()));
+    #[cfg(feature = "webhook")]
+    let outbox = common::temp_file("outbox.jsonl");
+    // Aucun serveur sur le port : les notifications restent dans la boîte d'envoi
+    #[cfg(feature = "webhook")]
+    libwifisnipe::webhook::start(libwifisnipe::webhook::WebhookConfig {
+        urls: vec![String::from("http://127.0.0.1:9/")],
+        outbox: Some(outbox.clone()),
+        sensor_status: false,
+        summary_interval: None,
+        ..Default::default()
+    })
+    .unwrap();
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+    // Trop tard : des trames ont été enregistrées
+    assert!(!privacy::disable());
+
+    let json = serde_json::to_string(&snapshot()).unwrap();
+    let history = serde_json::to_string(&libwifisnipe::observations()).unwrap();
+    let alerts = serde_json::to_string(&watchlist::alerts(0)).unwrap();
+    for raw in RAW_MACS {
+        assert!(!json.contains(raw), "{raw} dans {json}");
+        assert!(!history.contains(raw), "{raw} dans {history}");
+        assert!(!alerts.contains(raw), "{raw} dans {alerts}");
+    }
+    for ssid in ["MaBox", "FreeWifi", "Bureau"] {
+        assert!(!json.contains(ssid), "{ssid} dans {json}");
+    }
+
+    let devices = snapshot();
+    assert_eq!(devices.len(), RAW_MACS.len());
+    let ts = devices[0].ts;
+    let device = devices
+        .iter()
+        .find(|data| data.mac == privacy::mac("a4:5e:60:11:22:33", ts))
+        .unwrap();
+    let mut ssids = device.ssids.clone();
+    ssids.sort();
+    assert_eq!(ssids, ["Bure", "MaBo"]);
+    assert_eq!(oui::vendor(&device.mac), oui::UNKNOWN);
+
+    // Seules les règles mac et ssid correspondent, avec les pseudonymes dans les alertes
+    let alerts = watchlist::alerts(0);
+    let rules: Vec<&str> = alerts.iter().map(|alert| alert.rule.as_str()).collect();
+    let pseudonym = privacy::mac("da:a1:19:00:00:01", ts);
+    assert_eq!(rules, ["MaBo", "direction", pseudonym.as_str(), "MaBo"]);
+    assert_eq!(alerts[1].mac, pseudonym);
+    assert_eq!(alerts[1].pattern, alerts[1].mac);
+
+    let text = std::fs::read_to_string(&alert_log).unwrap();
+    assert_eq!(text.matches("\"rule\"").count(), 4, "{text}");
+    assert_no_raw_pattern(&text);
+    // La boîte d'envoi est réécrite à chaque nouvelle tentative
+    #[cfg(feature = "webhook")]
+    {
+        let text = std::fs::read_to_string(&outbox).unwrap();
+        assert!(text.contains("\"rule\""), "{text}");
+        assert_no_raw_pattern(&text);
+    }
+}
+
+/// Vérifie qu'un export ne contient aucun motif brut des règles sans nom
+fn assert_no_raw_pattern(text: &str) {
+    for raw in ["da:a1:19:00:00:01", "DA:A1:19:00:00:01", "MaBox"] {
+        assert!(!text.contains(raw), "{raw} dans {text}");
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use libwifisnipe::retention::{self, AuditAction};
+use libwifisnipe::watchlist::{self, AlertRoute, Matcher, WatchRule};
+use libwifisnipe::{observations, sessions, snapshot, ssids};
+
+const ERASED: &str = "da:a1:19:00:00:01";
+
+#[test]
+fn retention_erase() {
+    let alert_log = common::temp_file("alerts.jsonl");
+    let audit_log = common::temp_file("audit.jsonl");
+    watchlist::add_rule(WatchRule {
+        name: String::from("direction"),
+        matcher: Matcher::new("mac", ERASED).unwrap(),
+    });
+    watchlist::add_rule(WatchRule {
+        name: String::from("apple"),
+        matcher: Matcher::new("oui", "a4:5e:60").unwrap(),
+    });
+    watchlist::add_route(AlertRoute::Log(alert_log.clone()));
+    retention::set_audit_log(audit_log.clone()).unwrap();
+    retention::set_audit_key(b"secret d'audit");
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+
+    let devices = snapshot().len();
+    let history = observations()
+        .iter()
+        .filter(|obs| obs.mac == ERASED)
+        .count();
+    assert!(history > 0);
+    assert_eq!(watchlist::alerts(0).len(), 2);
+
+    // Adresse invalide : rien n'est effacé ni consigné
+    assert!(retention::erase("pas une adresse").is_none());
+    assert!(retention::audit_log(0).is_empty());
+
+    let entry = retention::erase(&ERASED.to_uppercase()).unwrap();
+    assert_eq!(entry.action, AuditAction::Erase);
+    let subject = entry.subject.clone().unwrap();
+    assert!(subject.starts_with("hmac-sha256:"), "{subject}");
+    assert!(!subject.contains(ERASED));
+    // Empreinte à clé : différente du SHA-256 de l'adresse seule
+    let unkeyed: String = hmac_sha256::Hash::hash(ERASED.as_bytes())[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    assert!(!subject.contains(&unkeyed));
+    assert_eq!(entry.deleted["devices"], 1);
+    assert_eq!(entry.deleted["observations"], history);
+    assert_eq!(entry.deleted["alerts"], 1);
+    assert_eq!(entry.deleted["alert_log_lines"], 1);
+    assert_eq!(entry.deleted["ssid_links"], 2);
+
+    // Plus aucune trace de l'appareil
+    assert_eq!(snapshot().len(), devices - 1);
+    assert!(snapshot().iter().all(|data| data.mac != ERASED));
+    assert!(observations().iter().all(|obs| obs.mac != ERASED));
+    assert!(sessions::sessions(Some(ERASED), 0, i64::MAX).is_empty());
+    assert!(watchlist::alerts(0).iter().all(|alert| alert.mac != ERASED));
+    assert!(ssids::links(1).iter().all(|link| link.mac != ERASED));
+    let logged = std::fs::read_to_string(&alert_log).unwrap();
+    assert!(!logged.contains(ERASED), "{logged}");
+    assert_eq!(logged.lines().count(), 1);
+
+    // Journal d'audit en mémoire et dans le fichier, sans l'adresse
+    let audit = std::fs::read_to_string(&audit_log).unwrap();
+    assert!(!audit.contains(ERASED), "{audit}");
+    assert_eq!(audit.lines().count(), 1);
+    assert_eq!(retention::audit_log(0).len(), 1);
+
+    // Un second effacement ne supprime rien mais est consigné, sans empreinte faute de clé
+    retention::set_audit_key(b"");
+    let entry = retention::erase(ERASED).unwrap();
+    assert_eq!(entry.total(), 0);
+    assert!(entry.subject.is_none());
+    assert_eq!(retention::audit_log(0).len(), 2);
+
+    // Conservation : tout est plus ancien que la date limite
+    let entry = retention::purge_before(i64::MAX);
+    assert_eq!(entry.action, AuditAction::Retention);
+    assert_eq!(entry.deleted["devices"], devices - 1);
+    assert!(snapshot().is_empty());
+    assert!(observations().is_empty());
+    assert!(watchlist::alerts(0).is_empty());
+    assert_eq!(retention::audit_log(0).len(), 3);
+
+    // Rien à effacer : pas d'entrée d'audit
+    assert_eq!(retention::purge_before(i64::MAX).total(), 0);
+    assert_eq!(retention::audit_log(0).len(), 3);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use libwifisnipe::rssi::{self, PathLoss};
+
+// Relit des trames d'une adresse MAC avec les RSSI donnés
+fn replay(mac: &str, rssis: &[&str]) {
+    let capture: String = rssis
+        .iter()
+        .map(|rssi| common::line(&["6", mac, rssi, "", "", "", "", "", "4", "", ""]))
+        .collect();
+    common::replay(&capture);
+}
+
+#[test]
+fn rssi_smoothing() {
+    // Moyenne mobile exponentielle par défaut (alpha = 0.3)
+    replay("00:11:22:33:44:55", &["-50", "-70", "-50", "-70"]);
+    let data = libwifisnipe::device("00:11:22:33:44:55").unwrap();
+    assert_eq!(data.rssi, -70);
+    assert_eq!(data.rssi_smoothed, -58.9);
+    assert!((data.distance - 5.0).abs() < 0.1);
+
+    // Le filtre de Kalman converge vers la moyenne
+    assert!(!rssi::set_rssi_kalman(1.0, 0.0));
+    assert!(rssi::set_rssi_kalman(0.1, 25.0));
+    let rssis: Vec<&str> = ["-55", "-65"].iter().cycle().take(40).copied().collect();
+    replay("00:11:22:33:44:66", &rssis);
+    let data = libwifisnipe::device("00:11:22:33:44:66").unwrap();
+    assert!((data.rssi_smoothed + 60.0).abs() < 2.0);
+
+    // Calibration sur des mesures à 1, 2, 4 et 8 mètres
+    let model = PathLoss {
+        reference: -42.0,
+        exponent: 2.5,
+    };
+    let samples: Vec<(f64, f64)> = [1.0, 2.0, 4.0, 8.0]
+        .iter()
+        .map(|d| (*d, model.reference - 25.0 * f64::log10(*d)))
+        .collect();
+    let calibrated = PathLoss::calibrate(&samples).unwrap();
+    assert!((calibrated.reference - model.reference).abs() < 1e-9);
+    assert!((calibrated.exponent - model.exponent).abs() < 1e-9);
+    assert!(PathLoss::calibrate(&samples[..1]).is_none());
+
+    assert!(rssi::set_distance_model(-42.0, 2.5));
+    assert!(!rssi::set_distance_model(-42.0, 0.0));
+    assert!(!rssi::set_distance_model(-42.0, f64::NAN));
+    assert!(!rssi::set_distance_model(-42.0, f64::INFINITY));
+    assert!(!rssi::set_distance_model(f64::NAN, 2.5));
+    assert!((rssi::distance(-67.0) - 10.0).abs() < 1e-9);
+}
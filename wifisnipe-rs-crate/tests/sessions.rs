@@ -0,0 +1,81 @@
+mod common;
+
+use libwifisnipe::events::{subscribe, Event};
+use libwifisnipe::sessions::{self, set_absence_timeout};
+
+#[test]
+fn presence_sessions() {
+    let mut events = subscribe();
+    let frames = [
+        ("da:a1:19:00:00:01", "-70"),
+        ("da:a1:19:00:00:01", "-52"),
+        ("6e:02:aa:00:00:02", "-80"),
+        ("da:a1:19:00:00:01", "-60"),
+    ];
+    let capture: String = frames
+        .iter()
+        .map(|(mac, rssi)| common::line(&["6", mac, rssi, "", "", "", "", "", "4", "", ""]))
+        .collect();
+    common::replay(&capture);
+
+    // Une arrivée par appareil, avant son observation
+    let mut arrivals = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let Event::Arrive(session) = event {
+            arrivals.push(session.mac);
+        }
+    }
+    assert_eq!(arrivals, ["da:a1:19:00:00:01", "6e:02:aa:00:00:02"]);
+
+    let found = sessions::sessions(Some("DA:A1:19:00:00:01"), 0, i64::MAX);
+    assert_eq!(found.len(), 1);
+    assert!(found[0].open);
+    assert_eq!(found[0].frames, 3);
+    assert_eq!(found[0].peak_rssi, -52);
+    // Fenêtre antérieure aux observations
+    assert!(sessions::sessions(None, 0, 1).is_empty());
+
+    // Les deux appareils partent après le délai d'absence
+    assert!(!set_absence_timeout(0));
+    assert!(set_absence_timeout(1));
+    // Deux secondes après la dernière observation
+    sessions::expire(libwifisnipe::observations().last().unwrap().ts + 2);
+    let found = sessions::sessions(None, 0, i64::MAX);
+    assert_eq!(found.len(), 2);
+    assert!(found.iter().all(|session| !session.open));
+    let leaves = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| matches!(event, Event::Leave(_)))
+        .count();
+    assert_eq!(leaves, 2);
+
+    let stats = sessions::dwell_stats(None, 0, i64::MAX);
+    assert_eq!(stats.sessions, 2);
+    assert_eq!(stats.devices, 2);
+    assert_eq!(stats.total, found.iter().map(|s| s.duration).sum::<i64>());
+
+    // Sans autre trame, le thread d'expiration termine la session d'un nouvel appareil
+    common::replay(&common::line(&[
+        "6",
+        "6e:02:aa:00:00:03",
+        "-75",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "4",
+        "",
+        "",
+    ]));
+    let mut left = false;
+    for _ in 0..50 {
+        left = std::iter::from_fn(|| events.try_recv().ok()).any(
+            |event| matches!(event, Event::Leave(session) if session.mac == "6e:02:aa:00:00:03"),
+        );
+        if left {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(left);
+}
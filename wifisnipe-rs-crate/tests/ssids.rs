@@ -0,0 +1,73 @@
+use libwifisnipe::ssids::{self, SsidLink};
+
+const APPLE: &str = "a4:5e:60:11:22:33";
+const RANDOM: &str = "da:a1:19:00:00:01";
+
+#[test]
+fn ssids_index() {
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+
+    // MaBox est recherché par deux appareils
+    let all = ssids::ssids();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].ssid, "MaBox");
+    assert_eq!(all[0].devices, 2);
+    assert_eq!(all[0].frames, 2);
+    assert!(all[0].first_seen <= all[0].last_seen);
+    assert!(all[1..].iter().all(|stats| stats.devices == 1));
+
+    assert_eq!(ssids::links(1).len(), 4);
+    let shared = ssids::links(2);
+    assert_eq!(shared.len(), 2);
+    assert_eq!(shared[0].mac, APPLE);
+    assert_eq!(shared[1].mac, RANDOM);
+    assert_eq!(
+        ssids::shared_with(APPLE).get(RANDOM),
+        Some(&vec![String::from("MaBox")])
+    );
+    assert!(ssids::shared_with("5c:cf:7f:aa:bb:cc").is_empty());
+
+    let dot = ssids::to_dot(&shared);
+    assert!(dot.starts_with("graph ssids {"));
+    assert!(dot.contains(&format!("\"mac:{APPLE}\" -- \"ssid:MaBox\"")));
+    assert!(dot.contains("\"ssid:MaBox\" [shape=box, label=\"MaBox\", devices=2]"));
+    assert!(!dot.contains("Bureau"));
+
+    let graphml = ssids::to_graphml(&ssids::links(1));
+    assert_eq!(graphml.matches("<node ").count(), 2 + 3);
+    assert_eq!(graphml.matches("<edge ").count(), 4);
+}
+
+#[test]
+fn ssids_escape() {
+    let links = [SsidLink {
+        mac: String::from(APPLE),
+        ssid: String::from("Café \"<&>\""),
+        first_seen: 1700000000,
+        last_seen: 1700000060,
+        frames: 3,
+    }];
+    let dot = ssids::to_dot(&links);
+    assert!(dot.contains("\"ssid:Café \\\"<&>\\\"\""), "{dot}");
+    assert!(dot.contains("weight=3"));
+    let graphml = ssids::to_graphml(&links);
+    assert!(
+        graphml.contains("id=\"ssid:Café &quot;&lt;&amp;&gt;&quot;\""),
+        "{graphml}"
+    );
+    assert!(graphml.contains("<data key=\"vendor\">Apple</data>"));
+
+    // Caractères de contrôle : échappés en DOT, remplacés ou encodés en GraphML
+    let links = [SsidLink {
+        ssid: String::from("a\u{1}b\tc\n"),
+        ..links[0].clone()
+    }];
+    let dot = ssids::to_dot(&links);
+    assert!(dot.contains("\"ssid:a\\x01b\\tc\\n\""), "{dot}");
+    let graphml = ssids::to_graphml(&links);
+    assert!(
+        graphml.contains("id=\"ssid:a\u{FFFD}b&#9;c&#10;\""),
+        "{graphml}"
+    );
+    assert!(!graphml.contains('\u{1}'));
+}
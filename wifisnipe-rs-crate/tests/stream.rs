@@ -0,0 +1,67 @@
+#![cfg(feature = "http")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use serde_json::Value;
+
+#[test]
+fn stream_replay() {
+    let addr = libwifisnipe::http::serve("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        stream,
+        "GET /stream?min_rssi=-60 HTTP/1.1\r\nHost: {addr}\r\n\r\n"
+    )
+    .unwrap();
+    let mut reader = BufReader::new(stream);
+    // Attendre les en-têtes : le client est alors abonné
+    let mut line = String::new();
+    while line != "\r\n" {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+    }
+
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+
+    let mut events: Vec<Value> = Vec::new();
+    while events.len() < 6 {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        if let Some(data) = line.strip_prefix("data: ") {
+            events.push(serde_json::from_str(data.trim_end()).unwrap());
+        }
+    }
+    let summary: Vec<(&str, i64)> = events
+        .iter()
+        .map(|event| {
+            (
+                event["type"].as_str().unwrap(),
+                // Les sessions portent le RSSI maximal, les zones le RSSI lissé
+                event
+                    .get("rssi")
+                    .unwrap_or(&event["peak_rssi"])
+                    .as_f64()
+                    .unwrap() as i64,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("new_device", -52),
+            ("arrive", -52),
+            ("zone", -52),
+            ("observation", -52),
+            ("observation", -55),
+            ("observation", -49)
+        ]
+    );
+    assert!(events
+        .iter()
+        .all(|event| event["mac"] == "a4:5e:60:11:22:33"));
+}
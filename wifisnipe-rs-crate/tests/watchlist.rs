@@ -0,0 +1,95 @@
+mod common;
+
+use libwifisnipe::events::{subscribe, Event};
+use libwifisnipe::watchlist::{self, AlertRoute};
+use std::sync::{Arc, Mutex};
+
+const WATCHLIST: &str = "# Règles de test
+mac    DA:A1:19:00:00:01  direction
+oui    a4:5e:60           apple
+
+ssid   MaBox
+regex  ^Free
+";
+
+#[test]
+fn watchlist_parse() {
+    let rules = watchlist::parse(WATCHLIST).unwrap();
+    let names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+    assert_eq!(names, ["direction", "apple", "MaBox", "^Free"]);
+    assert_eq!(rules[1].matcher.kind(), "prefix");
+    assert_eq!(rules[0].matcher.pattern(), "da:a1:19:00:00:01");
+    // Adresses normalisées : minuscules et séparateur `:`
+    let rules = watchlist::parse("oui A4-5E-60 apple\nmac DA-A1-19-00-00-01").unwrap();
+    assert_eq!(rules[0].matcher.pattern(), "a4:5e:60");
+    assert_eq!(rules[1].matcher.pattern(), "da:a1:19:00:00:01");
+
+    let err = watchlist::parse("ssid MaBox\n\nbssid 00:11:22").unwrap_err();
+    assert!(err.starts_with("ligne 3 :"), "{err}");
+    let err = watchlist::parse("regex (").unwrap_err();
+    assert!(err.starts_with("ligne 1 :"), "{err}");
+    assert!(watchlist::parse("mac").is_err());
+}
+
+#[test]
+fn watchlist_alerts() {
+    let path = common::temp_file("watchlist.txt");
+    std::fs::write(&path, WATCHLIST).unwrap();
+    assert_eq!(watchlist::load(path.to_str().unwrap()).unwrap(), 4);
+    let log = common::temp_file("alerts.jsonl");
+    watchlist::add_route(AlertRoute::Log(log.clone()));
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let visible = Arc::new(Mutex::new(Vec::new()));
+    let tables = visible.clone();
+    watchlist::add_route(AlertRoute::Callback(Arc::new(move |alert| {
+        sink.lock().unwrap().push(alert.rule.clone());
+        // Les tables sont relisibles depuis une route d'alerte, appareil déjà enregistré
+        let known = libwifisnipe::snapshot()
+            .iter()
+            .any(|data| data.mac == alert.mac);
+        let recorded = libwifisnipe::observations()
+            .iter()
+            .any(|obs| obs.mac == alert.mac);
+        tables.lock().unwrap().push(known && recorded);
+    })));
+
+    let mut events = subscribe();
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+
+    // Une alerte par règle et par appareil pendant le délai de répétition
+    let expected = ["apple", "MaBox", "direction", "^Free", "MaBox"];
+    let published: Vec<(String, String)> = std::iter::from_fn(|| events.try_recv().ok())
+        .filter_map(|event| match event {
+            Event::Alert(alert) => Some((alert.rule, alert.mac)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        published,
+        [
+            ("apple", "a4:5e:60:11:22:33"),
+            ("MaBox", "a4:5e:60:11:22:33"),
+            ("direction", "da:a1:19:00:00:01"),
+            ("^Free", "da:a1:19:00:00:01"),
+            ("MaBox", "da:a1:19:00:00:01"),
+        ]
+        .map(|(rule, mac)| (rule.to_owned(), mac.to_owned()))
+    );
+    assert_eq!(*received.lock().unwrap(), expected);
+    assert_eq!(*visible.lock().unwrap(), [true; 5]);
+    let logged = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(logged.lines().count(), expected.len());
+    let first: serde_json::Value = serde_json::from_str(logged.lines().next().unwrap()).unwrap();
+    assert_eq!(first["rule"], "apple");
+    assert_eq!(first["kind"], "prefix");
+    assert_eq!(watchlist::alerts(0).len(), expected.len());
+    assert!(watchlist::alerts(i64::MAX).is_empty());
+
+    assert!(watchlist::remove_rule("MaBox"));
+    assert!(!watchlist::remove_rule("MaBox"));
+    assert_eq!(watchlist::rules().len(), 3);
+    watchlist::clear_rules();
+    watchlist::clear_routes();
+    assert!(watchlist::rules().is_empty());
+}
@@ -0,0 +1,189 @@
+#![cfg(feature = "webhook")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libwifisnipe::sensor::SensorStatus;
+use libwifisnipe::watchlist::{self, Matcher, WatchRule};
+use libwifisnipe::webhook::{self, Payload, Summary, WebhookConfig};
+use serde_json::Value;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Serveur HTTP minimal répondant les statuts donnés, puis 200
+fn stub(statuses: Vec<u16>) -> (String, Receiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut statuses = statuses.into_iter();
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let status = statuses.next().unwrap_or(200);
+            write!(
+                stream,
+                "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+            let _ = tx.send(serde_json::from_slice(&body).unwrap());
+        }
+    });
+    (url, rx)
+}
+
+fn wait_empty_outbox() {
+    let start = Instant::now();
+    while webhook::pending() > 0 {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "notifications toujours en attente"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn webhook_backoff() {
+    let config = WebhookConfig {
+        retry_delay: Duration::from_secs(2),
+        max_retry_delay: Duration::from_secs(30),
+        ..WebhookConfig::default()
+    };
+    assert_eq!(config.backoff(1), Duration::from_secs(2));
+    assert_eq!(config.backoff(3), Duration::from_secs(8));
+    assert_eq!(config.backoff(5), Duration::from_secs(30));
+    assert_eq!(config.backoff(100), Duration::from_secs(30));
+}
+
+#[test]
+fn webhook_offline_reason() {
+    // Carte muette depuis le démarrage de la surveillance
+    assert_eq!(webhook::offline_reason(None, None, 10), None);
+    assert_eq!(
+        webhook::offline_reason(None, None, 31).as_deref(),
+        Some("aucun état reçu en 31 s")
+    );
+    let status = SensorStatus {
+        ts: chrono::Utc::now().timestamp() - 60,
+        uptime: 3600,
+        channel: 6,
+        heap: 24576,
+        frames: 1200,
+        version: String::from("1.7"),
+    };
+    assert!(webhook::offline_reason(None, Some(&status), 0)
+        .unwrap()
+        .starts_with("aucun état depuis 6"));
+    let fresh = SensorStatus {
+        ts: chrono::Utc::now().timestamp(),
+        ..status
+    };
+    assert_eq!(webhook::offline_reason(None, Some(&fresh), 3600), None);
+    assert_eq!(
+        webhook::offline_reason(Some(String::from("port fermé")), Some(&fresh), 0).as_deref(),
+        Some("port fermé")
+    );
+}
+
+#[test]
+fn webhook_delivery() {
+    // Restaurée, échec puis succès, alerte, refusée
+    let (url, received) = stub(vec![200, 500, 200, 200, 404]);
+    let outbox = common::temp_file("outbox.jsonl");
+    let restored = serde_json::json!({
+        "url": url,
+        "attempts": 3,
+        "next_attempt": 0,
+        "notification": {
+            "id": 7,
+            "sensor": "salle1",
+            "ts": 1700000000,
+            "type": "sensor_online",
+            "data": { "offline_since": 1699999000 }
+        }
+    });
+    // Notification 8 : ajoutée puis envoyée, non reprise
+    let mut delivered = restored.clone();
+    delivered["notification"]["id"] = 8.into();
+    let done = serde_json::json!({ "done": 8, "url": url });
+    std::fs::write(
+        &outbox,
+        format!("{delivered}\n{restored}\n{done}\n{{\"url\": \"interrompue\n"),
+    )
+    .unwrap();
+
+    webhook::start(WebhookConfig {
+        urls: vec![url.clone()],
+        sensor: String::from("salle1"),
+        outbox: Some(outbox.clone()),
+        sensor_status: false,
+        summary_interval: None,
+        retry_delay: Duration::from_millis(50),
+        ..WebhookConfig::default()
+    })
+    .unwrap();
+    assert!(webhook::start(WebhookConfig {
+        urls: vec![url],
+        ..WebhookConfig::default()
+    })
+    .is_err());
+
+    // La notification de la boîte d'envoi est reprise au démarrage
+    let notification = received.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(notification["id"], 7);
+    assert_eq!(notification["type"], "sensor_online");
+
+    // Nouvelle tentative après un échec, avec le même identifiant
+    webhook::notify(Payload::SensorOffline {
+        reason: String::from("test"),
+        last_status: None,
+    });
+    let first = received.recv_timeout(TIMEOUT).unwrap();
+    let second = received.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first["type"], "sensor_offline");
+    assert_eq!(first["sensor"], "salle1");
+    assert_eq!(first["data"]["reason"], "test");
+    wait_empty_outbox();
+
+    // Alertes de la liste de surveillance
+    watchlist::add_rule(WatchRule {
+        name: String::from("direction"),
+        matcher: Matcher::new("mac", "da:a1:19:00:00:01").unwrap(),
+    });
+    libwifisnipe::replay("tests/data/capture.txt").unwrap();
+    let alert = received.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(alert["type"], "alert");
+    assert_eq!(alert["data"]["rule"], "direction");
+    assert_eq!(alert["data"]["mac"], "da:a1:19:00:00:01");
+
+    // Une notification refusée (4xx) n'est pas renvoyée
+    webhook::notify(Payload::Summary(Summary::between(0, i64::MAX)));
+    let summary = received.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["data"]["alerts"], 1);
+    wait_empty_outbox();
+    assert!(received.recv_timeout(Duration::from_millis(200)).is_err());
+    assert_eq!(std::fs::read_to_string(&outbox).unwrap(), "");
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::ffi::CString;
+
+use interoptopus::patterns::string::AsciiPointer;
+use libwifisnipe::events::{subscribe, Event};
+use libwifisnipe::zones::{self, Zone, ZoneConfig};
+
+#[test]
+fn zone_hysteresis() {
+    let config = ZoneConfig::default();
+    assert_eq!(config.next(None, -56.0), Zone::Medium);
+    // Moins de 3 dB au-delà du seuil : pas de changement
+    assert_eq!(config.next(Some(Zone::Medium), -53.0), Zone::Medium);
+    assert_eq!(config.next(Some(Zone::Medium), -52.0), Zone::Near);
+    assert_eq!(config.next(Some(Zone::Near), -57.0), Zone::Near);
+    assert_eq!(config.next(Some(Zone::Near), -90.0), Zone::Far);
+    assert!(!zones::set_config(ZoneConfig {
+        near: -70,
+        medium: -55,
+        hysteresis: 3
+    }));
+}
+
+#[test]
+fn zone_changes() {
+    // Sans lissage, pour suivre le RSSI brut
+    assert!(libwifisnipe::rssi::set_rssi_ema(1.0));
+    let mut events = subscribe();
+    let capture: String = ["-50", "-57", "-60", "-54", "-51", "-90"]
+        .iter()
+        .map(|rssi| {
+            common::line(&[
+                "6",
+                "00:11:22:33:44:55",
+                rssi,
+                "",
+                "",
+                "",
+                "",
+                "",
+                "4",
+                "",
+                "",
+            ])
+        })
+        .collect();
+    common::replay(&capture);
+
+    let published: Vec<(Option<Zone>, Zone)> = std::iter::from_fn(|| events.try_recv().ok())
+        .filter_map(|event| match event {
+            Event::Zone(change) => Some((change.from, change.to)),
+            _ => None,
+        })
+        .collect();
+    let expected = [
+        (None, Zone::Near),
+        (Some(Zone::Near), Zone::Medium),
+        (Some(Zone::Medium), Zone::Near),
+        (Some(Zone::Near), Zone::Far),
+    ];
+    assert_eq!(published, expected);
+    let kept: Vec<(Option<Zone>, Zone)> = zones::changes(0)
+        .into_iter()
+        .map(|change| (change.from, change.to))
+        .collect();
+    assert_eq!(kept, expected);
+    let local = libwifisnipe::localization::local_sensor();
+    assert_eq!(
+        zones::zone_of(&local, "00:11:22:33:44:55").unwrap().zone,
+        Zone::Far
+    );
+    assert!(zones::changes(i64::MAX).is_empty());
+
+    // Les mesures d'un autre capteur ont leur propre zone
+    assert!(libwifisnipe::localization::add(
+        "salle2",
+        "00:11:22:33:44:55",
+        1000,
+        -50
+    ));
+    match events.try_recv() {
+        Ok(Event::Zone(change)) => {
+            assert_eq!(change.sensor, "salle2");
+            assert_eq!((change.from, change.to), (None, Zone::Near));
+        }
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(
+        zones::zone_of("salle2", "00:11:22:33:44:55").unwrap().zone,
+        Zone::Near
+    );
+    assert_eq!(
+        zones::zone_of(&local, "00:11:22:33:44:55").unwrap().zone,
+        Zone::Far
+    );
+    let remote = zones::zones(Some("salle2"));
+    assert_eq!(remote.len(), 1);
+    assert_eq!(remote[0].sensor, "salle2");
+    assert_eq!(zones::zones(None).len(), 2);
+
+    // La chaîne retournée par la FFI reste valide après l'appel
+    let sensor = CString::new("salle2").unwrap();
+    let json = zones::get_zones(AsciiPointer::from_cstr(&sensor));
+    let remote: Vec<serde_json::Value> = serde_json::from_str(json.as_str().unwrap()).unwrap();
+    assert_eq!(remote.len(), 1);
+    assert_eq!(remote[0]["sensor"], "salle2");
+}
@@ -1,249 +1,422 @@
-#[macro_use]
-extern crate lazy_static;
-extern crate futures;
+mod tui;
 
-use chrono::{DateTime, Local};
-use std::collections::HashMap;
+use chrono::{DateTime, Local, TimeZone};
 use std::io;
-use std::mem::MaybeUninit;
-use std::str;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::thread;
-use std::time::SystemTime;
+use std::time::Duration;
 
-use bytes::BytesMut;
-use futures::stream::StreamExt;
-use regex::Regex;
-use ringbuf::{Consumer, HeapRb, SharedRb};
-use tokio_serial::SerialPortBuilderExt;
-use tokio_util::codec::{Decoder, Encoder};
-
-lazy_static! {
-    // Listes des adresses MAC
-    static ref MACS: Arc<Mutex<Vec<String>>> = {
-        let macs: Vec<String> = Vec::new();
-        Arc::new(Mutex::new(macs))
-    };
-    // HashMap avec les canaux ou certaines MAC sont visibles
-    static ref CHANNELS: Arc<Mutex<HashMap<String, Vec<u8>>>> = {
-        let cha = HashMap::new();
-        Arc::new(Mutex::new(cha))
-    };
-    // HashMap avec les SSIDs récupérés
-    static ref SSIDS: Arc<Mutex<HashMap<String, Vec<String>>>> = {
-        let s = HashMap::new();
-        Arc::new(Mutex::new(s))
-    };
-    // HashMap avec le dernier RSSI
-    static ref RSSIS: Arc<Mutex<HashMap<String, i16>>> = {
-        let r = HashMap::new();
-        Arc::new(Mutex::new(r))
-    };
-    // HashMap avec les timestamp ou les addresses MAC sont vues en dernier
-    static ref LAST_SEEN: Arc<Mutex<HashMap<String, SystemTime>>> = {
-        let ts = HashMap::new();
-        Arc::new(Mutex::new(ts))
-    };
-    // Timestamp du dernier print (ici statique)
-    static ref LAST_PRINT: SystemTime = SystemTime::now();
-    // Nombre de print effectués
-    static ref PRINT_COUNT: AtomicU64 = AtomicU64::new(1);
-}
+use libwifisnipe::command::CaptureMode;
+#[cfg(feature = "mqtt")]
+use libwifisnipe::config::MqttSection;
+#[cfg(feature = "webhook")]
+use libwifisnipe::config::WebhookSection;
+use libwifisnipe::config::{self, Config, DisplaySection, PrivacySection, SsidSetting};
+use libwifisnipe::watchlist::{self, AlertRoute};
+use libwifisnipe::{ap, clock, sensor, serial_error, snapshot, start_replay, start_tty};
 
+// Port série par défaut
 #[cfg(windows)]
 const DEFAULT_TTY: &str = "COM3";
+#[cfg(not(windows))]
+const DEFAULT_TTY: &str = "/dev/ttyUSB0";
 
-// Structure utilisée pour scinder les informations reçues par lignes
-struct LineCodec;
+// Adresse d'écoute par défaut de l'API HTTP
+#[cfg(feature = "http")]
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
 
-impl Decoder for LineCodec {
-    type Item = String;
-    type Error = io::Error;
+// Options de la ligne de commande, avec leur disponibilité selon les features compilées
+const OPTIONS: &[(bool, &str)] = &[
+    (true, "  --config <fichier>    Fichier de configuration TOML, les autres options le complètent"),
+    (true, "  --replay <fichier>    Relit une capture au lieu du port série"),
+    (true, "  --capture <mode>      Trames capturées : probe, ap ou all (défaut : all en mode aps)"),
+    (cfg!(feature = "http"), "  --listen <adresse>    Adresse de l'API HTTP (défaut : 127.0.0.1:8080)"),
+    (cfg!(feature = "http"), "  --metrics <adresse>   Exporte les métriques Prometheus sur <adresse>/metrics"),
+    (true, "  --sensor <nom>        Nom du capteur (défaut : sensor)"),
+    (true, "  --position <x,y>      Position du capteur en mètres, pour la localisation"),
+    (true, "  --adaptive-hop <s>    Pondère le parcours des canaux par l'activité, recalculé toutes les <s> secondes"),
+    (true, "  --privacy             Pseudonymise les adresses MAC et les SSIDs (clé aléatoire)"),
+    (true, "  --privacy-key <clé>   Clé de pseudonymisation, stable d'un lancement à l'autre"),
+    (true, "  --daily-salt          Renouvelle les pseudonymes chaque jour"),
+    (true, "  --ssid <mode>         SSIDs en mode confidentialité : keep, hash, drop ou nombre de caractères"),
+    (true, "  --retention <durée>   Efface les données plus anciennes que <durée> secondes"),
+    (true, "  --audit-log <fichier> Consigne les effacements en JSON à la fin d'un fichier"),
+    (true, "  --audit-key <clé>     Clé des empreintes d'adresses du journal d'audit"),
+    (true, "  --watchlist <fichier> Règles d'alerte (mac, oui, ssid ou regex, une par ligne)"),
+    (true, "  --alert-log <fichier> Ajoute les alertes en JSON à la fin d'un fichier"),
+    (cfg!(feature = "webhook"), "  --webhook <url>       Notifie une URL par POST : alertes, état de la carte, résumés"),
+    (cfg!(feature = "webhook"), "  --outbox <fichier>    Conserve les notifications non envoyées entre deux lancements"),
+    (cfg!(feature = "mqtt"), "  --mqtt <hôte[:port]>  Publie les observations sur un broker MQTT"),
+    (cfg!(feature = "mqtt"), "  --mqtt-qos <0|1|2>    QoS des messages MQTT (défaut : 0)"),
+    (cfg!(feature = "mqtt"), "  --mqtt-retain         Retient les messages MQTT des observations"),
+];
 
-    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let newline = src.as_ref().iter().position(|b| *b == b'\n');
-        if let Some(n) = newline {
-            let line = src.split_to(n + 1);
-            return match str::from_utf8(line.as_ref()) {
-                Ok(s) => Ok(Some(s.to_string())),
-                Err(_) => Err(io::Error::new(io::ErrorKind::Other, "Invalid String")),
-            };
-        }
-        Ok(None)
-    }
+// Aide de la ligne de commande, sans les options des features absentes
+fn usage() -> String {
+    let modes = if cfg!(feature = "http") {
+        "[tui|http|aps]"
+    } else {
+        "[tui|aps]"
+    };
+    let options: Vec<&str> = OPTIONS
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, option)| *option)
+        .collect();
+    format!(
+        "Utilisation : wifisnipe-rs {modes} [options] [port]\n\nOptions :\n{}",
+        options.join("\n")
+    )
 }
 
-impl Encoder<String> for LineCodec {
-    type Error = io::Error;
-
-    fn encode(&mut self, _item: String, _dst: &mut BytesMut) -> Result<(), Self::Error> {
-        Ok(())
-    }
+// Mode de fonctionnement du programme
+enum Mode {
+    // Affichage du tableau toutes les 30 secondes
+    Print,
+    // Tableau de bord interactif
+    Tui,
+    // Affichage des points d'accès toutes les 30 secondes
+    Aps,
+    // API HTTP
+    #[cfg(feature = "http")]
+    Http,
 }
 
-#[tokio::main]
-async fn main() -> tokio_serial::Result<()> {
-    // Récupère le port demandé ou fallback sur le défaut
-    let mut args = std::env::args();
-    let tty_path = args.nth(1).unwrap_or_else(|| DEFAULT_TTY.into());
-    // FIFO queue
-    let data_queue = HeapRb::<String>::new(255);
-    // Recuperer Producteur et Consommateur
-    let (mut data_queue_tx, data_queue_rx) = data_queue.split();
-    // Envoi du consommateur dans le thread pour le traitement
-    thread::spawn(move || {
-        parse_str(data_queue_rx);
-    });
-    // Ouvre le port serie
-    let port = tokio_serial::new(tty_path, 115_200).open_native_async()?;
-    // Déclare le lecteur
-    let mut reader = LineCodec.framed(port);
-    while let Some(line_result) = reader.next().await {
-        let line = line_result.expect("Failed to read line");
-        // Si la ligne n'est pas mauvaise, la push sur le FIFO
-        data_queue_tx.push(line).unwrap();
-    }
-    Ok(())
+// Arguments de la ligne de commande
+struct Args {
+    mode: Mode,
+    // Fichier de configuration complété par les options
+    config: Config,
 }
 
-// Silence un warning sur la complexité de type, nécessaire ici
-#[allow(clippy::type_complexity)]
-fn parse_str(mut data_queue_rx: Consumer<String, Arc<SharedRb<String, Vec<MaybeUninit<String>>>>>) {
-    loop {
-        while data_queue_rx.is_empty() {
-            // Ne rien faire si la queue est vide
+fn main() -> io::Result<()> {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{}", usage());
+            std::process::exit(2);
         }
-        lazy_static! {
-            static ref MAC_REGEX: Regex =
-                Regex::new(r"^([0-9A-Fa-f]{2}[:-]){5}([0-9A-Fa-f]{2})$").unwrap();
+    };
+    let config = &args.config;
+    // Capteur, confidentialité (avant les premières trames), conservation, alertes et sorties
+    config
+        .apply()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    if !config.alerts.rules.is_empty() || config.alerts.watchlist.is_some() {
+        println!(
+            "{} règle(s) de surveillance chargée(s)",
+            watchlist::rules().len()
+        );
+    }
+    // Le tableau de bord occupe le terminal
+    if !matches!(args.mode, Mode::Tui) {
+        watchlist::add_route(AlertRoute::Callback(Arc::new(|alert| {
+            println!(
+                "ALERTE {} | {} ({}) | {} | {}",
+                alert.rule, alert.mac, alert.vendor, alert.ssid, alert.rssi
+            )
+        })));
+    }
+    // Démarre l'enregistrement dans la librairie, les commandes sont envoyées par la librairie
+    let source = match &config.sensor.replay {
+        Some(path) => {
+            start_replay(path.clone());
+            path.clone()
+        }
+        None => {
+            let port = config.sensor.port.clone().unwrap_or(DEFAULT_TTY.into());
+            start_tty(port.clone());
+            port
+        }
+    };
+    match args.mode {
+        Mode::Print => print_loop(&config.display),
+        Mode::Tui => tui::run(&source),
+        Mode::Aps => ap_loop(&config.display),
+        #[cfg(feature = "http")]
+        Mode::Http => {
+            println!(
+                "API HTTP sur http://{}",
+                config.outputs.http.as_deref().unwrap_or(DEFAULT_LISTEN)
+            );
+            // Le programme s'arrête avec le serveur
+            loop {
+                if let Some(err) = libwifisnipe::http::server_error() {
+                    return Err(io::Error::other(err));
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
         }
-        // Recupere un element de la FIFO
-        let frame: String = data_queue_rx.pop().unwrap();
-        // Clean la trame en enlevant tout les caractères spéciaux qui nous
-        let cleaned_frame: String = frame.replace(&['\u{2}', '\u{3}', '\r', '\n'][..], "");
-        // Split au niveau des caracteres de controle
-        let splitted_frame: Vec<_> = cleaned_frame.split('\x1F').collect();
-        // Check suivant si le channel est a un chiffre ou a deux chiffres
-        let channel: u8 = (splitted_frame[0]).parse::<u8>().unwrap_or(0);
-        let mac_address: String = if MAC_REGEX.is_match(splitted_frame[1]) {
-            splitted_frame[1].to_string()
-        } else {
-            // Si l'addresse MAC est invalide
-            "".to_string()
-        };
-        // Récupère le RSSI
-        let rssi: String = splitted_frame[2].to_string();
-        // Vérifie que le SSID n'est pas le RSSI
-        let ssid: String = if splitted_frame[splitted_frame.len() - 2] != rssi {
-            splitted_frame[splitted_frame.len() - 2].to_string()
-        } else {
-            splitted_frame[splitted_frame.len() - 1].to_string()
-        };
-        store(channel, mac_address, rssi, ssid);
     }
 }
 
-fn store(channel: u8, mac_address: String, rssi: String, ssid: String) {
-    // Récupérer le lock sur les hashmaps
-    let mut mac_table = MACS.lock().unwrap();
-    // Ajouter a la liste des Adresses MAC connues si non dupliquées
-    if !mac_table.contains(&mac_address) {
-        mac_table.push(mac_address.clone())
+// Récupère le mode, la configuration et les options, qui la complètent
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let args: Vec<String> = args.collect();
+    // Le fichier de configuration est lu avant les autres options
+    let mut config = match args.iter().position(|arg| arg == "--config") {
+        Some(index) => {
+            let path = args.get(index + 1).ok_or("--config attend un fichier")?;
+            config::load(path).map_err(|err| format!("{path} : {err}"))?
+        }
+        None => Config::default(),
+    };
+    let mut args = args.into_iter().peekable();
+    let mode = match args.peek().map(String::as_str) {
+        Some("tui") => Mode::Tui,
+        Some("aps") => Mode::Aps,
+        #[cfg(feature = "http")]
+        Some("http") => Mode::Http,
+        _ => Mode::Print,
+    };
+    if !matches!(mode, Mode::Print) {
+        args.next();
     }
-
-    // Mets a jour le timestamp
-    let mut timestamp_table = LAST_SEEN.lock().unwrap();
-    timestamp_table
-        .entry(mac_address.clone())
-        .and_modify(|ts_tmp| *ts_tmp = SystemTime::now())
-        .or_insert(SystemTime::now());
-
-    let mut channel_table = CHANNELS.lock().unwrap();
-    // S'assurer de la présence du vecteur dans la table
-    if let Some(tmp_channel) = channel_table.get(&mac_address) {
-        // Déduplication
-        if !tmp_channel.contains(&channel) {
-            channel_table
-                .entry(mac_address.clone())
-                .or_insert_with(Vec::new)
-                .push(channel);
+    #[cfg(feature = "mqtt")]
+    let (mut mqtt_qos, mut mqtt_retain) = (None, false);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                args.next();
+            }
+            "--replay" => {
+                config.sensor.replay = Some(args.next().ok_or("--replay attend un fichier")?);
+            }
+            "--capture" => {
+                let mode = args.next().ok_or("--capture attend probe, ap ou all")?;
+                if CaptureMode::from_name(&mode).is_none() {
+                    return Err(format!("Mode de capture invalide : {mode}"));
+                }
+                config.sensor.capture = Some(mode);
+            }
+            #[cfg(feature = "http")]
+            "--listen" => {
+                config.outputs.http = Some(args.next().ok_or("--listen attend une adresse")?)
+            }
+            #[cfg(feature = "http")]
+            "--metrics" => {
+                config.outputs.metrics = Some(args.next().ok_or("--metrics attend une adresse")?)
+            }
+            "--sensor" => config.sensor.name = args.next().ok_or("--sensor attend un nom")?,
+            "--position" => {
+                let position = args.next().ok_or("--position attend x,y")?;
+                config.sensor.position = Some(
+                    position
+                        .split_once(',')
+                        .and_then(|(x, y)| Some([x.trim().parse().ok()?, y.trim().parse().ok()?]))
+                        .ok_or(format!("Position invalide : {position}"))?,
+                );
+            }
+            "--adaptive-hop" => {
+                let seconds = args
+                    .next()
+                    .ok_or("--adaptive-hop attend une durée en secondes")?;
+                config.sensor.adaptive_hop = seconds
+                    .parse()
+                    .ok()
+                    .filter(|seconds| *seconds > 0)
+                    .ok_or(format!("Période de parcours invalide : {seconds}"))?;
+            }
+            "--privacy" => {
+                config.privacy.get_or_insert_with(PrivacySection::default);
+            }
+            "--privacy-key" => {
+                let key = args.next().ok_or("--privacy-key attend une clé")?;
+                config
+                    .privacy
+                    .get_or_insert_with(PrivacySection::default)
+                    .key = key;
+            }
+            "--daily-salt" => {
+                config
+                    .privacy
+                    .get_or_insert_with(PrivacySection::default)
+                    .daily_salt = true
+            }
+            "--ssid" => {
+                let mode = args
+                    .next()
+                    .ok_or("--ssid attend keep, hash, drop ou un nombre")?;
+                let setting = match mode.parse() {
+                    Ok(len) => SsidSetting::Length(len),
+                    Err(_) => SsidSetting::Mode(mode.clone()),
+                };
+                if setting.policy().is_none() {
+                    return Err(format!("Mode SSID invalide : {mode}"));
+                }
+                config
+                    .privacy
+                    .get_or_insert_with(PrivacySection::default)
+                    .ssid = setting;
+            }
+            "--retention" => {
+                let seconds = args
+                    .next()
+                    .ok_or("--retention attend une durée en secondes")?;
+                config.retention.max_age = seconds
+                    .parse()
+                    .ok()
+                    .filter(|seconds| *seconds > 0)
+                    .ok_or(format!("Durée de conservation invalide : {seconds}"))?;
+            }
+            "--audit-log" => {
+                config.retention.audit_log =
+                    Some(args.next().ok_or("--audit-log attend un fichier")?.into())
+            }
+            "--audit-key" => {
+                config.retention.audit_key = Some(args.next().ok_or("--audit-key attend une clé")?)
+            }
+            "--watchlist" => {
+                config.alerts.watchlist = Some(args.next().ok_or("--watchlist attend un fichier")?)
+            }
+            "--alert-log" => {
+                config.outputs.alert_log =
+                    Some(args.next().ok_or("--alert-log attend un fichier")?.into())
+            }
+            #[cfg(feature = "webhook")]
+            "--webhook" => config
+                .outputs
+                .webhook
+                .get_or_insert_with(WebhookSection::default)
+                .urls
+                .push(args.next().ok_or("--webhook attend une URL")?),
+            #[cfg(feature = "webhook")]
+            "--outbox" => {
+                config
+                    .outputs
+                    .webhook
+                    .get_or_insert_with(WebhookSection::default)
+                    .outbox = Some(args.next().ok_or("--outbox attend un fichier")?.into())
+            }
+            #[cfg(feature = "mqtt")]
+            "--mqtt" => {
+                let broker = args.next().ok_or("--mqtt attend une adresse")?;
+                let mut mqtt = MqttSection::default();
+                match broker.rsplit_once(':') {
+                    Some((host, port)) => {
+                        mqtt.host = host.to_owned();
+                        mqtt.port = port
+                            .parse()
+                            .map_err(|_| format!("Port MQTT invalide : {port}"))?;
+                    }
+                    None => mqtt.host = broker,
+                }
+                config.outputs.mqtt = Some(mqtt);
+            }
+            #[cfg(feature = "mqtt")]
+            "--mqtt-qos" => {
+                let qos = args.next().ok_or("--mqtt-qos attend 0, 1 ou 2")?;
+                mqtt_qos = Some(
+                    qos.parse()
+                        .map_err(|_| format!("QoS MQTT invalide : {qos}"))?,
+                );
+            }
+            #[cfg(feature = "mqtt")]
+            "--mqtt-retain" => mqtt_retain = true,
+            _ if arg.starts_with("--") => return Err(format!("Option inconnue : {arg}")),
+            _ => {
+                config.sensor.port = Some(arg);
+                config.sensor.replay = None;
+            }
         }
-    } else {
-        // Le créer si pas présent
-        channel_table
-            .entry(mac_address.clone())
-            .or_insert_with(Vec::new)
-            .push(channel);
     }
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = &mut config.outputs.mqtt {
+        mqtt.qos = mqtt_qos.unwrap_or(mqtt.qos);
+        mqtt.retain |= mqtt_retain;
+    }
+    match mode {
+        // Le mode aps a besoin des beacons
+        Mode::Aps if config.sensor.capture.is_none() => {
+            config.sensor.capture = Some(String::from("all"))
+        }
+        #[cfg(feature = "http")]
+        Mode::Http if config.outputs.http.is_none() => {
+            config.outputs.http = Some(DEFAULT_LISTEN.into())
+        }
+        _ => {}
+    }
+    config.validate().map_err(|err| err.to_string())?;
+    Ok(Args { mode, config })
+}
 
-    // Ne rien faire si le SSID est vide
-    if !ssid.is_empty() {
-        // Récupérer le lock sur la table des SSIDs
-        let mut ssid_table = SSIDS.lock().unwrap();
-        // S'assurer de la présence du vecteur dans la table
-        if let Some(tmp_ssid) = ssid_table.get(&mac_address) {
-            // Déduplication
-            if !tmp_ssid.contains(&ssid) {
-                ssid_table
-                    .entry(mac_address.clone())
-                    .or_insert_with(Vec::new)
-                    .push(ssid);
-            }
+// Affiche régulièrement le tableau des appareils vus récemment
+fn print_loop(display: &DisplaySection) -> io::Result<()> {
+    loop {
+        thread::sleep(Duration::from_secs(display.interval));
+        // Arrête le programme si le port série n'a pas pu être lu
+        if let Some(err) = serial_error() {
+            return Err(io::Error::other(err));
         }
-        // Le créer si pas présent
-        else {
-            ssid_table
-                .entry(mac_address.clone())
-                .or_insert_with(Vec::new)
-                .push(ssid);
+        println!(
+            "---------- {} ----------",
+            Local::now().format("%Y-%m-%d][%H:%M:%S")
+        );
+        match sensor::status() {
+            Some(status) => println!(
+                "Sensor {} | Uptime : {}s | Channel : {} | Heap : {} | Frames sent : {} | Frames lost : {} | Status age : {}s{}",
+                status.version,
+                status.uptime,
+                status.channel,
+                status.heap,
+                status.frames,
+                clock::frames_lost(),
+                status.age(),
+                if status.is_stale() { " | NO RESPONSE" } else { "" }
+            ),
+            None => println!("Sensor | No status received"),
+        }
+        for data in snapshot() {
+            // Convertis le timestamp dans un format qui permets l'affichage
+            let seen_ts: DateTime<Local> = match Local.timestamp_opt(data.ts, 0).single() {
+                Some(ts) => ts,
+                None => continue,
+            };
+            let diff = Local::now() - seen_ts;
+            if diff.num_seconds() >= display.max_age {
+                continue;
+            }
+            println!(
+                "{} | Last seen : {} | {:?} | {:?} | {:?}",
+                data.mac,
+                seen_ts.format("%Y-%m-%d -- %H:%M:%S"),
+                data.channels,
+                data.ssids,
+                data.rssi
+            )
         }
     }
-    // RSSI
-    let mut rssi_table = RSSIS.lock().unwrap();
-    // Ajout du dernier RSSI vu
-    rssi_table
-        .entry(mac_address)
-        .and_modify(|rssi_tmp| *rssi_tmp = (rssi).parse::<i16>().unwrap_or(-50))
-        .or_insert((rssi).parse::<i16>().unwrap_or(-50));
+}
 
-    // S'assurer que la clock n'a pas skew en arrière
-    if let Ok(dur) = LAST_PRINT.elapsed() {
-        // Augmenter la condition suivant le nombre de print pour contrecarrer la non mutabilité de SystemTime
-        if dur.as_secs() > PRINT_COUNT.load(Ordering::SeqCst) * 30 {
-            let seen_macs = Arc::clone(&MACS);
-            let seen_ssids = Arc::clone(&SSIDS);
-            let seen_channels = Arc::clone(&CHANNELS);
-            let seen_rssi = Arc::clone(&RSSIS);
-            let last_seen = Arc::clone(&LAST_SEEN);
-            thread::spawn(move || {
-                let seen_macs = seen_macs.lock().unwrap().clone();
-                let seen_ssids = seen_ssids.lock().unwrap().clone();
-                let seen_channels = seen_channels.lock().unwrap().clone();
-                let seen_rssi = seen_rssi.lock().unwrap().clone();
-                let last_seen = last_seen.lock().unwrap().clone();
-                println!(
-                    "---------- {} ----------",
-                    Local::now().format("%Y-%m-%d][%H:%M:%S")
-                );
-                for mac in seen_macs.into_iter() {
-                    // Convertis le timestamp dans un format qui permets l'affichage
-                    let seen_ts: DateTime<Local> = (*last_seen.get(&mac.clone()).unwrap()).into();
-                    let diff = Local::now() - seen_ts;
-                    if diff.num_minutes() >= 30 {
-                        continue;
-                    }
-                    println!(
-                        "{mac} | Last seen : {} | {:?} | {:?} | {:?}",
-                        seen_ts.format("%Y-%m-%d -- %H:%M:%S"),
-                        seen_channels.get(&mac.clone()).unwrap(),
-                        seen_ssids.get(&mac.clone()),
-                        seen_rssi.get(&mac.clone()).unwrap()
-                    )
-                }
-            });
-            // Ajoute un au PRINT_COUNT
-            PRINT_COUNT.fetch_add(1, Ordering::SeqCst);
+// Affiche régulièrement le tableau des points d'accès vus récemment
+fn ap_loop(display: &DisplaySection) -> io::Result<()> {
+    loop {
+        thread::sleep(Duration::from_secs(display.interval));
+        // Arrête le programme si le port série n'a pas pu être lu
+        if let Some(err) = serial_error() {
+            return Err(io::Error::other(err));
+        }
+        println!(
+            "---------- {} ----------",
+            Local::now().format("%Y-%m-%d][%H:%M:%S")
+        );
+        for ap in ap::snapshot() {
+            let seen_ts: DateTime<Local> = match Local.timestamp_opt(ap.ts, 0).single() {
+                Some(ts) => ts,
+                None => continue,
+            };
+            if (Local::now() - seen_ts).num_seconds() >= display.max_age {
+                continue;
+            }
+            println!(
+                "{} | Last seen : {} | Channel : {} | RSSI : {} | {} | {:?}",
+                ap.bssid,
+                seen_ts.format("%Y-%m-%d -- %H:%M:%S"),
+                ap.channel,
+                ap.rssi,
+                ap.security,
+                ap.ssid
+            )
         }
     }
 }
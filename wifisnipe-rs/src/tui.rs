@@ -0,0 +1,362 @@
+//! Tableau de bord interactif dans le terminal
+//!
+//! | Touche | Action |
+//! | ------ | ------ |
+//! | `q` / `Echap` | Quitter |
+//! | `s` | Changer la colonne de tri |
+//! | `r` | Inverser le tri |
+//! | `/` | Filtrer par MAC ou SSID (`Entrée` pour valider, `Echap` pour effacer) |
+//! | `↑` / `↓` | Sélectionner un appareil |
+
+use chrono::Utc;
+use std::cmp::Ordering;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use libwifisnipe::{
+    channel_activity, clock, frame_count, is_started, oui, sensor, serial_error, snapshot, Data,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{BarChart, Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+// Délai maximal d'attente d'une touche avant de redessiner
+const TICK: Duration = Duration::from_millis(250);
+// Période de calcul du débit de trames
+const RATE_PERIOD: Duration = Duration::from_secs(1);
+// Bornes de la barre de RSSI (dBm)
+const RSSI_MIN: i32 = -100;
+const RSSI_MAX: i32 = -30;
+// Largeur de la barre de RSSI
+const RSSI_BAR_WIDTH: i32 = 10;
+
+// Colonne utilisée pour le tri du tableau
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Mac,
+    Vendor,
+    Rssi,
+    Channels,
+    Ssids,
+    LastSeen,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Mac => SortKey::Vendor,
+            SortKey::Vendor => SortKey::Rssi,
+            SortKey::Rssi => SortKey::Channels,
+            SortKey::Channels => SortKey::Ssids,
+            SortKey::Ssids => SortKey::LastSeen,
+            SortKey::LastSeen => SortKey::Mac,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Mac => "MAC",
+            SortKey::Vendor => "Constructeur",
+            SortKey::Rssi => "RSSI",
+            SortKey::Channels => "Canaux",
+            SortKey::Ssids => "SSIDs",
+            SortKey::LastSeen => "Vu",
+        }
+    }
+
+    fn compare(self, a: &Data, b: &Data) -> Ordering {
+        match self {
+            SortKey::Mac => a.mac.cmp(&b.mac),
+            SortKey::Vendor => oui::vendor(&a.mac).cmp(oui::vendor(&b.mac)),
+            // Le plus fort en premier
+            SortKey::Rssi => b.rssi.cmp(&a.rssi),
+            SortKey::Channels => b.channels.len().cmp(&a.channels.len()),
+            SortKey::Ssids => ssid_count(b).cmp(&ssid_count(a)),
+            // Le plus récent en premier
+            SortKey::LastSeen => b.ts.cmp(&a.ts),
+        }
+    }
+}
+
+// État du tableau de bord
+struct App {
+    port: String,
+    sort: SortKey,
+    reverse: bool,
+    filter: String,
+    editing: bool,
+    table: TableState,
+    rate: f64,
+    rate_count: u64,
+    rate_instant: Instant,
+}
+
+/// Lance le tableau de bord jusqu'à ce que l'utilisateur quitte
+pub fn run(port: &str) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(port).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(port: &str) -> Self {
+        App {
+            port: port.to_owned(),
+            sort: SortKey::LastSeen,
+            reverse: false,
+            filter: String::new(),
+            editing: false,
+            table: TableState::default(),
+            rate: 0.0,
+            rate_count: frame_count(),
+            rate_instant: Instant::now(),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            self.update_rate();
+            let devices = self.devices();
+            terminal.draw(|frame| self.draw(frame, &devices))?;
+            if !event::poll(TICK)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if self.editing {
+                    match key.code {
+                        KeyCode::Enter => self.editing = false,
+                        KeyCode::Esc => {
+                            self.editing = false;
+                            self.filter.clear();
+                        }
+                        KeyCode::Backspace => {
+                            self.filter.pop();
+                        }
+                        KeyCode::Char(c) => self.filter.push(c),
+                        _ => {}
+                    }
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('s') => self.sort = self.sort.next(),
+                    KeyCode::Char('r') => self.reverse = !self.reverse,
+                    KeyCode::Char('/') => self.editing = true,
+                    KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Mets à jour le débit de trames par seconde
+    fn update_rate(&mut self) {
+        let elapsed = self.rate_instant.elapsed();
+        if elapsed >= RATE_PERIOD {
+            let count = frame_count();
+            self.rate = (count - self.rate_count) as f64 / elapsed.as_secs_f64();
+            self.rate_count = count;
+            self.rate_instant = Instant::now();
+        }
+    }
+
+    // Appareils filtrés et triés
+    fn devices(&self) -> Vec<Data> {
+        let filter = self.filter.to_lowercase();
+        let mut devices: Vec<Data> = snapshot()
+            .into_iter()
+            .filter(|data| {
+                filter.is_empty()
+                    || data.mac.to_lowercase().contains(&filter)
+                    || data
+                        .ssids
+                        .iter()
+                        .any(|ssid| ssid.to_lowercase().contains(&filter))
+            })
+            .collect();
+        devices.sort_by(|a, b| {
+            let ord = self.sort.compare(a, b);
+            if self.reverse {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        devices
+    }
+
+    fn draw(&mut self, frame: &mut Frame, devices: &[Data]) {
+        let [header, table, histogram, footer] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let status = match serial_error() {
+            Some(err) => format!("erreur : {err}"),
+            None if is_started() => String::from("connecté"),
+            None => String::from("déconnecté"),
+        };
+        // État envoyé par la carte
+        let sensor = match sensor::status() {
+            Some(status) => Line::from(format!(
+                " carte {} | canal {} | démarrée depuis {} | {} octets libres | {} trames envoyées | {} perdues | état reçu il y a {}",
+                status.version,
+                status.channel,
+                elapsed(status.uptime as i64),
+                status.heap,
+                status.frames,
+                clock::frames_lost(),
+                elapsed(status.age()),
+            ))
+            .style(if status.is_stale() {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            }),
+            None => Line::from(" carte : aucun état reçu"),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!(
+                    " {} ({status}) | {:.1} trames/s | {} trames | {} appareils | tri : {}{}",
+                    self.port,
+                    self.rate,
+                    frame_count(),
+                    devices.len(),
+                    self.sort.label(),
+                    if self.reverse { " (inversé)" } else { "" },
+                )),
+                sensor,
+            ])
+            .style(Style::default().add_modifier(Modifier::REVERSED)),
+            header,
+        );
+
+        let now = Utc::now().timestamp();
+        let rows = devices.iter().map(|data| {
+            Row::new(vec![
+                data.mac.clone(),
+                oui::vendor(&data.mac).to_owned(),
+                rssi_bar(data.rssi),
+                format!("{:.1} m", data.distance),
+                data.channels
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                data.ssids
+                    .iter()
+                    .filter(|ssid| !ssid.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                elapsed(now - data.ts),
+            ])
+        });
+        let widths = [
+            Constraint::Length(17),
+            Constraint::Length(14),
+            Constraint::Length(RSSI_BAR_WIDTH as u16 + 6),
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Fill(1),
+            Constraint::Length(7),
+        ];
+        frame.render_stateful_widget(
+            Table::new(rows, widths)
+                .header(
+                    Row::new(vec![
+                        "MAC",
+                        "Constructeur",
+                        "RSSI",
+                        "Distance",
+                        "Canaux",
+                        "SSIDs",
+                        "Vu",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .row_highlight_style(Style::default().fg(Color::Yellow))
+                .block(Block::default().borders(Borders::ALL).title(" Appareils ")),
+            table,
+            &mut self.table,
+        );
+
+        // Canaux 1 à 13 toujours affichés, plus les éventuels autres canaux vus
+        let activity = channel_activity();
+        let mut channels: Vec<u32> = (1..=13).collect();
+        channels.extend(
+            activity
+                .keys()
+                .filter(|channel| !(1..=13).contains(*channel)),
+        );
+        channels.sort_unstable();
+        let labels: Vec<String> = channels.iter().map(u32::to_string).collect();
+        let bars: Vec<(&str, u64)> = labels
+            .iter()
+            .zip(channels.iter())
+            .map(|(label, channel)| (label.as_str(), *activity.get(channel).unwrap_or(&0)))
+            .collect();
+        frame.render_widget(
+            BarChart::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Trames par canal "),
+                )
+                .bar_width(4)
+                .bar_gap(1)
+                .bar_style(Style::default().fg(Color::Cyan))
+                .data(&bars),
+            histogram,
+        );
+
+        let help = if self.editing {
+            format!(
+                " Filtre : {}_ (Entrée : valider, Echap : effacer)",
+                self.filter
+            )
+        } else if self.filter.is_empty() {
+            String::from(" q : quitter | s : trier | r : inverser | / : filtrer | ↑↓ : sélection")
+        } else {
+            format!(" Filtre : {} | / : modifier | q : quitter", self.filter)
+        };
+        frame.render_widget(Paragraph::new(Line::from(help)), footer);
+    }
+}
+
+// Nombre de SSIDs non vides
+fn ssid_count(data: &Data) -> usize {
+    data.ssids.iter().filter(|ssid| !ssid.is_empty()).count()
+}
+
+// Barre proportionnelle au RSSI suivie de sa valeur
+fn rssi_bar(rssi: i32) -> String {
+    let filled =
+        ((rssi.clamp(RSSI_MIN, RSSI_MAX) - RSSI_MIN) * RSSI_BAR_WIDTH) / (RSSI_MAX - RSSI_MIN);
+    format!(
+        "{}{} {rssi}",
+        "█".repeat(filled as usize),
+        "░".repeat((RSSI_BAR_WIDTH - filled) as usize)
+    )
+}
+
+// Durée écoulée lisible
+fn elapsed(secs: i64) -> String {
+    match secs.max(0) {
+        s if s < 60 => format!("{s} s"),
+        s if s < 3600 => format!("{} min", s / 60),
+        s => format!("{} h", s / 3600),
+    }
+}